@@ -0,0 +1,85 @@
+// Range and histogram of a per-cell field, read back to set the colour scale.
+//
+// Floats are mapped to unsigned integers that sort in the same order so that the range can be
+// reduced with integer atomics. `range` must run over every cell before `histogram`.
+
+const HISTOGRAM_BINS: u32 = 256u;
+
+struct Statistics {
+    // Index of the field in `CellField::ALL`, written from the CPU.
+    field: u32,
+    min: atomic<u32>,
+    max: atomic<u32>,
+    histogram: array<atomic<u32>, HISTOGRAM_BINS>,
+}
+
+@group(0) @binding(0)
+var<storage, read> values: array<f32>;
+
+@group(0) @binding(1)
+var<storage, read_write> statistics: Statistics;
+
+var<workgroup> workgroup_min: atomic<u32>;
+var<workgroup> workgroup_max: atomic<u32>;
+
+fn is_finite(value: f32) -> bool {
+    return (bitcast<u32>(value) & 0x7f800000u) != 0x7f800000u;
+}
+
+fn to_ordered(value: f32) -> u32 {
+    let bits = bitcast<u32>(value);
+    if (bits & 0x80000000u) != 0u {
+        return ~bits;
+    }
+    return bits | 0x80000000u;
+}
+
+fn from_ordered(ordered: u32) -> f32 {
+    if (ordered & 0x80000000u) != 0u {
+        return bitcast<f32>(ordered & 0x7fffffffu);
+    }
+    return bitcast<f32>(~ordered);
+}
+
+@compute @workgroup_size(64)
+fn range(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if local_index == 0u {
+        atomicStore(&workgroup_min, 0xffffffffu);
+        atomicStore(&workgroup_max, 0u);
+    }
+    workgroupBarrier();
+
+    let cell = global_id.x;
+    if cell < arrayLength(&values) && is_finite(values[cell]) {
+        let ordered = to_ordered(values[cell]);
+        atomicMin(&workgroup_min, ordered);
+        atomicMax(&workgroup_max, ordered);
+    }
+    workgroupBarrier();
+
+    // One global atomic per workgroup instead of one per cell.
+    if local_index == 0u {
+        atomicMin(&statistics.min, atomicLoad(&workgroup_min));
+        atomicMax(&statistics.max, atomicLoad(&workgroup_max));
+    }
+}
+
+@compute @workgroup_size(64)
+fn histogram(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let cell = global_id.x;
+    if cell >= arrayLength(&values) || !is_finite(values[cell]) {
+        return;
+    }
+
+    let min = from_ordered(atomicLoad(&statistics.min));
+    let max = from_ordered(atomicLoad(&statistics.max));
+    let span = max - min;
+    var bin = 0u;
+    if span > 0.0 {
+        bin = min(u32((values[cell] - min) / span * f32(HISTOGRAM_BINS)), HISTOGRAM_BINS - 1u);
+    }
+    atomicAdd(&statistics.histogram[bin], 1u);
+}
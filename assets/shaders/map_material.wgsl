@@ -0,0 +1,85 @@
+#import bevy_pbr::mesh_functions::get_world_from_local
+#import bevy_pbr::mesh_view_bindings::view
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0)
+var<storage, read> vertex_values: array<f32>;
+
+struct ColorScale {
+    min: f32,
+    max: f32,
+    // Number of colours of a categorical colormap, or 0 for a continuous one.
+    categories: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(1)
+var<uniform> color_scale: ColorScale;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(2)
+var colormap: texture_1d<f32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(3)
+var colormap_sampler: sampler;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(4)
+var<storage, read> cell_values: array<f32>;
+
+// The map has vertices of its own, with triangles crossing the antimeridian drawn twice; these
+// map them back to the grid vertices and cells the field buffers are indexed by.
+@group(#{MATERIAL_BIND_GROUP}) @binding(5)
+var<storage, read> map_vertices: array<u32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(6)
+var<storage, read> map_cells: array<u32>;
+
+// 1 to colour each cell by its own value, 0 to interpolate the values at its corners.
+@group(#{MATERIAL_BIND_GROUP}) @binding(7)
+var<uniform> flat_shading: u32;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) value: f32,
+    @location(1) @interpolate(flat) cell_value: f32,
+}
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = get_world_from_local(in.instance_index);
+    out.position = view.clip_from_world * (world_from_local * vec4(in.position, 1.0));
+    out.value = vertex_values[map_vertices[in.vertex_index]];
+    out.cell_value = cell_values[map_cells[in.vertex_index]];
+
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var value = in.value;
+    if flat_shading != 0u {
+        value = in.cell_value;
+    }
+    return vec4(colormap_color(value), 1.0);
+}
+
+// Matches `colormap_color` in `pressure_material.wgsl`.
+fn colormap_color(value: f32) -> vec3<f32> {
+    var u: f32;
+    if color_scale.categories > 0u {
+        let category = u32(max(round(value), 0.0)) % color_scale.categories;
+        u = (f32(category) + 0.5) / f32(color_scale.categories);
+    } else {
+        let span = max(color_scale.max - color_scale.min, 1e-6);
+        let t = clamp((value - color_scale.min) / span, 0.0, 1.0);
+        // Map the range onto the first and last texel centres rather than the texture edges.
+        let texels = f32(textureDimensions(colormap));
+        u = (t * (texels - 1.0) + 0.5) / texels;
+    }
+    return textureSample(colormap, colormap_sampler, u).rgb;
+}
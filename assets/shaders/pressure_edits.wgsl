@@ -0,0 +1,21 @@
+@group(0) @binding(0)
+var<storage, read_write> pressure: array<f32>;
+
+@group(0) @binding(1)
+var<storage, read> cells: array<u32>;
+
+@group(0) @binding(2)
+var<storage, read> deltas: array<f32>;
+
+// Adds each painted change to the pressure the solver reads next. A cell is painted at most once
+// per frame, so no two invocations touch the same value.
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x;
+    if idx >= arrayLength(&cells) {
+        return;
+    }
+
+    let cell = cells[idx];
+    pressure[cell] = pressure[cell] + deltas[idx];
+}
@@ -2,7 +2,49 @@
 #import bevy_pbr::mesh_view_bindings::view
 
 @group(#{MATERIAL_BIND_GROUP}) @binding(0)
-var<storage, read> vertex_pressure: array<f32>;
+var<storage, read> vertex_values: array<f32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(1)
+var<storage, read> vertex_elevation: array<f32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(2)
+var<uniform> elevation_exaggeration: f32;
+
+struct ColorScale {
+    min: f32,
+    max: f32,
+    // Number of colours of a categorical colormap, or 0 for a continuous one.
+    categories: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(3)
+var<uniform> color_scale: ColorScale;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(4)
+var colormap: texture_1d<f32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(5)
+var colormap_sampler: sampler;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(6)
+var<storage, read> cell_values: array<f32>;
+
+// The mesh has three vertices of its own per cell, in cell order; this maps them back to the
+// grid vertices that `vertex_values` and `vertex_elevation` are indexed by.
+@group(#{MATERIAL_BIND_GROUP}) @binding(7)
+var<storage, read> mesh_vertices: array<u32>;
+
+// 1 to colour each cell by its own value, 0 to interpolate the values at its corners.
+@group(#{MATERIAL_BIND_GROUP}) @binding(8)
+var<uniform> flat_shading: u32;
+
+// World-space position of the point light shading the planet.
+@group(#{MATERIAL_BIND_GROUP}) @binding(9)
+var<uniform> light_position: vec3<f32>;
+
+// Elevations are in metres on an Earth-sized planet, the mesh has unit radius.
+const PLANET_RADIUS: f32 = 6371000.0;
+const AMBIENT: f32 = 0.25;
 
 struct Vertex {
     @builtin(instance_index) instance_index: u32,
@@ -13,36 +55,63 @@ struct Vertex {
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
-    @location(0) pressure: f32,
+    @location(0) value: f32,
+    @location(1) world_position: vec3<f32>,
+    @location(2) @interpolate(flat) cell_value: f32,
 }
 
 @vertex
 fn vertex(in: Vertex) -> VertexOutput {
     var out: VertexOutput;
 
+    let grid_vertex = mesh_vertices[in.vertex_index];
+    let elevation = vertex_elevation[grid_vertex];
+    let radius = 1.0 + elevation * elevation_exaggeration / PLANET_RADIUS;
+    let displaced = normalize(in.position) * radius;
+
     let world_from_local = get_world_from_local(in.instance_index);
-    let world_position = world_from_local * vec4(in.position, 1.0);
+    let world_position = world_from_local * vec4(displaced, 1.0);
 
     out.position = view.clip_from_world * world_position;
-    out.pressure = vertex_pressure[in.vertex_index];
+    out.value = vertex_values[grid_vertex];
+    out.cell_value = cell_values[in.vertex_index / 3u];
+    out.world_position = world_position.xyz;
 
     return out;
 }
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    let pressure = in.pressure;
-    let normalized = clamp(pressure / 8800.0, 0.0, 1.0);
+    var value = in.value;
+    if flat_shading != 0u {
+        value = in.cell_value;
+    }
+    let color = colormap_color(value);
 
-    var color: vec3<f32>;
+    // The displaced surface no longer matches the radial mesh normals, so derive the normal from
+    // the screen-space derivatives of the displaced position instead.
+    var normal = normalize(cross(dpdx(in.world_position), dpdy(in.world_position)));
+    if dot(normal, in.world_position) < 0.0 {
+        normal = -normal;
+    }
+    let light_direction = normalize(light_position - in.world_position);
+    let diffuse = max(dot(normal, light_direction), 0.0);
+    let shade = AMBIENT + (1.0 - AMBIENT) * diffuse;
+
+    return vec4(color * shade, 1.0);
+}
 
-    if normalized < 0.5 {
-        let t = normalized * 2.0;
-        color = mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, 0.0), t);
+fn colormap_color(value: f32) -> vec3<f32> {
+    var u: f32;
+    if color_scale.categories > 0u {
+        let category = u32(max(round(value), 0.0)) % color_scale.categories;
+        u = (f32(category) + 0.5) / f32(color_scale.categories);
     } else {
-        let t = (normalized - 0.5) * 2.0;
-        color = mix(vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), t);
+        let span = max(color_scale.max - color_scale.min, 1e-6);
+        let t = clamp((value - color_scale.min) / span, 0.0, 1.0);
+        // Map the range onto the first and last texel centres rather than the texture edges.
+        let texels = f32(textureDimensions(colormap));
+        u = (t * (texels - 1.0) + 0.5) / texels;
     }
-
-    return vec4(color, 1.0);
+    return textureSample(colormap, colormap_sampler, u).rgb;
 }
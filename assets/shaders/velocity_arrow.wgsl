@@ -0,0 +1,36 @@
+#import bevy_pbr::mesh_functions::{get_tag, get_world_from_local}
+#import bevy_pbr::mesh_view_bindings::view
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0)
+var colormap: texture_1d<f32>;
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(1)
+var colormap_sampler: sampler;
+
+// Matches `ARROW_TAG_SCALE` in `velocity_arrow_material.rs`.
+const ARROW_TAG_SCALE: f32 = 65535.0;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) speed: f32,
+}
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = get_world_from_local(in.instance_index);
+    out.position = view.clip_from_world * (world_from_local * vec4(in.position, 1.0));
+    // The arrow's speed relative to the fastest one, packed into its mesh tag.
+    out.speed = f32(get_tag(in.instance_index)) / ARROW_TAG_SCALE;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(colormap, colormap_sampler, in.speed, 0.0);
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser, error::ErrorKind};
+
+use crate::{
+    materials::colormap::Colormap,
+    resources::{
+        field_display::{FieldDisplay, Shading, ValueRange},
+        mantle_grid::CellField,
+        simulation_config::{ConfigError, SimulationConfig},
+    },
+    simulation::scenario::ScenarioPreset,
+};
+
+#[derive(Parser, Debug)]
+#[command(about = "Simulates mantle convection and plate tectonics on an icosphere")]
+pub struct Cli {
+    /// TOML file to read the simulation configuration from.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Scenario preset to start from.
+    #[arg(long, value_enum)]
+    pub scenario: Option<ScenarioPreset>,
+    /// Seed of the scenario generator.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Run the simulation without opening a window.
+    #[arg(long)]
+    pub headless: bool,
+    /// Number of steps after which a headless run stops.
+    #[arg(long)]
+    pub steps: Option<u64>,
+    /// Field to colour the planet, or the vertices of exported meshes, by.
+    #[arg(long, value_enum, default_value = "pressure")]
+    pub field: CellField,
+    /// Colormap to show the field with, instead of the one suiting it best.
+    #[arg(long, value_enum)]
+    pub colormap: Option<Colormap>,
+    /// Shading of the field, instead of the one suiting it best. Toggled with F.
+    #[arg(long, value_enum)]
+    pub shading: Option<Shading>,
+    /// Span the colormap between two percentiles of the field, such as `2 98`, instead of its
+    /// smallest and largest values.
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["LOW", "HIGH"],
+        value_parser = parse_percent,
+        allow_negative_numbers = true
+    )]
+    pub percentiles: Option<Vec<f32>>,
+    /// Keep the colour scale of the first frame instead of following the field. Toggled with L.
+    #[arg(long)]
+    pub lock_range: bool,
+}
+
+impl Cli {
+    /// Parses the command line, exiting with a usage error if the arguments are inconsistent.
+    #[must_use]
+    pub fn parse_and_validate() -> Self {
+        let cli = Self::parse();
+        if let Err(error) = cli.validate() {
+            error.exit();
+        }
+        cli
+    }
+
+    /// Checks the constraints between arguments that clap cannot express.
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        if let Some([low, high]) = self.percentiles.as_deref()
+            && low > high
+        {
+            return Err(Self::command().error(
+                ErrorKind::ValueValidation,
+                format!("--percentiles needs LOW <= HIGH, got {low} and {high}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Loads the configuration file, if any, and applies the command line overrides to it.
+    pub fn simulation_config(&self) -> Result<SimulationConfig, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => SimulationConfig::load(path)?,
+            None => SimulationConfig::default(),
+        };
+        if let Some(preset) = self.scenario {
+            config.scenario.preset = preset;
+        }
+        if let Some(seed) = self.seed {
+            config.scenario.seed = seed;
+        }
+        if let Some(steps) = self.steps {
+            config.steps = steps;
+        }
+        Ok(config)
+    }
+
+    /// Initial field, colormap, shading and colour scale of the windowed viewer, which also
+    /// colour exported meshes.
+    #[must_use]
+    pub fn field_display(&self) -> FieldDisplay {
+        let mut display = FieldDisplay::new(self.field);
+        if let Some(colormap) = self.colormap {
+            display.colormap = colormap;
+        }
+        if let Some(shading) = self.shading {
+            display.shading = shading;
+        }
+        if let Some([low, high]) = self.percentiles.as_deref() {
+            display.range = ValueRange::Percentile {
+                low: *low,
+                high: *high,
+            };
+        }
+        display.locked = self.lock_range;
+        display
+    }
+}
+
+fn parse_percent(value: &str) -> Result<f32, String> {
+    let percent: f32 = value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a number"))?;
+    if (0.0..=100.0).contains(&percent) {
+        Ok(percent)
+    } else {
+        Err(format!("{percent} is not a percentage between 0 and 100"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> Result<Cli, clap::Error> {
+        let cli =
+            Cli::try_parse_from(std::iter::once("simulator").chain(arguments.iter().copied()))?;
+        cli.validate()?;
+        Ok(cli)
+    }
+
+    #[test]
+    fn percentiles_must_be_ordered_percentages() {
+        let cli = parse(&["--percentiles", "2", "98"]).unwrap();
+        assert_eq!(
+            cli.field_display().range,
+            ValueRange::Percentile {
+                low: 2.0,
+                high: 98.0
+            }
+        );
+        assert!(parse(&["--percentiles", "50", "50"]).is_ok());
+
+        for arguments in [["-1", "98"], ["2", "101"], ["2", "NaN"], ["2", "high"]] {
+            let error = parse(&["--percentiles", arguments[0], arguments[1]]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ValueValidation, "{arguments:?}");
+        }
+        let error = parse(&["--percentiles", "98", "2"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        assert!(error.to_string().contains("LOW <= HIGH"));
+    }
+}
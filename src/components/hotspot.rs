@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+/// A mantle plume rising beneath the lithosphere. Hotspots are fixed in the mantle frame, so the
+/// plates drift over them and carry away the volcanic edifices they build.
+#[derive(Component, Debug, Clone)]
+pub struct Hotspot {
+    /// Unit vector to the centre of the plume.
+    pub position: Vec3,
+    /// Angular radius of the plume head, in radians.
+    pub radius: f32,
+    /// Uplift at the centre of the plume, in metres per year.
+    pub uplift_rate: f32,
+    /// Heating of the mantle at the centre of the plume, in kelvin per year.
+    pub heating_rate: f32,
+    /// Unit vector of the axis the hotspot slowly drifts around.
+    pub drift_axis: Vec3,
+    /// Drift rate about `drift_axis`, in radians per year.
+    pub drift_rate: f32,
+}
+
+impl Default for Hotspot {
+    fn default() -> Self {
+        Self {
+            position: Vec3::Y,
+            radius: 0.08,
+            uplift_rate: 5.0e-4,
+            heating_rate: 1.0e-5,
+            drift_axis: Vec3::Y,
+            drift_rate: 0.0,
+        }
+    }
+}
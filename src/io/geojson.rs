@@ -0,0 +1,350 @@
+//! Plate boundaries and coastlines as GeoJSON line strings in longitude and latitude.
+//!
+//! Boundaries follow the triangle edges shared by cells of different plates and are split
+//! wherever the pair of plates or the kind of boundary changes. Coastlines are the sea-level
+//! contour of the vertex-averaged elevation, traced through the triangles by linear
+//! interpolation. Lines crossing the antimeridian are split there so that map views do not draw
+//! them across the whole map.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fs,
+    hash::Hash,
+    io,
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    resources::{
+        mantle_grid::{CellField, MantleGrid},
+        plates::Plates,
+    },
+    simulation::{
+        geography::LatLon,
+        plate_dynamics::{BoundaryKind, PlateDynamicsParameters, boundary_edges},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GeoJsonLayer {
+    PlateBoundaries,
+    Coastlines,
+}
+
+/// Line features to write at the end of a headless run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoJsonExport {
+    pub path: PathBuf,
+    #[serde(default = "default_layers")]
+    pub layers: Vec<GeoJsonLayer>,
+}
+
+fn default_layers() -> Vec<GeoJsonLayer> {
+    vec![GeoJsonLayer::PlateBoundaries, GeoJsonLayer::Coastlines]
+}
+
+impl GeoJsonExport {
+    pub fn write(
+        &self,
+        grid: &MantleGrid,
+        plates: &Plates,
+        parameters: &PlateDynamicsParameters,
+        sea_level: f32,
+    ) -> io::Result<()> {
+        let mut features = Vec::new();
+        for layer in &self.layers {
+            match layer {
+                GeoJsonLayer::PlateBoundaries => {
+                    for boundary in plate_boundaries(grid, plates, parameters) {
+                        features.extend(line_features(
+                            &boundary.points,
+                            json!({
+                                "layer": "plate-boundary",
+                                "plates": boundary.plates,
+                                "kind": boundary_kind_name(boundary.kind),
+                            }),
+                        ));
+                    }
+                }
+                GeoJsonLayer::Coastlines => {
+                    for coastline in coastlines(grid, sea_level) {
+                        features.extend(line_features(&coastline, json!({ "layer": "coastline" })));
+                    }
+                }
+            }
+        }
+
+        let collection = json!({ "type": "FeatureCollection", "features": features });
+        fs::write(&self.path, serde_json::to_vec(&collection)?)
+    }
+}
+
+/// A polyline along the boundary between two plates.
+#[derive(Debug, Clone)]
+pub struct PlateBoundary {
+    /// The two plates, lower index first.
+    pub plates: [usize; 2],
+    pub kind: BoundaryKind,
+    /// Vertices of the polyline on the unit sphere.
+    pub points: Vec<Vec3>,
+}
+
+/// Traces the boundaries between plates along the edges of the cells.
+#[must_use]
+pub fn plate_boundaries(
+    grid: &MantleGrid,
+    plates: &Plates,
+    parameters: &PlateDynamicsParameters,
+) -> Vec<PlateBoundary> {
+    let points = grid.sphere.raw_points();
+    let mut groups = HashMap::<_, Vec<(u32, u32)>>::new();
+    for edge in boundary_edges(grid, plates, parameters) {
+        let vertices = grid.triangle(edge.cell);
+        let mut shared = grid
+            .triangle(edge.neighbor)
+            .into_iter()
+            .filter(|vertex| vertices.contains(vertex));
+        let (Some(a), Some(b)) = (shared.next(), shared.next()) else {
+            continue;
+        };
+        let plate_a = grid.cells[edge.cell].plate;
+        let plate_b = grid.cells[edge.neighbor].plate;
+        // Which side subducts is not needed to draw the line.
+        let kind = match edge.kind {
+            BoundaryKind::Subduction { .. } => BoundaryKind::Subduction { subducting: 0 },
+            kind => kind,
+        };
+        groups
+            .entry(([plate_a.min(plate_b), plate_a.max(plate_b)], kind))
+            .or_default()
+            .push((a, b));
+    }
+
+    let mut boundaries: Vec<PlateBoundary> = groups
+        .into_iter()
+        .flat_map(|((plates, kind), segments)| {
+            trace_polylines(&segments)
+                .into_iter()
+                .map(move |line| PlateBoundary {
+                    plates,
+                    kind,
+                    points: line
+                        .into_iter()
+                        .map(|vertex| Vec3::from(points[vertex as usize]).normalize())
+                        .collect(),
+                })
+        })
+        .collect();
+    // Keep the output stable from run to run despite the hash map.
+    boundaries.sort_by(|a, b| {
+        (a.plates, boundary_kind_name(a.kind))
+            .cmp(&(b.plates, boundary_kind_name(b.kind)))
+            .then_with(|| compare_points(a.points[0], b.points[0]))
+    });
+    boundaries
+}
+
+/// Traces the contour where the vertex-averaged elevation crosses `sea_level`.
+#[must_use]
+pub fn coastlines(grid: &MantleGrid, sea_level: f32) -> Vec<Vec<Vec3>> {
+    let points = grid.sphere.raw_points();
+    let elevations = grid.vertex_values(CellField::Elevation);
+    let is_land = |vertex: u32| elevations[vertex as usize] > sea_level;
+    let edge_key = |a: u32, b: u32| (a.min(b), a.max(b));
+
+    // Each triangle with land and sea corners contributes one segment between the two edges
+    // the contour crosses, identified by the edges' vertex pairs.
+    let mut segments = Vec::new();
+    for cell in 0..grid.cells.len() {
+        let [a, b, c] = grid.triangle(cell);
+        let crossed: Vec<(u32, u32)> = [(a, b), (b, c), (c, a)]
+            .into_iter()
+            .filter(|&(u, v)| is_land(u) != is_land(v))
+            .map(|(u, v)| edge_key(u, v))
+            .collect();
+        if let [first, second] = crossed[..] {
+            segments.push((first, second));
+        }
+    }
+
+    let crossing = |(u, v): (u32, u32)| {
+        let (eu, ev) = (elevations[u as usize], elevations[v as usize]);
+        let t = (sea_level - eu) / (ev - eu);
+        Vec3::from(points[u as usize])
+            .lerp(Vec3::from(points[v as usize]), t)
+            .normalize()
+    };
+    let mut lines: Vec<Vec<Vec3>> = trace_polylines(&segments)
+        .into_iter()
+        .map(|line| line.into_iter().map(crossing).collect())
+        .collect();
+    lines.sort_by(|a, b| compare_points(a[0], b[0]));
+    lines
+}
+
+/// Orders points by their coordinates, totally so that a NaN from a degenerate cell cannot
+/// panic the sort.
+fn compare_points(a: Vec3, b: Vec3) -> Ordering {
+    a.x.total_cmp(&b.x)
+        .then_with(|| a.y.total_cmp(&b.y))
+        .then_with(|| a.z.total_cmp(&b.z))
+}
+
+/// Joins segments sharing endpoints into polylines. Open lines run between endpoints that do not
+/// have exactly two segments; the remaining segments form closed loops, which repeat their first
+/// point at the end.
+fn trace_polylines<K: Copy + Eq + Hash>(segments: &[(K, K)]) -> Vec<Vec<K>> {
+    let mut adjacency: HashMap<K, Vec<usize>> = HashMap::new();
+    for (index, &(a, b)) in segments.iter().enumerate() {
+        adjacency.entry(a).or_default().push(index);
+        adjacency.entry(b).or_default().push(index);
+    }
+
+    let mut used = HashSet::new();
+    let mut lines = Vec::new();
+    let follow = |start: K, first: usize, used: &mut HashSet<usize>| {
+        let mut line = vec![start];
+        let mut current = start;
+        let mut segment = Some(first);
+        while let Some(index) = segment {
+            used.insert(index);
+            let (a, b) = segments[index];
+            current = if a == current { b } else { a };
+            line.push(current);
+            // Only continue through points where the line cannot branch.
+            let next = &adjacency[&current];
+            segment = (next.len() == 2)
+                .then(|| next.iter().copied().find(|s| !used.contains(s)))
+                .flatten();
+        }
+        line
+    };
+
+    let mut starts: Vec<(K, usize)> = adjacency
+        .iter()
+        .filter(|(_, segments)| segments.len() != 2)
+        .flat_map(|(&point, segments)| segments.iter().map(move |&s| (point, s)))
+        .collect();
+    starts.extend((0..segments.len()).map(|s| (segments[s].0, s)));
+    for (start, segment) in starts {
+        if !used.contains(&segment) {
+            lines.push(follow(start, segment, &mut used));
+        }
+    }
+    lines
+}
+
+/// GeoJSON features for a polyline, one per piece between crossings of the antimeridian.
+fn line_features(points: &[Vec3], properties: Value) -> Vec<Value> {
+    split_at_antimeridian(points)
+        .into_iter()
+        .filter(|line| line.len() >= 2)
+        .map(|line| {
+            json!({
+                "type": "Feature",
+                "properties": properties,
+                "geometry": { "type": "LineString", "coordinates": line },
+            })
+        })
+        .collect()
+}
+
+/// Converts a polyline to `[longitude, latitude]` pairs in degrees, breaking it where it crosses
+/// 180° of longitude and ending and resuming the pieces exactly on the antimeridian.
+fn split_at_antimeridian(points: &[Vec3]) -> Vec<Vec<[f64; 2]>> {
+    let mut coordinates: Vec<[f64; 2]> = points.iter().map(|&p| longitude_latitude(p)).collect();
+    // A pole has no longitude of its own; continue the line from or towards its neighbour.
+    for index in 0..coordinates.len() {
+        if coordinates[index][1].abs() > 90.0 - 1.0e-6 {
+            let neighbor = if index > 0 { index - 1 } else { index + 1 };
+            if let Some(&[longitude, _]) = coordinates.get(neighbor) {
+                coordinates[index][0] = longitude;
+            }
+        }
+    }
+
+    let mut lines = vec![Vec::new()];
+    let mut previous: Option<[f64; 2]> = None;
+    for current in coordinates {
+        if let Some(previous) = previous
+            && (current[0] - previous[0]).abs() > 180.0
+        {
+            // Unwrap the current longitude next to the previous one to find the crossing.
+            let edge = 180.0f64.copysign(previous[0]);
+            let unwrapped = current[0] + 360.0f64.copysign(previous[0]);
+            let t = (edge - previous[0]) / (unwrapped - previous[0]);
+            let latitude = previous[1] + t * (current[1] - previous[1]);
+            lines.last_mut().unwrap().push(round([edge, latitude]));
+            lines.push(vec![round([-edge, latitude])]);
+        }
+        lines.last_mut().unwrap().push(round(current));
+        previous = Some(current);
+    }
+    lines
+}
+
+fn longitude_latitude(point: Vec3) -> [f64; 2] {
+    let position = LatLon::from_unit_vector(point);
+    [position.longitude, position.latitude]
+}
+
+/// Rounds to a millionth of a degree, about ten centimetres, to keep the files small.
+fn round(coordinates: [f64; 2]) -> [f64; 2] {
+    coordinates.map(|value| (value * 1.0e6).round() / 1.0e6)
+}
+
+fn boundary_kind_name(kind: BoundaryKind) -> &'static str {
+    match kind {
+        BoundaryKind::Ridge => "ridge",
+        BoundaryKind::Subduction { .. } => "subduction",
+        BoundaryKind::Collision => "collision",
+        BoundaryKind::Transform => "transform",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coastline_of_a_polar_cap_is_one_closed_loop() {
+        let mut grid = MantleGrid::new(8);
+        for cell in &mut grid.cells {
+            cell.elevation = (cell.center.y - 0.3) * 1000.0;
+        }
+
+        let lines = coastlines(&grid, 0.0);
+        assert_eq!(lines.len(), 1);
+        let coastline = &lines[0];
+        assert_eq!(coastline.first(), coastline.last());
+        for point in coastline {
+            assert!((point.y - 0.3).abs() < 0.02, "{point} is off the coast");
+        }
+
+        // Split at the antimeridian, no piece jumps across the map.
+        let pieces = split_at_antimeridian(coastline);
+        assert!(pieces.len() >= 2);
+        for piece in &pieces {
+            for pair in piece.windows(2) {
+                assert!((pair[1][0] - pair[0][0]).abs() < 90.0, "{pair:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn land_or_sea_everywhere_has_no_coastline() {
+        let mut grid = MantleGrid::new(4);
+        assert!(coastlines(&grid, 0.0).is_empty());
+        for cell in &mut grid.cells {
+            cell.elevation = 100.0;
+        }
+        assert!(coastlines(&grid, 0.0).is_empty());
+        assert!(coastlines(&grid, 200.0).is_empty());
+    }
+}
@@ -0,0 +1,651 @@
+//! Initial conditions from real Earth data: an equirectangular elevation raster and plate
+//! polygons in the GeoJSON layout exported by GPlates, both sampled at the cell centres, and the
+//! hotspots of an earlier run.
+
+use std::{
+    collections::VecDeque,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    components::hotspot::Hotspot,
+    io::{
+        tiff::{GeoTransform, TiffError, read_tiff},
+        time_series::read_hotspots,
+    },
+    resources::{
+        mantle_grid::MantleGrid,
+        plates::{Plate, Plates},
+    },
+    simulation::geography::LatLon,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportSettings {
+    /// Replaces the scenario's elevations.
+    pub elevation: Option<ElevationImport>,
+    /// Replaces the scenario's plates. Imported plates start at rest.
+    pub plate_polygons: Option<PlatePolygonImport>,
+    /// Time series store whose latest hotspots replace the scenario's.
+    pub hotspots: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElevationImport {
+    /// A GeoTIFF, OpenEXR or greyscale PNG raster of elevations in metres.
+    pub path: PathBuf,
+    /// Elevations of the lowest and highest PNG levels. GeoTIFF and OpenEXR samples are used as
+    /// they are.
+    #[serde(default)]
+    pub range: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatePolygonImport {
+    /// A GeoJSON feature collection of polygons.
+    pub path: PathBuf,
+    /// Feature property holding the plate id.
+    #[serde(default = "default_plate_property")]
+    pub plate_property: String,
+}
+
+fn default_plate_property() -> String {
+    "PLATEID1".to_owned()
+}
+
+impl ImportSettings {
+    /// Replaces the configured parts of the scenario. Every import is read and sampled before
+    /// anything is written, so a failure leaves the scenario as it was.
+    pub fn apply(
+        &self,
+        grid: &mut MantleGrid,
+        plates: &mut Plates,
+        hotspots: &mut Vec<Hotspot>,
+    ) -> Result<(), ImportError> {
+        let elevations = match &self.elevation {
+            Some(import) => {
+                let raster = ElevationRaster::read(import)?;
+                let elevations: Vec<Option<f32>> = grid
+                    .cells
+                    .iter()
+                    .map(|cell| {
+                        let LatLon {
+                            latitude,
+                            longitude,
+                        } = LatLon::from_unit_vector(cell.center);
+                        raster.sample(latitude, longitude)
+                    })
+                    .collect();
+                Some((import, elevations))
+            }
+            None => None,
+        };
+        let assignment = match &self.plate_polygons {
+            Some(import) => {
+                let polygons = PlatePolygons::read(&import.path, &import.plate_property)?;
+                Some((import, polygons.assign(grid)?))
+            }
+            None => None,
+        };
+        let restored = match &self.hotspots {
+            Some(path) => Some((path, read_hotspots(path)?)),
+            None => None,
+        };
+
+        if let Some((import, elevations)) = elevations {
+            let mut missing = 0;
+            for (cell, elevation) in grid.cells.iter_mut().zip(elevations) {
+                match elevation {
+                    Some(elevation) => cell.elevation = elevation,
+                    None => missing += 1,
+                }
+            }
+            info!(
+                "Imported elevations from {}, {missing} cells outside the raster",
+                import.path.display()
+            );
+        }
+
+        if let Some((import, (ids, cell_plates))) = assignment {
+            for (cell, plate) in grid.cells.iter_mut().zip(cell_plates) {
+                cell.plate = plate;
+            }
+            *plates = Plates(vec![Plate::default(); ids.len()]);
+            info!("Imported plates {ids:?} from {}", import.path.display());
+        }
+
+        if let Some((path, restored)) = restored {
+            *hotspots = restored;
+            info!(
+                "Restored {} hotspots from {}",
+                hotspots.len(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// An elevation raster placed on the globe.
+#[derive(Debug, Clone)]
+pub struct ElevationRaster {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+    pub transform: GeoTransform,
+}
+
+impl ElevationRaster {
+    pub fn read(import: &ElevationImport) -> Result<Self, ImportError> {
+        let path = &import.path;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let (width, height, values, transform) = match extension.as_deref() {
+            Some("tif" | "tiff") => {
+                let tiff = read_tiff(path)?;
+                let values = match tiff.no_data {
+                    Some(no_data) => tiff
+                        .values
+                        .into_iter()
+                        .map(|value| if value == no_data { f32::NAN } else { value })
+                        .collect(),
+                    None => tiff.values,
+                };
+                (tiff.width, tiff.height, values, tiff.transform)
+            }
+            Some("png") => {
+                let (min, max) = import
+                    .range
+                    .ok_or_else(|| ImportError::MissingRange(path.clone()))?;
+                let (width, height, levels) = read_png_levels(path)?;
+                let values = levels
+                    .into_iter()
+                    .map(|level| min + level * (max - min))
+                    .collect();
+                (
+                    width,
+                    height,
+                    values,
+                    read_world_file(&path.with_extension("pgw")),
+                )
+            }
+            Some("exr") => {
+                let image = exr::prelude::read_first_flat_layer_from_file(path)?;
+                let layer = image.layer_data;
+                let channel = layer
+                    .channel_data
+                    .list
+                    .first()
+                    .ok_or_else(|| ImportError::EmptyRaster(path.clone()))?;
+                let values = channel.sample_data.values_as_f32().collect();
+                (layer.size.0, layer.size.1, values, None)
+            }
+            _ => return Err(ImportError::UnknownFormat(path.clone())),
+        };
+        if width == 0 || height == 0 {
+            return Err(ImportError::EmptyRaster(path.clone()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            values,
+            // Without georeferencing the raster is assumed to cover the whole globe.
+            transform: transform.unwrap_or(GeoTransform {
+                west: -180.0,
+                north: 90.0,
+                pixel_width: 360.0 / width as f64,
+                pixel_height: 180.0 / height as f64,
+            }),
+        })
+    }
+
+    /// Bilinearly interpolated elevation at a latitude and longitude in degrees, or `None` outside
+    /// the raster or where it has no data.
+    #[must_use]
+    pub fn sample(&self, latitude: f64, longitude: f64) -> Option<f32> {
+        let t = &self.transform;
+        let (width, height) = (self.width as f64, self.height as f64);
+        // Rasters spanning every longitude wrap around at the antimeridian.
+        let wraps = (width * t.pixel_width - 360.0).abs() < t.pixel_width;
+
+        let mut x = (longitude - t.west) / t.pixel_width;
+        if wraps {
+            x = x.rem_euclid(width);
+        }
+        let y = (t.north - latitude) / t.pixel_height;
+        if !(0.0..=width).contains(&x) || !(0.0..=height).contains(&y) {
+            return None;
+        }
+
+        // Interpolate between pixel centres.
+        let (x, y) = (x - 0.5, (y - 0.5).clamp(0.0, height - 1.0));
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = ((x - x0) as f32, (y - y0) as f32);
+        let column = |offset: f64| {
+            let column = x0 + offset;
+            if wraps {
+                column.rem_euclid(width) as usize
+            } else {
+                column.clamp(0.0, width - 1.0) as usize
+            }
+        };
+        let row = |offset: f64| (y0 + offset).min(height - 1.0) as usize;
+
+        let mut total = 0.0;
+        let mut weights = 0.0;
+        for (dx, dy, weight) in [
+            (0.0, 0.0, (1.0 - fx) * (1.0 - fy)),
+            (1.0, 0.0, fx * (1.0 - fy)),
+            (0.0, 1.0, (1.0 - fx) * fy),
+            (1.0, 1.0, fx * fy),
+        ] {
+            let value = self.values[row(dy) * self.width + column(dx)];
+            if value.is_finite() {
+                total += weight * value;
+                weights += weight;
+            }
+        }
+        (weights > 0.0).then(|| total / weights)
+    }
+}
+
+/// Levels of the first channel of a PNG, normalised to `0..=1`.
+fn read_png_levels(path: &Path) -> Result<(usize, usize, Vec<f32>), ImportError> {
+    let file = io::BufReader::new(fs::File::open(path)?);
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size().unwrap_or(0)];
+    let info = reader.next_frame(&mut buffer)?;
+    let channels = info.color_type.samples();
+
+    let levels = match info.bit_depth {
+        png::BitDepth::Sixteen => buffer[..info.buffer_size()]
+            .chunks_exact(2 * channels)
+            .map(|pixel| f32::from(u16::from_be_bytes([pixel[0], pixel[1]])) / 65535.0)
+            .collect(),
+        _ => buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|pixel| f32::from(pixel[0]) / 255.0)
+            .collect(),
+    };
+    Ok((info.width as usize, info.height as usize, levels))
+}
+
+/// Reads an ESRI world file, which locates the centre of the top-left pixel.
+fn read_world_file(path: &Path) -> Option<GeoTransform> {
+    let text = fs::read_to_string(path).ok()?;
+    let numbers: Vec<f64> = text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let [pixel_width, _, _, pixel_height, x, y] = numbers[..] else {
+        return None;
+    };
+    Some(GeoTransform {
+        west: x - pixel_width / 2.0,
+        north: y - pixel_height / 2.0,
+        pixel_width,
+        pixel_height: -pixel_height,
+    })
+}
+
+/// A polygon with holes, with longitudes unwrapped so that no edge jumps across the antimeridian.
+#[derive(Debug, Clone)]
+struct Polygon {
+    plate_id: i64,
+    /// Rings of `(longitude, latitude)` in degrees, the first being the outline.
+    rings: Vec<Vec<(f64, f64)>>,
+    /// Longitude and latitude bounds of the outline.
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Polygon {
+    fn new(plate_id: i64, rings: Vec<Vec<(f64, f64)>>) -> Self {
+        let rings: Vec<_> = rings.into_iter().map(unwrap_ring).collect();
+        let (min, max) = rings.first().into_iter().flatten().fold(
+            (
+                (f64::INFINITY, f64::INFINITY),
+                (f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), &(lon, lat)| {
+                (
+                    (min.0.min(lon), min.1.min(lat)),
+                    (max.0.max(lon), max.1.max(lat)),
+                )
+            },
+        );
+        Self {
+            plate_id,
+            rings,
+            min,
+            max,
+        }
+    }
+
+    fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        [longitude, longitude - 360.0, longitude + 360.0]
+            .into_iter()
+            .any(|longitude| {
+                (self.min.0..=self.max.0).contains(&longitude)
+                    && (self.min.1..=self.max.1).contains(&latitude)
+                    && self
+                        .rings
+                        .iter()
+                        .filter(|ring| ring_contains(ring, longitude, latitude))
+                        .count()
+                        % 2
+                        == 1
+            })
+    }
+}
+
+/// Removes jumps of more than 180° of longitude between consecutive points. A ring that goes
+/// once around a pole no longer closes after unwrapping and is closed over that pole instead.
+fn unwrap_ring(ring: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let mut unwrapped: Vec<(f64, f64)> = Vec::with_capacity(ring.len() + 2);
+    for (mut longitude, latitude) in ring {
+        if let Some(&(previous, _)) = unwrapped.last() {
+            while longitude - previous > 180.0 {
+                longitude -= 360.0;
+            }
+            while longitude - previous < -180.0 {
+                longitude += 360.0;
+            }
+        }
+        unwrapped.push((longitude, latitude));
+    }
+
+    if let (Some(&(first, _)), Some(&(last, _))) = (unwrapped.first(), unwrapped.last())
+        && (last - first).abs() > 180.0
+    {
+        let mean_latitude =
+            unwrapped.iter().map(|&(_, lat)| lat).sum::<f64>() / unwrapped.len() as f64;
+        let pole = 90.0f64.copysign(mean_latitude);
+        unwrapped.push((last, pole));
+        unwrapped.push((first, pole));
+    }
+    unwrapped
+}
+
+/// Even-odd ray casting in the longitude-latitude plane, as GeoJSON edges are straight there.
+fn ring_contains(ring: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match ring.last() {
+        Some(&point) => point,
+        None => return false,
+    };
+    for &point in ring {
+        let ((x0, y0), (x1, y1)) = (previous, point);
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
+
+/// Plate outlines read from a GeoJSON feature collection.
+#[derive(Debug, Clone)]
+pub struct PlatePolygons {
+    polygons: Vec<Polygon>,
+}
+
+impl PlatePolygons {
+    /// Reads every `Polygon` and `MultiPolygon` feature whose `plate_property` is a number.
+    pub fn read(path: &Path, plate_property: &str) -> Result<Self, ImportError> {
+        let document: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let features = document["features"]
+            .as_array()
+            .ok_or_else(|| ImportError::InvalidGeoJson("expected a feature collection".into()))?;
+
+        let mut polygons = Vec::new();
+        for feature in features {
+            let property = &feature["properties"][plate_property];
+            let plate_id = property
+                .as_i64()
+                .or_else(|| property.as_f64().map(|id| id as i64))
+                .or_else(|| property.as_str().and_then(|id| id.trim().parse().ok()));
+            let Some(plate_id) = plate_id else {
+                continue;
+            };
+
+            let geometry = &feature["geometry"];
+            let coordinates = &geometry["coordinates"];
+            match geometry["type"].as_str() {
+                Some("Polygon") => polygons.push(Polygon::new(plate_id, parse_rings(coordinates)?)),
+                Some("MultiPolygon") => {
+                    for polygon in coordinates.as_array().into_iter().flatten() {
+                        polygons.push(Polygon::new(plate_id, parse_rings(polygon)?));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if polygons.is_empty() {
+            return Err(ImportError::InvalidGeoJson(format!(
+                "no polygon has a numeric {plate_property:?} property"
+            )));
+        }
+        Ok(Self { polygons })
+    }
+
+    /// Plate id of the first polygon containing a point, in degrees.
+    #[must_use]
+    pub fn plate_at(&self, latitude: f64, longitude: f64) -> Option<i64> {
+        self.polygons
+            .iter()
+            .find(|polygon| polygon.contains(latitude, longitude))
+            .map(|polygon| polygon.plate_id)
+    }
+
+    /// Assigns every cell to the plate containing its centre, numbering plates by ascending id.
+    /// Cells in gaps between polygons join the plate of the nearest assigned cell. Returns the
+    /// original plate ids in the order of the new plate indices, and the plate of every cell.
+    pub fn assign(&self, grid: &MantleGrid) -> Result<(Vec<i64>, Vec<usize>), ImportError> {
+        let found: Vec<Option<i64>> = grid
+            .cells
+            .iter()
+            .map(|cell| {
+                let LatLon {
+                    latitude,
+                    longitude,
+                } = LatLon::from_unit_vector(cell.center);
+                self.plate_at(latitude, longitude)
+            })
+            .collect();
+        let mut ids: Vec<i64> = found.iter().flatten().copied().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.is_empty() {
+            return Err(ImportError::InvalidGeoJson(
+                "no cell centre lies inside a plate polygon".into(),
+            ));
+        }
+
+        let mut plates: Vec<Option<usize>> = found
+            .iter()
+            .map(|id| id.and_then(|id| ids.binary_search(&id).ok()))
+            .collect();
+        let mut queue: VecDeque<usize> =
+            (0..plates.len()).filter(|&c| plates[c].is_some()).collect();
+        while let Some(cell) = queue.pop_front() {
+            for &neighbor in &grid.neighbors[cell] {
+                if plates[neighbor].is_none() {
+                    plates[neighbor] = plates[cell];
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let plates = plates.into_iter().map(|plate| plate.unwrap_or(0)).collect();
+        Ok((ids, plates))
+    }
+}
+
+fn parse_rings(polygon: &Value) -> Result<Vec<Vec<(f64, f64)>>, ImportError> {
+    let invalid = || ImportError::InvalidGeoJson("malformed polygon coordinates".into());
+    polygon
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|ring| {
+            ring.as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(
+                    |position| match (position[0].as_f64(), position[1].as_f64()) {
+                        (Some(longitude), Some(latitude)) => Ok((longitude, latitude)),
+                        _ => Err(invalid()),
+                    },
+                )
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Tiff(TiffError),
+    Png(png::DecodingError),
+    Exr(exr::error::Error),
+    Json(serde_json::Error),
+    InvalidGeoJson(String),
+    UnknownFormat(PathBuf),
+    MissingRange(PathBuf),
+    EmptyRaster(PathBuf),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read import: {error}"),
+            Self::Tiff(error) => write!(f, "{error}"),
+            Self::Png(error) => write!(f, "failed to decode PNG: {error}"),
+            Self::Exr(error) => write!(f, "failed to decode OpenEXR: {error}"),
+            Self::Json(error) => write!(f, "invalid JSON: {error}"),
+            Self::InvalidGeoJson(message) => write!(f, "invalid plate polygons: {message}"),
+            Self::UnknownFormat(path) => write!(
+                f,
+                "cannot tell the raster format of {}; use a .tif, .png or .exr extension",
+                path.display()
+            ),
+            Self::MissingRange(path) => write!(
+                f,
+                "{} is a PNG, whose levels need an elevation range",
+                path.display()
+            ),
+            Self::EmptyRaster(path) => write!(f, "{} holds no samples", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<TiffError> for ImportError {
+    fn from(error: TiffError) -> Self {
+        Self::Tiff(error)
+    }
+}
+
+impl From<png::DecodingError> for ImportError {
+    fn from(error: png::DecodingError) -> Self {
+        Self::Png(error)
+    }
+}
+
+impl From<exr::error::Error> for ImportError {
+    fn from(error: exr::error::Error) -> Self {
+        Self::Exr(error)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn samples_pixel_centres_of_both_raster_types() {
+        for name in ["pixel_is_area.tif", "pixel_is_point.tif"] {
+            let raster = ElevationRaster::read(&ElevationImport {
+                path: fixture(name),
+                range: None,
+            })
+            .unwrap();
+            // The centre of the second pixel of the top row, at 45°N 45°W.
+            assert_eq!(raster.sample(45.0, -45.0), Some(raster.values[1]));
+        }
+    }
+
+    #[test]
+    fn assigns_plates_by_ascending_id() {
+        let grid = MantleGrid::new(4);
+        let polygons = PlatePolygons::read(&fixture("hemispheres.geojson"), "PLATEID1").unwrap();
+        let (ids, plates) = polygons.assign(&grid).unwrap();
+        assert_eq!(ids, [101, 201]);
+        for (cell, plate) in grid.cells.iter().zip(plates) {
+            let longitude = LatLon::from_unit_vector(cell.center).longitude;
+            if longitude.abs() > 1.0 && longitude.abs() < 179.0 {
+                assert_eq!(plate, usize::from(longitude < 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn failed_import_leaves_the_scenario_unchanged() {
+        let mut grid = MantleGrid::new(4);
+        let mut plates = Plates(vec![Plate::default(); 3]);
+        let mut hotspots = vec![Hotspot::default()];
+        let checksum = grid.checksum();
+
+        let settings = ImportSettings {
+            elevation: Some(ElevationImport {
+                path: fixture("pixel_is_area.tif"),
+                range: None,
+            }),
+            plate_polygons: Some(PlatePolygonImport {
+                path: fixture("malformed_polygon.geojson"),
+                plate_property: default_plate_property(),
+            }),
+            hotspots: None,
+        };
+        assert!(matches!(
+            settings.apply(&mut grid, &mut plates, &mut hotspots),
+            Err(ImportError::InvalidGeoJson(_))
+        ));
+        assert_eq!(grid.checksum(), checksum);
+        assert_eq!(plates.0.len(), 3);
+        assert_eq!(hotspots.len(), 1);
+    }
+}
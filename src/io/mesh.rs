@@ -0,0 +1,500 @@
+//! Exporters for the planet surface with per-vertex attributes.
+//!
+//! Vertex values are the mean of the cells sharing the vertex, as on the GPU, except plate ids
+//! which take the plate owning most of those cells. Positions are displaced by the elevation,
+//! exaggerated by `elevation_exaggeration`, on a sphere of radius one. Vertex colours show the
+//! field of the `FieldDisplay` with its colormap and range, as the viewer would.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::pressure_material::DEFAULT_ELEVATION_EXAGGERATION,
+    resources::{
+        field_display::FieldDisplay,
+        field_statistics::FieldStatistics,
+        mantle_grid::{CellField, MantleGrid, PLANET_RADIUS},
+    },
+};
+
+/// The planet mesh to write at the end of a headless run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshExport {
+    /// Output file, whose `.glb`, `.gltf`, `.ply` or `.obj` extension selects the format.
+    pub path: PathBuf,
+    #[serde(default = "default_elevation_exaggeration")]
+    pub elevation_exaggeration: f32,
+}
+
+fn default_elevation_exaggeration() -> f32 {
+    DEFAULT_ELEVATION_EXAGGERATION
+}
+
+impl MeshExport {
+    pub fn write(&self, grid: &MantleGrid, display: &FieldDisplay) -> Result<(), MeshExportError> {
+        // Check the format before creating the file, so that a bad extension leaves it alone.
+        let format = MeshFormat::from_path(&self.path)?;
+        let surface = Surface::new(grid, self.elevation_exaggeration, display);
+        let mut file = BufWriter::new(fs::File::create(&self.path)?);
+        match format {
+            MeshFormat::Glb => surface.write_glb(&mut file)?,
+            MeshFormat::Gltf => surface.write_gltf(&mut file, &self.path.with_extension("bin"))?,
+            MeshFormat::Ply => surface.write_ply(&mut file)?,
+            MeshFormat::Obj => surface.write_obj(&mut file)?,
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    /// Binary glTF 2.0.
+    Glb,
+    /// glTF 2.0 JSON with its buffer in a `.bin` file alongside.
+    Gltf,
+    /// Binary little-endian PLY.
+    Ply,
+    /// Wavefront OBJ, which only carries positions, normals and colours.
+    Obj,
+}
+
+impl MeshFormat {
+    pub fn from_path(path: &Path) -> Result<Self, MeshExportError> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("glb") => Ok(Self::Glb),
+            Some("gltf") => Ok(Self::Gltf),
+            Some("ply") => Ok(Self::Ply),
+            Some("obj") => Ok(Self::Obj),
+            _ => Err(MeshExportError::UnknownFormat(path.to_owned())),
+        }
+    }
+}
+
+/// Scalar attributes written for every vertex, in order.
+const SCALAR_FIELDS: [CellField; 4] = [
+    CellField::Pressure,
+    CellField::Elevation,
+    CellField::Temperature,
+    CellField::Age,
+];
+
+/// The displaced surface with its vertex attributes.
+pub struct Surface {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    /// Linear RGB colour of the displayed field, mapped as the planet material maps it.
+    pub colors: Vec<Vec3>,
+    /// One vector per entry of `SCALAR_FIELDS`.
+    pub scalars: Vec<Vec<f32>>,
+    pub plates: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl Surface {
+    #[must_use]
+    pub fn new(grid: &MantleGrid, elevation_exaggeration: f32, display: &FieldDisplay) -> Self {
+        let scalars: Vec<Vec<f32>> = SCALAR_FIELDS
+            .iter()
+            .map(|&field| grid.vertex_values(field))
+            .collect();
+        let elevations = &scalars[1];
+
+        let positions: Vec<Vec3> = grid
+            .sphere
+            .raw_points()
+            .iter()
+            .zip(elevations)
+            .map(|(&point, &elevation)| {
+                Vec3::from(point).normalize()
+                    * (1.0 + elevation * elevation_exaggeration / PLANET_RADIUS)
+            })
+            .collect();
+
+        // Area-weighted face normals, so exaggerated relief is shaded correctly.
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        for triangle in grid.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+            for vertex in [a, b, c] {
+                normals[vertex] += normal;
+            }
+        }
+        for (normal, position) in normals.iter_mut().zip(&positions) {
+            *normal = normal.try_normalize().unwrap_or(position.normalize());
+            if normal.dot(*position) < 0.0 {
+                *normal = -*normal;
+            }
+        }
+
+        let plates: Vec<u32> = grid
+            .vertex_triangles
+            .iter()
+            .map(|triangles| {
+                let mut counts: HashMap<usize, usize> = HashMap::new();
+                for &cell in triangles {
+                    *counts.entry(grid.cells[cell].plate).or_default() += 1;
+                }
+                counts
+                    .into_iter()
+                    .max_by_key(|&(plate, count)| (count, std::cmp::Reverse(plate)))
+                    .map_or(0, |(plate, _)| plate as u32)
+            })
+            .collect();
+
+        // Averaged plate ids would fall between categories, so vertices take their plate's colour.
+        let values = match display.field {
+            CellField::Plate => plates.iter().map(|&plate| plate as f32).collect(),
+            field => grid.vertex_values(field),
+        };
+        let color_scale = display.color_scale(&FieldStatistics::compute(grid, display.field));
+        let colors = values
+            .iter()
+            .map(|&value| {
+                let color = LinearRgba::from(display.colormap.color(color_scale.position(value)));
+                Vec3::new(color.red, color.green, color.blue)
+            })
+            .collect();
+
+        Self {
+            colors,
+            positions,
+            normals,
+            scalars,
+            plates,
+            indices: grid.indices.clone(),
+        }
+    }
+
+    pub fn write_ply(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format binary_little_endian 1.0")?;
+        writeln!(writer, "element vertex {}", self.positions.len())?;
+        for property in ["x", "y", "z", "nx", "ny", "nz"] {
+            writeln!(writer, "property float {property}")?;
+        }
+        for property in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {property}")?;
+        }
+        for field in SCALAR_FIELDS {
+            writeln!(writer, "property float {}", field.name())?;
+        }
+        writeln!(writer, "property uint plate")?;
+        writeln!(writer, "element face {}", self.indices.len() / 3)?;
+        writeln!(writer, "property list uchar uint vertex_indices")?;
+        writeln!(writer, "end_header")?;
+
+        for vertex in 0..self.positions.len() {
+            for value in self.positions[vertex]
+                .to_array()
+                .into_iter()
+                .chain(self.normals[vertex].to_array())
+            {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&srgb_bytes(self.colors[vertex]))?;
+            for values in &self.scalars {
+                writer.write_all(&values[vertex].to_le_bytes())?;
+            }
+            writer.write_all(&self.plates[vertex].to_le_bytes())?;
+        }
+        for triangle in self.indices.chunks_exact(3) {
+            writer.write_all(&[3])?;
+            for index in triangle {
+                writer.write_all(&index.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_obj(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "# Vertex colours show the pressure.")?;
+        for (position, color) in self.positions.iter().zip(&self.colors) {
+            let [r, g, b] = srgb_bytes(*color).map(|c| f32::from(c) / 255.0);
+            writeln!(
+                writer,
+                "v {} {} {} {r} {g} {b}",
+                position.x, position.y, position.z
+            )?;
+        }
+        for normal in &self.normals {
+            writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] + 1);
+            writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+        Ok(())
+    }
+
+    pub fn write_glb(&self, writer: &mut impl Write) -> io::Result<()> {
+        const MAGIC: u32 = 0x4654_6c67;
+        const JSON_CHUNK: u32 = 0x4e4f_534a;
+        const BIN_CHUNK: u32 = 0x004e_4942;
+
+        let (document, buffer) = self.gltf(None);
+        let mut json = serde_json::to_vec(&document)?;
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut binary = buffer;
+        binary.resize(binary.len().next_multiple_of(4), 0);
+        let length = 12 + 8 + json.len() + 8 + binary.len();
+
+        for word in [MAGIC, 2, length as u32, json.len() as u32, JSON_CHUNK] {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.write_all(&json)?;
+        for word in [binary.len() as u32, BIN_CHUNK] {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.write_all(&binary)
+    }
+
+    /// Writes the JSON document to `writer` and its buffer to `buffer_path`.
+    pub fn write_gltf(&self, writer: &mut impl Write, buffer_path: &Path) -> io::Result<()> {
+        let uri = buffer_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid buffer path"))?;
+        let (document, buffer) = self.gltf(Some(uri));
+        fs::write(buffer_path, buffer)?;
+        serde_json::to_writer_pretty(&mut *writer, &document)?;
+        Ok(())
+    }
+
+    /// Builds the glTF document and the contents of its single buffer. Custom attributes are
+    /// prefixed with an underscore as the specification requires, and plate ids are stored as
+    /// floats since integer vertex attributes are limited to 16 bits.
+    fn gltf(&self, uri: Option<&str>) -> (serde_json::Value, Vec<u8>) {
+        const FLOAT: u32 = 5126;
+        const UNSIGNED_INT: u32 = 5125;
+        const ARRAY_BUFFER: u32 = 34962;
+        const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+        let mut buffer = Vec::new();
+        let mut views = Vec::new();
+        let mut accessors = Vec::new();
+        let mut attributes = serde_json::Map::new();
+        let mut push = |name: Option<String>,
+                        bytes: Vec<u8>,
+                        kind: &str,
+                        component_type: u32,
+                        count: usize,
+                        target: u32,
+                        bounds: Option<(Vec3, Vec3)>| {
+            let view = views.len();
+            views.push(serde_json::json!({
+                "buffer": 0,
+                "byteOffset": buffer.len(),
+                "byteLength": bytes.len(),
+                "target": target,
+            }));
+            buffer.extend(bytes);
+            let mut accessor = serde_json::json!({
+                "bufferView": view,
+                "componentType": component_type,
+                "count": count,
+                "type": kind,
+            });
+            if let Some((min, max)) = bounds {
+                accessor["min"] = serde_json::json!(min.to_array());
+                accessor["max"] = serde_json::json!(max.to_array());
+            }
+            let index = accessors.len();
+            accessors.push(accessor);
+            if let Some(name) = name {
+                attributes.insert(name, index.into());
+            }
+            index
+        };
+
+        let count = self.positions.len();
+        let vectors = |values: &[Vec3]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|v| v.to_array())
+                .flat_map(f32::to_le_bytes)
+                .collect()
+        };
+        let scalars = |values: &[f32]| -> Vec<u8> {
+            values.iter().copied().flat_map(f32::to_le_bytes).collect()
+        };
+
+        let bounds = self
+            .positions
+            .iter()
+            .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), &p| {
+                (min.min(p), max.max(p))
+            });
+        push(
+            Some("POSITION".into()),
+            vectors(&self.positions),
+            "VEC3",
+            FLOAT,
+            count,
+            ARRAY_BUFFER,
+            Some(bounds),
+        );
+        push(
+            Some("NORMAL".into()),
+            vectors(&self.normals),
+            "VEC3",
+            FLOAT,
+            count,
+            ARRAY_BUFFER,
+            None,
+        );
+        push(
+            Some("COLOR_0".into()),
+            vectors(&self.colors),
+            "VEC3",
+            FLOAT,
+            count,
+            ARRAY_BUFFER,
+            None,
+        );
+        for (field, values) in SCALAR_FIELDS.iter().zip(&self.scalars) {
+            push(
+                Some(format!("_{}", field.name().to_ascii_uppercase())),
+                scalars(values),
+                "SCALAR",
+                FLOAT,
+                count,
+                ARRAY_BUFFER,
+                None,
+            );
+        }
+        let plates: Vec<f32> = self.plates.iter().map(|&plate| plate as f32).collect();
+        push(
+            Some("_PLATE".into()),
+            scalars(&plates),
+            "SCALAR",
+            FLOAT,
+            count,
+            ARRAY_BUFFER,
+            None,
+        );
+        let indices = push(
+            None,
+            self.indices
+                .iter()
+                .copied()
+                .flat_map(u32::to_le_bytes)
+                .collect(),
+            "SCALAR",
+            UNSIGNED_INT,
+            self.indices.len(),
+            ELEMENT_ARRAY_BUFFER,
+            None,
+        );
+
+        let mut buffer_object = serde_json::json!({ "byteLength": buffer.len() });
+        if let Some(uri) = uri {
+            buffer_object["uri"] = uri.into();
+        }
+        let document = serde_json::json!({
+            "asset": { "version": "2.0", "generator": env!("CARGO_PKG_NAME") },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0, "name": "planet" }],
+            "meshes": [{
+                "name": "planet",
+                "primitives": [{ "attributes": attributes, "indices": indices, "mode": 4 }],
+            }],
+            "accessors": accessors,
+            "bufferViews": views,
+            "buffers": [buffer_object],
+        });
+        (document, buffer)
+    }
+}
+
+fn srgb_bytes(color: Vec3) -> [u8; 3] {
+    let [r, g, b, _] = Srgba::from(LinearRgba::rgb(color.x, color.y, color.z)).to_u8_array();
+    [r, g, b]
+}
+
+#[derive(Debug)]
+pub enum MeshExportError {
+    Io(io::Error),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for MeshExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to write mesh: {error}"),
+            Self::UnknownFormat(path) => write!(
+                f,
+                "cannot tell the mesh format of {}; use a .glb, .gltf, .ply or .obj extension",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MeshExportError {}
+
+impl From<io::Error> for MeshExportError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(color: Srgba) -> Vec3 {
+        let color = LinearRgba::from(color);
+        Vec3::new(color.red, color.green, color.blue)
+    }
+
+    #[test]
+    fn colors_span_the_colormap_of_the_display() {
+        let mut grid = MantleGrid::new(4);
+        for cell in &mut grid.cells {
+            cell.elevation = cell.center.y * 1000.0;
+        }
+        let display = FieldDisplay::new(CellField::Elevation);
+        let surface = Surface::new(&grid, 1.0, &display);
+
+        // With an automatic range, the poles lie close to the ends of the range and take
+        // colours from the two ends of the colormap.
+        for (pole, t) in [(Vec3::NEG_Y, 0.0), (Vec3::Y, 1.0)] {
+            let vertex = surface
+                .positions
+                .iter()
+                .position(|position| position.normalize().dot(pole) > 0.999)
+                .unwrap();
+            let color = surface.colors[vertex];
+            let end = linear(display.colormap.color(t));
+            assert!(color.distance(end) < 0.05, "{color} is not near {end}");
+        }
+    }
+
+    #[test]
+    fn plate_colors_follow_the_categorical_colormap() {
+        let mut grid = MantleGrid::new(2);
+        for (index, cell) in grid.cells.iter_mut().enumerate() {
+            cell.plate = index % 3;
+        }
+        let display = FieldDisplay::new(CellField::Plate);
+        let surface = Surface::new(&grid, 1.0, &display);
+        let categories = display.colormap.texels() as f32;
+        for (color, &plate) in surface.colors.iter().zip(&surface.plates) {
+            let expected = linear(display.colormap.color((plate as f32 + 0.5) / categories));
+            assert!(color.distance(expected) < 1.0e-5);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod geojson;
+pub mod import;
+pub mod mesh;
+pub mod plate_statistics;
+pub mod raster;
+pub mod tiff;
+pub mod time_series;
@@ -0,0 +1,224 @@
+//! Per-step tables of plate statistics as CSV or JSON Lines.
+//!
+//! CSV files hold one row per plate and step, followed by a row whose `plate` column is `all`
+//! with the global statistics, where the perimeter is the total boundary length and columns that
+//! only make sense for a single plate are left empty. JSON Lines files hold one object per step
+//! with the plates and the global statistics.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    resources::simulation_clock::SimulationClock, simulation::plate_statistics::Statistics,
+};
+
+const CSV_HEADER: &str = "step,time,plate,cells,area_km2,perimeter_km,centroid_lat,centroid_lon,\
+euler_pole_lat,euler_pole_lon,angular_speed_deg_per_myr,continental_fraction,mean_elevation_m,\
+rms_velocity_mm_per_yr,ridge_km,subduction_km,collision_km,transform_km";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlateStatisticsSettings {
+    /// Output file, whose `.csv` or `.jsonl` extension selects the format.
+    pub path: PathBuf,
+    /// Number of steps between rows.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TableFormat {
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => Ok(Self::Csv),
+            Some("jsonl" | "ndjson") => Ok(Self::JsonLines),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot tell the table format of {}; use a .csv or .jsonl extension",
+                    path.display()
+                ),
+            )),
+        }
+    }
+}
+
+/// Appends plate statistics to a file, flushing after every step so partial runs are usable.
+#[derive(Resource, Debug)]
+pub struct PlateStatisticsWriter {
+    file: BufWriter<File>,
+    format: TableFormat,
+    interval: u64,
+}
+
+impl PlateStatisticsWriter {
+    pub fn create(settings: &PlateStatisticsSettings) -> io::Result<Self> {
+        let format = TableFormat::from_path(&settings.path)?;
+        let mut file = BufWriter::new(File::create(&settings.path)?);
+        if format == TableFormat::Csv {
+            writeln!(file, "{CSV_HEADER}")?;
+        }
+        Ok(Self {
+            file,
+            format,
+            interval: settings.interval.max(1),
+        })
+    }
+
+    /// Whether a row is due after `step` steps.
+    #[must_use]
+    pub fn is_due(&self, step: u64) -> bool {
+        step.is_multiple_of(self.interval)
+    }
+
+    pub fn write(&mut self, clock: &SimulationClock, statistics: &Statistics) -> io::Result<()> {
+        match self.format {
+            TableFormat::Csv => self.write_csv(clock, statistics)?,
+            TableFormat::JsonLines => {
+                let line = json!({
+                    "step": clock.step,
+                    "time": clock.elapsed,
+                    "plates": statistics.plates,
+                    "global": statistics.global,
+                });
+                serde_json::to_writer(&mut self.file, &line)?;
+                writeln!(self.file)?;
+            }
+        }
+        self.file.flush()
+    }
+
+    fn write_csv(&mut self, clock: &SimulationClock, statistics: &Statistics) -> io::Result<()> {
+        let prefix = format!("{},{}", clock.step, clock.elapsed);
+        for plate in &statistics.plates {
+            let b = &plate.boundaries;
+            writeln!(
+                self.file,
+                "{prefix},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                plate.plate,
+                plate.cells,
+                plate.area,
+                plate.perimeter,
+                plate.centroid_latitude,
+                plate.centroid_longitude,
+                plate.euler_pole_latitude,
+                plate.euler_pole_longitude,
+                plate.angular_speed,
+                plate.continental_fraction,
+                plate.mean_elevation,
+                plate.rms_velocity,
+                b.ridge,
+                b.subduction,
+                b.collision,
+                b.transform,
+            )?;
+        }
+
+        let global = &statistics.global;
+        let b = &global.boundaries;
+        let cells: usize = statistics.plates.iter().map(|plate| plate.cells).sum();
+        let area: f32 = statistics.plates.iter().map(|plate| plate.area).sum();
+        writeln!(
+            self.file,
+            "{prefix},all,{},{},{},,,,,,{},{},{},{},{},{},{}",
+            cells,
+            area,
+            b.total(),
+            global.continental_fraction,
+            global.mean_elevation,
+            global.rms_velocity,
+            b.ridge,
+            b.subduction,
+            b.collision,
+            b.transform,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        resources::{
+            mantle_grid::MantleGrid,
+            plates::{Plate, Plates},
+        },
+        simulation::plate_dynamics::PlateDynamicsParameters,
+    };
+
+    use super::*;
+
+    #[test]
+    fn csv_has_a_complete_row_per_plate_and_frame() {
+        let path =
+            std::env::temp_dir().join(format!("plate_statistics_{}.csv", std::process::id()));
+        let mut grid = MantleGrid::new(2);
+        let mut plates = Plates::from_seeds(&mut grid, &[Vec3::X, Vec3::NEG_X]);
+        plates.0[0] = Plate {
+            euler_pole: Vec3::Z,
+            angular_speed: 1.0e-8,
+        };
+        for cell in &mut grid.cells {
+            cell.elevation = if cell.plate == 0 { 1000.0 } else { -3000.0 };
+        }
+        let statistics = Statistics::compute(&grid, &plates, &PlateDynamicsParameters::default());
+
+        let settings = PlateStatisticsSettings {
+            path: path.clone(),
+            interval: 1,
+        };
+        let mut writer = PlateStatisticsWriter::create(&settings).unwrap();
+        let mut clock = SimulationClock::default();
+        for step in 1..=2 {
+            clock.step = step;
+            clock.elapsed = step as f64 * f64::from(clock.time_step);
+            writer.write(&clock, &statistics).unwrap();
+        }
+        drop(writer);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let mut lines = contents.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let column = |name: &str| header.iter().position(|&column| column == name).unwrap();
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 2 * 3);
+
+        for (frame, rows) in rows.chunks(3).enumerate() {
+            let plates: Vec<&str> = rows.iter().map(|row| row[column("plate")]).collect();
+            assert_eq!(plates, ["0", "1", "all"]);
+            for row in rows {
+                assert_eq!(row.len(), header.len());
+                assert_eq!(row[column("step")], (frame + 1).to_string());
+            }
+            let value = |row: &[&str], name: &str| row[column(name)].parse::<f32>().unwrap();
+            assert!((value(&rows[0], "mean_elevation_m") - 1000.0).abs() < 0.01);
+            assert!((value(&rows[1], "mean_elevation_m") + 3000.0).abs() < 0.01);
+            assert!(value(&rows[0], "rms_velocity_mm_per_yr") > 0.0);
+            assert_eq!(value(&rows[1], "rms_velocity_mm_per_yr"), 0.0);
+            let global = value(&rows[2], "mean_elevation_m");
+            assert!(global > -3000.0 && global < 1000.0);
+            assert!(rows[2][column("centroid_lat")].is_empty());
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Equirectangular latitude/longitude rasters of per-cell fields.
+//!
+//! Rows run from north to south and columns from 180°W to 180°E, with pixel centres at
+//! `(row + 0.5, column + 0.5)` so that no pixel sits exactly on a pole. PNG rasters are written
+//! alongside a world file so GIS tools place them on the globe without further input.
+
+use std::{
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, SmallVec,
+    WritableImage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resources::{
+        cell_locator::CellLocator,
+        mantle_grid::{CellField, MantleGrid},
+    },
+    simulation::geography::LatLon,
+};
+
+/// A field to write as a raster at the end of a headless run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RasterExport {
+    pub field: CellField,
+    /// Output file, whose `.png` or `.exr` extension selects the format.
+    pub path: PathBuf,
+    /// Width in pixels. The height is always half of it.
+    #[serde(default = "default_width")]
+    pub width: u32,
+    /// Values mapped to black and white in 16-bit PNGs, or the field's extent when unset.
+    #[serde(default)]
+    pub range: Option<(f32, f32)>,
+}
+
+fn default_width() -> u32 {
+    1024
+}
+
+impl RasterExport {
+    pub fn write(&self, grid: &MantleGrid, locator: &CellLocator) -> Result<(), RasterError> {
+        let raster = Raster::sample(grid, locator, self.field, self.width);
+        match RasterFormat::from_path(&self.path)? {
+            RasterFormat::Png => {
+                // Plate ids are categories and are stored as they are.
+                let range = match self.field {
+                    CellField::Plate => Some((0.0, f32::from(u16::MAX))),
+                    _ => self.range,
+                };
+                raster.write_png(&self.path, range)
+            }
+            RasterFormat::Exr => raster.write_exr(&self.path, self.field.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterFormat {
+    Png,
+    Exr,
+}
+
+impl RasterFormat {
+    pub fn from_path(path: &Path) -> Result<Self, RasterError> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png") => Ok(Self::Png),
+            Some("exr") => Ok(Self::Exr),
+            _ => Err(RasterError::UnknownFormat(path.to_owned())),
+        }
+    }
+}
+
+/// Field values on an equirectangular grid, row-major from the north-west corner.
+#[derive(Debug, Clone)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub values: Vec<f32>,
+}
+
+impl Raster {
+    /// Resamples `field` from the cell centres onto a raster `width` pixels wide.
+    ///
+    /// Continuous fields are interpolated by inverse distance weighting over the cell containing
+    /// each pixel centre and its neighbours; plate ids take the value of the containing cell.
+    #[must_use]
+    pub fn sample(grid: &MantleGrid, locator: &CellLocator, field: CellField, width: u32) -> Self {
+        let width = width.max(2);
+        let height = width / 2;
+        let mut values = Vec::with_capacity(width as usize * height as usize);
+        for row in 0..height {
+            for column in 0..width {
+                let point = pixel_coordinates(row, column, width, height).to_unit_vector();
+                let cell = locator.locate(grid, point);
+                values.push(match field {
+                    CellField::Plate => field.value(&grid.cells[cell]),
+                    _ => interpolate(grid, field, cell, point),
+                });
+            }
+        }
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// Smallest and largest finite value.
+    #[must_use]
+    pub fn extent(&self) -> (f32, f32) {
+        self.values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            })
+    }
+
+    /// Writes a 16-bit greyscale PNG mapping `range` linearly onto `0..=65535`, and a `.pgw`
+    /// world file next to it.
+    pub fn write_png(&self, path: &Path, range: Option<(f32, f32)>) -> Result<(), RasterError> {
+        let (min, max) = range.unwrap_or_else(|| self.extent());
+        let scale = if max > min {
+            f32::from(u16::MAX) / (max - min)
+        } else {
+            0.0
+        };
+        let data: Vec<u8> = self
+            .values
+            .iter()
+            .flat_map(|&value| {
+                let level = ((value - min) * scale)
+                    .round()
+                    .clamp(0.0, f32::from(u16::MAX));
+                (level as u16).to_be_bytes()
+            })
+            .collect();
+
+        let file = BufWriter::new(fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+
+        self.write_world_file(&path.with_extension("pgw"))
+    }
+
+    /// Writes a single-channel 32-bit float OpenEXR image.
+    pub fn write_exr(&self, path: &Path, channel: &str) -> Result<(), RasterError> {
+        let channels = AnyChannels::sort(SmallVec::from_vec(vec![AnyChannel::new(
+            channel,
+            FlatSamples::F32(self.values.clone()),
+        )]));
+        let layer = Layer::new(
+            (self.width as usize, self.height as usize),
+            LayerAttributes::named(channel),
+            Encoding::SMALL_LOSSLESS,
+            channels,
+        );
+        Image::from_layer(layer).write().to_file(path)?;
+        Ok(())
+    }
+
+    /// Georeferences the raster in degrees of longitude and latitude.
+    fn write_world_file(&self, path: &Path) -> Result<(), RasterError> {
+        let pixel_width = 360.0 / f64::from(self.width);
+        let pixel_height = 180.0 / f64::from(self.height);
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        writeln!(file, "{pixel_width}")?;
+        writeln!(file, "0")?;
+        writeln!(file, "0")?;
+        writeln!(file, "{}", -pixel_height)?;
+        writeln!(file, "{}", -180.0 + pixel_width / 2.0)?;
+        writeln!(file, "{}", 90.0 - pixel_height / 2.0)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Geographic position of a pixel centre.
+fn pixel_coordinates(row: u32, column: u32, width: u32, height: u32) -> LatLon {
+    LatLon::new(
+        90.0 - (f64::from(row) + 0.5) * 180.0 / f64::from(height),
+        -180.0 + (f64::from(column) + 0.5) * 360.0 / f64::from(width),
+    )
+}
+
+fn interpolate(grid: &MantleGrid, field: CellField, cell: usize, point: Vec3) -> f32 {
+    let mut total = 0.0;
+    let mut weights = 0.0;
+    for sample in std::iter::once(cell).chain(grid.neighbors[cell].iter().copied()) {
+        let distance = grid.cells[sample].center.angle_between(point);
+        if distance <= f32::EPSILON {
+            return field.value(&grid.cells[sample]);
+        }
+        let weight = 1.0 / (distance * distance);
+        total += weight * field.value(&grid.cells[sample]);
+        weights += weight;
+    }
+    total / weights
+}
+
+#[derive(Debug)]
+pub enum RasterError {
+    Io(io::Error),
+    Png(png::EncodingError),
+    Exr(exr::error::Error),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to write raster: {error}"),
+            Self::Png(error) => write!(f, "failed to encode PNG: {error}"),
+            Self::Exr(error) => write!(f, "failed to encode OpenEXR: {error}"),
+            Self::UnknownFormat(path) => write!(
+                f,
+                "cannot tell the raster format of {}; use a .png or .exr extension",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RasterError {}
+
+impl From<io::Error> for RasterError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<png::EncodingError> for RasterError {
+    fn from(error: png::EncodingError) -> Self {
+        Self::Png(error)
+    }
+}
+
+impl From<exr::error::Error> for RasterError {
+    fn from(error: exr::error::Error) -> Self {
+        Self::Exr(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_centres_are_offset_by_half_a_pixel() {
+        let north_west = pixel_coordinates(0, 0, 8, 4);
+        assert_eq!((north_west.latitude, north_west.longitude), (67.5, -157.5));
+        let south_east = pixel_coordinates(3, 7, 8, 4);
+        assert_eq!((south_east.latitude, south_east.longitude), (-67.5, 157.5));
+    }
+
+    #[test]
+    fn resampling_round_trips_through_the_raster() {
+        let mut grid = MantleGrid::new(6);
+        for cell in &mut grid.cells {
+            // Continuous across the antimeridian, where it is at its lowest.
+            cell.elevation = 1000.0 * cell.center.x;
+        }
+        let locator = CellLocator::new(&grid);
+        let raster = Raster::sample(&grid, &locator, CellField::Elevation, 128);
+        assert_eq!((raster.width, raster.height), (128, 64));
+
+        // Bilinear lookup of the raster at a position, wrapping round in longitude.
+        let lookup = |position: LatLon| {
+            let (width, height) = (raster.width as f64, raster.height as f64);
+            let column = (position.longitude + 180.0) / 360.0 * width - 0.5;
+            let row = ((90.0 - position.latitude) / 180.0 * height - 0.5).clamp(0.0, height - 1.0);
+            let (column0, row0) = (column.floor(), row.floor());
+            let (s, t) = ((column - column0) as f32, (row - row0) as f32);
+            let value = |row: f64, column: f64| {
+                let row = row.min(height - 1.0) as usize;
+                let column = column.rem_euclid(width) as usize;
+                raster.values[row * raster.width as usize + column]
+            };
+            let top = value(row0, column0) * (1.0 - s) + value(row0, column0 + 1.0) * s;
+            let bottom =
+                value(row0 + 1.0, column0) * (1.0 - s) + value(row0 + 1.0, column0 + 1.0) * s;
+            top * (1.0 - t) + bottom * t
+        };
+
+        let mut worst = 0.0f32;
+        for (cell, data) in grid.cells.iter().enumerate() {
+            let error = (lookup(grid.cell_lat_lon(cell)) - data.elevation).abs();
+            worst = worst.max(error);
+        }
+        assert!(worst < 10.0, "cell values came back up to {worst} m off");
+
+        // Both sides of the antimeridian hold the same values.
+        let width = raster.width as usize;
+        for row in raster.values.chunks_exact(width) {
+            assert!((row[0] - row[width - 1]).abs() < 10.0);
+        }
+    }
+}
@@ -0,0 +1,586 @@
+//! A minimal reader for single-band GeoTIFF rasters such as ETOPO elevation grids.
+//!
+//! Supports classic (not Big) TIFF in either byte order, stored in strips or tiles, uncompressed
+//! or Deflate compressed with optional horizontal differencing, and integer or floating-point
+//! samples of 8 to 64 bits. Only the first sample of each pixel is read.
+
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::ZlibDecoder;
+
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const PLANAR_CONFIGURATION: u16 = 284;
+const PREDICTOR: u16 = 317;
+const TILE_WIDTH: u16 = 322;
+const TILE_LENGTH: u16 = 323;
+const TILE_OFFSETS: u16 = 324;
+const TILE_BYTE_COUNTS: u16 = 325;
+const SAMPLE_FORMAT: u16 = 339;
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GDAL_NODATA: u16 = 42113;
+
+/// Geo key telling whether the tiepoint locates the corner or the centre of its pixel.
+const RASTER_TYPE_GEO_KEY: f64 = 1025.0;
+const RASTER_PIXEL_IS_POINT: f64 = 2.0;
+
+/// Placement of a raster in degrees of longitude and latitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoTransform {
+    /// Longitude of the western edge of the first column.
+    pub west: f64,
+    /// Latitude of the northern edge of the first row.
+    pub north: f64,
+    pub pixel_width: f64,
+    pub pixel_height: f64,
+}
+
+/// Samples of a single-band TIFF, row-major from the top-left corner.
+#[derive(Debug, Clone)]
+pub struct TiffRaster {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+    pub transform: Option<GeoTransform>,
+    pub no_data: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleFormat {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+/// One directory entry, with its values already decoded to numbers.
+struct Entry {
+    tag: u16,
+    values: Vec<f64>,
+    text: String,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], TiffError> {
+        offset
+            .checked_add(N)
+            .and_then(|end| self.data.get(offset..end))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| TiffError::Malformed(format!("unexpected end of file at byte {offset}")))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, TiffError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, TiffError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, TiffError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+
+    fn entry(&self, offset: usize) -> Result<Entry, TiffError> {
+        let tag = self.u16(offset)?;
+        let kind = self.u16(offset + 2)?;
+        let count = self.u32(offset + 4)? as usize;
+        let size: usize = match kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => {
+                return Err(TiffError::Unsupported(format!("type {kind} of tag {tag}")));
+            }
+        };
+        let outside = || TiffError::Malformed(format!("tag {tag} points outside the file"));
+        let length = size.checked_mul(count).ok_or_else(outside)?;
+        // Values that fit in four bytes are stored in the entry itself.
+        let start = if length <= 4 {
+            offset + 8
+        } else {
+            self.u32(offset + 8)? as usize
+        };
+        let end = start.checked_add(length).ok_or_else(outside)?;
+
+        if kind == 2 {
+            let text = self.data.get(start..end).ok_or_else(outside)?;
+            let text = String::from_utf8_lossy(text)
+                .trim_end_matches('\0')
+                .to_owned();
+            return Ok(Entry {
+                tag,
+                values: Vec::new(),
+                text,
+            });
+        }
+
+        let values = (0..count)
+            .map(|index| {
+                let at = start + index * size;
+                Ok(match kind {
+                    1 | 7 => f64::from(self.bytes::<1>(at)?[0]),
+                    6 => f64::from(self.bytes::<1>(at)?[0] as i8),
+                    3 => f64::from(self.u16(at)?),
+                    8 => f64::from(self.u16(at)? as i16),
+                    4 => f64::from(self.u32(at)?),
+                    9 => f64::from(self.u32(at)? as i32),
+                    11 => f64::from(f32::from_bits(self.u32(at)?)),
+                    12 => f64::from_bits(self.u64(at)?),
+                    5 => f64::from(self.u32(at)?) / f64::from(self.u32(at + 4)?),
+                    _ => f64::from(self.u32(at)? as i32) / f64::from(self.u32(at + 4)? as i32),
+                })
+            })
+            .collect::<Result<_, TiffError>>()?;
+        Ok(Entry {
+            tag,
+            values,
+            text: String::new(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum TiffError {
+    Io(io::Error),
+    /// A valid file using a feature this reader does not support.
+    Unsupported(String),
+    /// A file whose structure is inconsistent.
+    Malformed(String),
+}
+
+impl fmt::Display for TiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read TIFF: {error}"),
+            Self::Unsupported(message) => write!(f, "unsupported TIFF: {message}"),
+            Self::Malformed(message) => write!(f, "malformed TIFF: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TiffError {}
+
+impl From<io::Error> for TiffError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Reads the first image of a TIFF file.
+pub fn read_tiff(path: &Path) -> Result<TiffRaster, TiffError> {
+    let data = fs::read(path)?;
+    let little_endian = match data.get(0..4) {
+        Some(b"II*\0") => true,
+        Some(b"MM\0*") => false,
+        _ => {
+            return Err(TiffError::Unsupported("not a classic TIFF file".to_owned()));
+        }
+    };
+    let reader = Reader {
+        data: &data,
+        little_endian,
+    };
+
+    let directory = reader.u32(4)? as usize;
+    let count = usize::from(reader.u16(directory)?);
+    let entries = (0..count)
+        .map(|index| reader.entry(directory + 2 + index * 12))
+        .collect::<Result<Vec<_>, _>>()?;
+    let find = |tag: u16| entries.iter().find(|entry| entry.tag == tag);
+    let number = |tag: u16, default: Option<f64>| {
+        find(tag)
+            .and_then(|entry| entry.values.first().copied())
+            .or(default)
+            .ok_or_else(|| TiffError::Malformed(format!("missing tag {tag}")))
+    };
+    let list = |tag: u16| find(tag).map(|entry| entry.values.clone());
+
+    let width = number(IMAGE_WIDTH, None)? as usize;
+    let height = number(IMAGE_LENGTH, None)? as usize;
+    let bits = number(BITS_PER_SAMPLE, Some(1.0))? as usize;
+    let compression = number(COMPRESSION, Some(1.0))? as u32;
+    let samples_per_pixel = number(SAMPLES_PER_PIXEL, Some(1.0))? as usize;
+    let planar = number(PLANAR_CONFIGURATION, Some(1.0))? as u32;
+    let predictor = number(PREDICTOR, Some(1.0))? as u32;
+    let format = match number(SAMPLE_FORMAT, Some(1.0))? as u32 {
+        1 => SampleFormat::Unsigned,
+        2 => SampleFormat::Signed,
+        3 => SampleFormat::Float,
+        other => {
+            return Err(TiffError::Unsupported(format!("sample format {other}")));
+        }
+    };
+    if !matches!(bits, 8 | 16 | 32 | 64) || (format == SampleFormat::Float && bits < 32) {
+        return Err(TiffError::Unsupported(format!(
+            "sample size of {bits} bits"
+        )));
+    }
+    if !matches!(compression, 1 | 8 | 32946) {
+        return Err(TiffError::Unsupported(format!("compression {compression}")));
+    }
+    if !matches!(predictor, 1 | 2) || (predictor == 2 && format == SampleFormat::Float) {
+        return Err(TiffError::Unsupported(format!("predictor {predictor}")));
+    }
+    if width == 0 || height == 0 || samples_per_pixel == 0 {
+        return Err(TiffError::Malformed(
+            "zero image width, image length or samples per pixel".to_owned(),
+        ));
+    }
+    // Separate planes store the first band contiguously, as if the image had a single sample.
+    let samples_per_pixel = if planar == 2 { 1 } else { samples_per_pixel };
+    let bytes_per_sample = bits / 8;
+
+    // Strips are tiles as wide as the image.
+    let (tile_width, tile_height, offsets, byte_counts) = match list(TILE_OFFSETS) {
+        Some(offsets) => (
+            number(TILE_WIDTH, None)? as usize,
+            number(TILE_LENGTH, None)? as usize,
+            offsets,
+            list(TILE_BYTE_COUNTS).ok_or_else(|| missing("tile byte counts"))?,
+        ),
+        None => (
+            width,
+            (number(ROWS_PER_STRIP, Some(height as f64))? as usize).min(height),
+            list(STRIP_OFFSETS).ok_or_else(|| missing("strip offsets"))?,
+            list(STRIP_BYTE_COUNTS).ok_or_else(|| missing("strip byte counts"))?,
+        ),
+    };
+    if tile_width == 0 || tile_height == 0 {
+        return Err(TiffError::Malformed(
+            "zero tile width, tile length or rows per strip".to_owned(),
+        ));
+    }
+    let too_large = || TiffError::Malformed("image size overflows".to_owned());
+    let tiles_across = width.div_ceil(tile_width);
+    let tiles_down = height.div_ceil(tile_height);
+    let tiles = tiles_across.checked_mul(tiles_down).ok_or_else(too_large)?;
+    if offsets.len() < tiles || byte_counts.len() < offsets.len() {
+        return Err(TiffError::Malformed(
+            "too few strips or tiles for the image size".to_owned(),
+        ));
+    }
+
+    let mut values = vec![0.0f32; width.checked_mul(height).ok_or_else(too_large)?];
+    let row_bytes = tile_width
+        .checked_mul(samples_per_pixel * bytes_per_sample)
+        .ok_or_else(too_large)?;
+    let block_bytes = row_bytes.checked_mul(tile_height).ok_or_else(too_large)?;
+    for tile_row in 0..tiles_down {
+        for tile_column in 0..tiles_across {
+            let index = tile_row * tiles_across + tile_column;
+            let start = offsets[index] as usize;
+            let raw = start
+                .checked_add(byte_counts[index] as usize)
+                .and_then(|end| data.get(start..end))
+                .ok_or_else(|| {
+                    TiffError::Malformed("strip or tile points outside the file".to_owned())
+                })?;
+            let mut block = match compression {
+                1 => raw.to_vec(),
+                _ => {
+                    let mut block = Vec::new();
+                    ZlibDecoder::new(raw).read_to_end(&mut block)?;
+                    block
+                }
+            };
+            block.resize(block_bytes, 0);
+
+            for (y, row) in block.chunks_exact_mut(row_bytes).enumerate() {
+                let image_y = tile_row * tile_height + y;
+                if image_y >= height {
+                    break;
+                }
+                let mut samples: Vec<f64> = row
+                    .chunks_exact(bytes_per_sample)
+                    .map(|bytes| decode_sample(bytes, format, little_endian))
+                    .collect();
+                if predictor == 2 {
+                    undo_differencing(&mut samples, samples_per_pixel, bits, format);
+                }
+                for x in 0..tile_width {
+                    let image_x = tile_column * tile_width + x;
+                    if image_x < width {
+                        values[image_y * width + image_x] = samples[x * samples_per_pixel] as f32;
+                    }
+                }
+            }
+        }
+    }
+
+    // A tiepoint of a PixelIsPoint raster is at the centre of its pixel rather than its corner.
+    let corner =
+        if geo_key(list(GEO_KEY_DIRECTORY), RASTER_TYPE_GEO_KEY) == Some(RASTER_PIXEL_IS_POINT) {
+            0.5
+        } else {
+            0.0
+        };
+    let transform = match (list(MODEL_PIXEL_SCALE), list(MODEL_TIEPOINT)) {
+        (Some(scale), Some(tiepoint)) if scale.len() >= 2 && tiepoint.len() >= 6 => {
+            Some(GeoTransform {
+                west: tiepoint[3] - (tiepoint[0] + corner) * scale[0],
+                north: tiepoint[4] + (tiepoint[1] + corner) * scale[1],
+                pixel_width: scale[0],
+                pixel_height: scale[1],
+            })
+        }
+        _ => None,
+    };
+    let no_data = find(GDAL_NODATA).and_then(|entry| entry.text.trim().parse().ok());
+
+    Ok(TiffRaster {
+        width,
+        height,
+        values,
+        transform,
+        no_data,
+    })
+}
+
+fn missing(what: &str) -> TiffError {
+    TiffError::Malformed(format!("missing {what}"))
+}
+
+/// Value of a short geo key stored directly in the GeoKeyDirectory, which is a header of four
+/// shorts followed by `(key, location, count, value)` entries.
+fn geo_key(directory: Option<Vec<f64>>, key: f64) -> Option<f64> {
+    let directory = directory?;
+    let keys = directory.get(4..)?;
+    keys.chunks_exact(4)
+        .find(|entry| entry[0] == key && entry[1] == 0.0)
+        .map(|entry| entry[3])
+}
+
+fn decode_sample(bytes: &[u8], format: SampleFormat, little_endian: bool) -> f64 {
+    let mut buffer = [0u8; 8];
+    let size = bytes.len();
+    if little_endian {
+        buffer[..size].copy_from_slice(bytes);
+    } else {
+        for (target, &byte) in buffer[..size].iter_mut().zip(bytes.iter().rev()) {
+            *target = byte;
+        }
+    }
+    let bits = u64::from_le_bytes(buffer);
+    match (format, size) {
+        (SampleFormat::Float, 4) => f64::from(f32::from_bits(bits as u32)),
+        (SampleFormat::Float, _) => f64::from_bits(bits),
+        (SampleFormat::Unsigned, _) => bits as f64,
+        (SampleFormat::Signed, _) => {
+            // Sign-extend from the sample size.
+            let shift = 64 - 8 * size;
+            ((bits << shift) as i64 >> shift) as f64
+        }
+    }
+}
+
+/// Reverses the horizontal differencing predictor, which stores each sample as the difference to
+/// the same sample of the previous pixel, wrapping around at the sample size.
+fn undo_differencing(
+    samples: &mut [f64],
+    samples_per_pixel: usize,
+    bits: usize,
+    format: SampleFormat,
+) {
+    let modulus = 2f64.powi(bits as i32);
+    for index in samples_per_pixel..samples.len() {
+        let mut value = samples[index] + samples[index - samples_per_pixel];
+        value = value.rem_euclid(modulus);
+        if format == SampleFormat::Signed && value >= modulus / 2.0 {
+            value -= modulus;
+        }
+        samples[index] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    const GLOBE: GeoTransform = GeoTransform {
+        west: -180.0,
+        north: 90.0,
+        pixel_width: 90.0,
+        pixel_height: 90.0,
+    };
+
+    #[test]
+    fn reads_pixel_is_area_raster() {
+        let raster = read_tiff(&fixture("pixel_is_area.tif")).unwrap();
+        assert_eq!((raster.width, raster.height), (4, 2));
+        assert_eq!(raster.values, [1.0, 2.0, 3.0, 4.0, -1.0, -2.0, -3.0, -4.0]);
+        assert_eq!(raster.transform, Some(GLOBE));
+        assert_eq!(raster.no_data, None);
+    }
+
+    #[test]
+    fn moves_pixel_is_point_tiepoint_to_the_corner() {
+        let raster = read_tiff(&fixture("pixel_is_point.tif")).unwrap();
+        assert_eq!((raster.width, raster.height), (4, 2));
+        assert_eq!(
+            raster.values,
+            [100.0, 200.0, 300.0, -9999.0, -100.0, -200.0, -300.0, -400.0]
+        );
+        assert_eq!(raster.transform, Some(GLOBE));
+        assert_eq!(raster.no_data, Some(-9999.0));
+    }
+
+    /// Writes a little-endian TIFF with `entries` of type `(tag, type, values)` in its directory
+    /// and four zero bytes of pixel data at offset 8.
+    fn write_tiff(name: &str, entries: &[(u16, u16, &[f64])]) -> PathBuf {
+        let directory = 12;
+        let mut extra = directory + 2 + entries.len() * 12 + 4;
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&(directory as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        let mut values_data = Vec::new();
+        for &(tag, kind, values) in entries {
+            let bytes: Vec<u8> = values
+                .iter()
+                .flat_map(|&value| match kind {
+                    3 => (value as u16).to_le_bytes().to_vec(),
+                    4 => (value as u32).to_le_bytes().to_vec(),
+                    _ => value.to_le_bytes().to_vec(),
+                })
+                .collect();
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&kind.to_le_bytes());
+            data.extend_from_slice(&(values.len() as u32).to_le_bytes());
+            if bytes.len() <= 4 {
+                let mut inline = bytes;
+                inline.resize(4, 0);
+                data.extend_from_slice(&inline);
+            } else {
+                data.extend_from_slice(&(extra as u32).to_le_bytes());
+                extra += bytes.len();
+                values_data.extend(bytes);
+            }
+        }
+        data.extend_from_slice(&[0; 4]);
+        data.extend(values_data);
+
+        let path = std::env::temp_dir().join(format!("{name}_{}.tif", std::process::id()));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Reads a 2 by 2 image of bytes with `entries` replacing or adding to those of a single
+    /// strip.
+    fn read_strip_with(
+        name: &str,
+        entries: &[(u16, u16, &[f64])],
+    ) -> Result<TiffRaster, TiffError> {
+        let mut all: Vec<(u16, u16, &[f64])> = vec![
+            (IMAGE_WIDTH, 3, &[2.0]),
+            (IMAGE_LENGTH, 3, &[2.0]),
+            (BITS_PER_SAMPLE, 3, &[8.0]),
+            (STRIP_OFFSETS, 4, &[8.0]),
+            (ROWS_PER_STRIP, 3, &[2.0]),
+            (STRIP_BYTE_COUNTS, 4, &[4.0]),
+        ];
+        for &entry in entries {
+            all.retain(|&(tag, _, _)| tag != entry.0);
+            all.push(entry);
+        }
+        all.sort_by_key(|&(tag, _, _)| tag);
+        let path = write_tiff(name, &all);
+        let result = read_tiff(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    fn assert_malformed(result: Result<TiffRaster, TiffError>) {
+        match result {
+            Err(TiffError::Malformed(_)) => {}
+            other => panic!("expected a malformed TIFF, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reads_a_synthetic_strip() {
+        let raster = read_strip_with("tiff_strip", &[]).unwrap();
+        assert_eq!((raster.width, raster.height), (2, 2));
+        assert_eq!(raster.values, [0.0; 4]);
+    }
+
+    #[test]
+    fn rejects_zero_rows_per_strip() {
+        assert_malformed(read_strip_with("tiff_rows", &[(ROWS_PER_STRIP, 3, &[0.0])]));
+    }
+
+    #[test]
+    fn rejects_zero_tile_sizes() {
+        for (name, width, length) in [
+            ("tiff_tile_width", 0.0, 16.0),
+            ("tiff_tile_length", 16.0, 0.0),
+        ] {
+            assert_malformed(read_strip_with(
+                name,
+                &[
+                    (TILE_WIDTH, 4, &[width]),
+                    (TILE_LENGTH, 4, &[length]),
+                    (TILE_OFFSETS, 4, &[8.0]),
+                    (TILE_BYTE_COUNTS, 4, &[4.0]),
+                ],
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_zero_row_stride() {
+        assert_malformed(read_strip_with(
+            "tiff_stride",
+            &[(SAMPLES_PER_PIXEL, 3, &[0.0])],
+        ));
+    }
+
+    #[test]
+    fn rejects_strips_whose_end_overflows() {
+        // Stored as doubles, the byte count saturates to `usize::MAX` and its end overflows.
+        assert_malformed(read_strip_with(
+            "tiff_overflow",
+            &[(STRIP_BYTE_COUNTS, 12, &[1.0e30])],
+        ));
+    }
+}
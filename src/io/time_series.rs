@@ -0,0 +1,464 @@
+//! Per-step output of every per-cell field as a Zarr (version 2) store.
+//!
+//! The store is a directory of uncompressed little-endian arrays that `zarr` and `xarray` open
+//! directly. It holds the static grid geometry (cell centres, vertex positions, triangle
+//! connectivity and neighbours) and, for every field, a `(time, cell)` array with one chunk per
+//! written frame. Array metadata is rewritten after each frame, so an interrupted run still leaves
+//! a readable store.
+//!
+//! The hotspots of the latest frame are kept in the root group's attributes, from which
+//! `read_hotspots` restores them to start a new run.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    components::hotspot::Hotspot,
+    resources::{
+        mantle_grid::{CellField, MantleGrid},
+        plates::Plates,
+        simulation_clock::SimulationClock,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSeriesSettings {
+    /// Directory of the store. An existing directory is only replaced if it is empty or holds a
+    /// store written by an earlier run, unless `overwrite` is set.
+    pub path: PathBuf,
+    /// Number of steps between frames.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    /// Replace whatever directory is at `path`.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+fn default_interval() -> u64 {
+    1
+}
+
+/// Root group attribute that marks a store as written by [`TimeSeriesWriter`].
+const WRITER_ATTRIBUTE: &str = "time_series_writer";
+
+/// Whether the directory at `root` holds a store written by [`TimeSeriesWriter`].
+fn is_own_store(root: &Path) -> bool {
+    fs::read(root.join(".zattrs"))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .is_some_and(|attributes| attributes[WRITER_ATTRIBUTE] == env!("CARGO_PKG_NAME"))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DataType {
+    F32,
+    F64,
+    U32,
+    U64,
+}
+
+impl DataType {
+    fn code(self) -> &'static str {
+        match self {
+            Self::F32 => "<f4",
+            Self::F64 => "<f8",
+            Self::U32 => "<u4",
+            Self::U64 => "<u8",
+        }
+    }
+}
+
+/// One array of the store together with the bytes of the chunk being written.
+struct Variable {
+    name: String,
+    data_type: DataType,
+    /// Dimensions and their sizes, excluding the leading time dimension of frame variables.
+    dimensions: Vec<(&'static str, usize)>,
+    attributes: serde_json::Value,
+    data: Vec<u8>,
+}
+
+impl Variable {
+    fn new(
+        name: impl Into<String>,
+        data_type: DataType,
+        dimensions: Vec<(&'static str, usize)>,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            data_type,
+            dimensions,
+            attributes: json!({}),
+            data,
+        }
+    }
+
+    fn with_attributes(mut self, attributes: serde_json::Value) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Writes the array as a single chunk, or as chunk `frame` along time when `frame` is set.
+    fn write(&self, root: &Path, frame: Option<usize>) -> io::Result<()> {
+        let directory = root.join(&self.name);
+        fs::create_dir_all(&directory)?;
+
+        let mut names: Vec<&str> = self.dimensions.iter().map(|&(name, _)| name).collect();
+        let mut shape: Vec<usize> = self.dimensions.iter().map(|&(_, size)| size).collect();
+        let mut chunks = shape.clone();
+        let mut key: Vec<String> = vec!["0".to_owned(); shape.len()];
+        if let Some(frame) = frame {
+            names.insert(0, "time");
+            shape.insert(0, frame + 1);
+            chunks.insert(0, 1);
+            key.insert(0, frame.to_string());
+        }
+        fs::write(directory.join(key.join(".")), &self.data)?;
+
+        let metadata = json!({
+            "zarr_format": 2,
+            "shape": shape,
+            "chunks": chunks,
+            "dtype": self.data_type.code(),
+            "compressor": null,
+            "fill_value": 0,
+            "order": "C",
+            "filters": null,
+        });
+        let mut attributes = self.attributes.clone();
+        attributes["_ARRAY_DIMENSIONS"] = json!(names);
+        write_json(&directory.join(".zarray"), &metadata)?;
+        write_json(&directory.join(".zattrs"), &attributes)
+    }
+}
+
+/// A hotspot as it is kept in the store's attributes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HotspotRecord {
+    position: [f32; 3],
+    radius: f32,
+    uplift_rate: f32,
+    heating_rate: f32,
+    drift_axis: [f32; 3],
+    drift_rate: f32,
+}
+
+impl From<&Hotspot> for HotspotRecord {
+    fn from(hotspot: &Hotspot) -> Self {
+        Self {
+            position: hotspot.position.to_array(),
+            radius: hotspot.radius,
+            uplift_rate: hotspot.uplift_rate,
+            heating_rate: hotspot.heating_rate,
+            drift_axis: hotspot.drift_axis.to_array(),
+            drift_rate: hotspot.drift_rate,
+        }
+    }
+}
+
+impl From<HotspotRecord> for Hotspot {
+    fn from(record: HotspotRecord) -> Self {
+        Self {
+            position: Vec3::from(record.position).normalize_or(Vec3::Y),
+            radius: record.radius,
+            uplift_rate: record.uplift_rate,
+            heating_rate: record.heating_rate,
+            drift_axis: Vec3::from(record.drift_axis).normalize_or(Vec3::Y),
+            drift_rate: record.drift_rate,
+        }
+    }
+}
+
+/// Reads the hotspots of the latest frame written to the store at `path`.
+pub fn read_hotspots(path: &Path) -> io::Result<Vec<Hotspot>> {
+    let attributes: serde_json::Value = serde_json::from_slice(&fs::read(path.join(".zattrs"))?)?;
+    let records: Vec<HotspotRecord> = serde_json::from_value(attributes["hotspots"].clone())?;
+    Ok(records.into_iter().map(Hotspot::from).collect())
+}
+
+/// Appends frames of a run to a Zarr store.
+#[derive(Resource, Debug)]
+pub struct TimeSeriesWriter {
+    root: PathBuf,
+    /// Attributes of the root group, rewritten with the hotspots of every frame.
+    attributes: serde_json::Value,
+    interval: u64,
+    frames: usize,
+}
+
+impl TimeSeriesWriter {
+    /// Creates the store and writes the grid geometry. Unless `overwrite` is set, fails with
+    /// `AlreadyExists` rather than replace anything but an empty directory or a store this writer
+    /// created, so that a mistyped path cannot wipe unrelated data.
+    pub fn create(settings: &TimeSeriesSettings, grid: &MantleGrid) -> io::Result<Self> {
+        let root = settings.path.clone();
+        if root.exists() {
+            let replaceable = root.is_dir()
+                && (settings.overwrite
+                    || is_own_store(&root)
+                    || fs::read_dir(&root)?.next().is_none());
+            if !replaceable {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} exists and was not written by this simulator; remove it, choose \
+                         another path or set `overwrite = true`",
+                        root.display()
+                    ),
+                ));
+            }
+            fs::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(&root)?;
+        write_json(&root.join(".zgroup"), &json!({ "zarr_format": 2 }))?;
+        let attributes = json!({
+            "title": "Mantle convection and plate tectonics",
+            "source": env!("CARGO_PKG_NAME"),
+            "subdivisions": grid.subdivisions(),
+            WRITER_ATTRIBUTE: env!("CARGO_PKG_NAME"),
+            "hotspots": [],
+        });
+        write_json(&root.join(".zattrs"), &attributes)?;
+
+        let num_cells = grid.cells.len();
+        let points = grid.sphere.raw_points();
+        let centers: Vec<f32> = grid
+            .cells
+            .iter()
+            .flat_map(|cell| cell.center.to_array())
+            .collect();
+        let vertices: Vec<f32> = points
+            .iter()
+            .flat_map(|&point| Vec3::from(point).to_array())
+            .collect();
+        let neighbors: Vec<u32> = grid
+            .neighbors
+            .iter()
+            .flatten()
+            .map(|&neighbor| neighbor as u32)
+            .collect();
+        let areas: Vec<f32> = (0..num_cells).map(|cell| grid.cell_area(cell)).collect();
+
+        let geometry = [
+            Variable::new(
+                "cell_center",
+                DataType::F32,
+                vec![("cell", num_cells), ("xyz", 3)],
+                f32_bytes(&centers),
+            )
+            .with_attributes(json!({ "long_name": "unit vector to the cell centre" })),
+            Variable::new(
+                "vertex_position",
+                DataType::F32,
+                vec![("vertex", points.len()), ("xyz", 3)],
+                f32_bytes(&vertices),
+            )
+            .with_attributes(json!({ "long_name": "vertex position on the unit sphere" })),
+            Variable::new(
+                "cell_vertices",
+                DataType::U32,
+                vec![("cell", num_cells), ("corner", 3)],
+                u32_bytes(&grid.indices),
+            )
+            .with_attributes(json!({ "long_name": "vertices of the triangle backing the cell" })),
+            Variable::new(
+                "cell_neighbors",
+                DataType::U32,
+                vec![("cell", num_cells), ("side", 3)],
+                u32_bytes(&neighbors),
+            )
+            .with_attributes(json!({ "long_name": "cells sharing an edge with the cell" })),
+            Variable::new(
+                "cell_area",
+                DataType::F32,
+                vec![("cell", num_cells)],
+                f32_bytes(&areas),
+            )
+            .with_attributes(json!({ "long_name": "cell area on the unit sphere", "units": "sr" })),
+        ];
+        for variable in &geometry {
+            variable.write(&root, None)?;
+        }
+
+        Ok(Self {
+            root,
+            attributes,
+            interval: settings.interval.max(1),
+            frames: 0,
+        })
+    }
+
+    /// Whether a frame is due after `step` steps.
+    #[must_use]
+    pub fn is_due(&self, step: u64) -> bool {
+        step.is_multiple_of(self.interval)
+    }
+
+    /// Appends the current state as a new frame.
+    pub fn write_frame(
+        &mut self,
+        grid: &MantleGrid,
+        plates: &Plates,
+        hotspots: &[Hotspot],
+        clock: &SimulationClock,
+    ) -> io::Result<()> {
+        let num_cells = grid.cells.len();
+        let num_plates = plates.0.len();
+        let mut variables = vec![
+            Variable::new(
+                "time",
+                DataType::F64,
+                Vec::new(),
+                clock.elapsed.to_le_bytes().to_vec(),
+            )
+            .with_attributes(json!({ "long_name": "simulated time", "units": "yr" })),
+            Variable::new(
+                "step",
+                DataType::U64,
+                Vec::new(),
+                clock.step.to_le_bytes().to_vec(),
+            ),
+        ];
+        for field in CellField::ALL {
+            let variable = match field {
+                CellField::Plate => {
+                    let plates: Vec<u32> = grid.cells.iter().map(|c| c.plate as u32).collect();
+                    Variable::new(
+                        field.name(),
+                        DataType::U32,
+                        vec![("cell", num_cells)],
+                        u32_bytes(&plates),
+                    )
+                }
+                _ => Variable::new(
+                    field.name(),
+                    DataType::F32,
+                    vec![("cell", num_cells)],
+                    f32_bytes(&grid.field_values(field)),
+                ),
+            };
+            variables.push(match field.units() {
+                "" => variable,
+                units => variable.with_attributes(json!({ "units": units })),
+            });
+        }
+
+        let poles: Vec<f32> = plates
+            .0
+            .iter()
+            .flat_map(|plate| plate.euler_pole.to_array())
+            .collect();
+        let speeds: Vec<f32> = plates.0.iter().map(|plate| plate.angular_speed).collect();
+        variables.push(
+            Variable::new(
+                "euler_pole",
+                DataType::F32,
+                vec![("plate", num_plates), ("xyz", 3)],
+                f32_bytes(&poles),
+            )
+            .with_attributes(json!({ "long_name": "unit vector through the plate's Euler pole" })),
+        );
+        variables.push(
+            Variable::new(
+                "angular_speed",
+                DataType::F32,
+                vec![("plate", num_plates)],
+                f32_bytes(&speeds),
+            )
+            .with_attributes(json!({ "long_name": "plate rotation rate", "units": "rad/yr" })),
+        );
+
+        let hotspot_positions: Vec<f32> = hotspots
+            .iter()
+            .flat_map(|hotspot| hotspot.position.to_array())
+            .collect();
+        variables.push(
+            Variable::new(
+                "hotspot_position",
+                DataType::F32,
+                vec![("hotspot", hotspots.len()), ("xyz", 3)],
+                f32_bytes(&hotspot_positions),
+            )
+            .with_attributes(json!({ "long_name": "unit vector to the centre of the plume" })),
+        );
+
+        for variable in &variables {
+            variable.write(&self.root, Some(self.frames))?;
+        }
+        let records: Vec<HotspotRecord> = hotspots.iter().map(HotspotRecord::from).collect();
+        self.attributes["hotspots"] = serde_json::to_value(records)?;
+        write_json(&self.root.join(".zattrs"), &self.attributes)?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> io::Result<()> {
+    fs::write(path, serde_json::to_vec_pretty(value)?)
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn u32_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_only_replaces_empty_directories_and_stores() {
+        let directory = std::env::temp_dir().join(format!("time_series_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let grid = MantleGrid::new(1);
+        let settings = TimeSeriesSettings {
+            path: directory.clone(),
+            interval: 1,
+            overwrite: false,
+        };
+
+        fs::write(directory.join("notes.txt"), "keep me").unwrap();
+        let error = TimeSeriesWriter::create(&settings, &grid).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert!(directory.join("notes.txt").is_file());
+
+        fs::remove_file(directory.join("notes.txt")).unwrap();
+        TimeSeriesWriter::create(&settings, &grid).unwrap();
+        TimeSeriesWriter::create(&settings, &grid).unwrap();
+        assert!(directory.join(".zgroup").is_file());
+
+        // A Zarr group written by another tool is kept unless overwriting is asked for.
+        fs::remove_dir_all(&directory).unwrap();
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(".zgroup"), r#"{ "zarr_format": 2 }"#).unwrap();
+        fs::write(directory.join(".zattrs"), r#"{ "source": "elsewhere" }"#).unwrap();
+        let error = TimeSeriesWriter::create(&settings, &grid).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert!(directory.join(".zattrs").is_file());
+
+        let overwrite = TimeSeriesSettings {
+            overwrite: true,
+            ..settings
+        };
+        TimeSeriesWriter::create(&overwrite, &grid).unwrap();
+        assert!(is_own_store(&directory));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,4 +1,8 @@
+pub mod cli;
+pub mod components;
+pub mod io;
 pub mod materials;
 pub mod plugins;
 pub mod resources;
+pub mod simulation;
 pub mod systems;
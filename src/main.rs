@@ -1,30 +1,132 @@
-use bevy::{prelude::*, render::extract_resource::ExtractResourcePlugin};
+use std::path::PathBuf;
+
+use bevy::{log::LogPlugin, prelude::*, render::extract_resource::ExtractResourcePlugin};
 use bevy_panorbit_camera::PanOrbitCameraPlugin;
 use tectonic_plate_simulator::{
+    cli::Cli,
+    io::{plate_statistics::PlateStatisticsWriter, time_series::TimeSeriesWriter},
     materials::pressure_material::PressureMaterial,
-    plugins::pressure_solver::PressureSolverPlugin,
-    resources::vertex_pressure_buffer::VertexPressureBufferHandle,
+    plugins::{
+        brush::BrushPlugin, camera_controls::CameraControlsPlugin,
+        cell_inspector::CellInspectorPlugin, control_panel::ControlPanelPlugin,
+        field_statistics::FieldStatisticsPlugin, grid_debug_overlay::GridDebugOverlayPlugin,
+        map_view::MapViewPlugin, pressure_solver::PressureSolverPlugin,
+        simulation::SimulationPlugin, velocity_field::VelocityFieldPlugin,
+    },
+    resources::{
+        cell_field_buffer::CellFieldBufferHandle,
+        field_display::FieldDisplay,
+        simulation_config::{ConfigFile, SimulationConfig},
+        vertex_elevation_buffer::VertexElevationBufferHandle,
+        vertex_field_buffer::VertexFieldBufferHandle,
+    },
     systems::{
-        gizmos::{draw_triangle_grid, draw_triangle_grid_centers, draw_triangle_grid_neighbors},
-        setup::setup,
+        exports::{
+            record_plate_statistics, record_time_series, start_plate_statistics, start_time_series,
+            write_geojson_exports, write_mesh_exports, write_raster_exports,
+        },
+        field_display::{follow_light, toggle_range_lock, toggle_shading, update_field_display},
+        legend::{spawn_color_legend, update_color_legend},
+        setup::{setup, setup_simulation},
+        shortcuts::shortcuts_enabled,
+        simulation_clock::{exit_after_steps, run_finished, run_simulation_steps},
     },
 };
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
+fn main() -> AppExit {
+    let cli = Cli::parse_and_validate();
+    let config = match cli.simulation_config() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            return AppExit::error();
+        }
+    };
+
+    if cli.headless {
+        run_headless(config, cli.field_display())
+    } else {
+        run_windowed(config, cli.config.clone(), cli.field_display())
+    }
+}
+
+fn run_windowed(
+    config: SimulationConfig,
+    config_file: Option<PathBuf>,
+    display: FieldDisplay,
+) -> AppExit {
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins)
         .add_plugins(PanOrbitCameraPlugin)
         .add_plugins(MaterialPlugin::<PressureMaterial>::default())
-        .add_plugins(ExtractResourcePlugin::<VertexPressureBufferHandle>::default())
+        .add_plugins(ExtractResourcePlugin::<VertexFieldBufferHandle>::default())
+        .add_plugins(ExtractResourcePlugin::<CellFieldBufferHandle>::default())
+        .add_plugins(ExtractResourcePlugin::<VertexElevationBufferHandle>::default())
         .add_plugins(PressureSolverPlugin)
-        .add_systems(Startup, setup)
-        // .add_systems(
-        //     Update,
-        //     (
-        // draw_triangle_grid,
-        // draw_triangle_grid_centers,
-        // draw_triangle_grid_neighbors,
-        // ),
-        // )
-        .run();
+        .add_plugins(FieldStatisticsPlugin)
+        .add_plugins(SimulationPlugin)
+        .add_plugins(ControlPanelPlugin)
+        .add_plugins(GridDebugOverlayPlugin)
+        .add_plugins(CellInspectorPlugin)
+        .add_plugins(BrushPlugin)
+        .add_plugins(VelocityFieldPlugin)
+        .add_plugins(MapViewPlugin)
+        .add_plugins(CameraControlsPlugin)
+        .insert_resource(config.camera.clone())
+        .insert_resource(config)
+        .insert_resource(display)
+        .add_systems(
+            Startup,
+            ((setup_simulation, setup).chain(), spawn_color_legend),
+        )
+        .add_systems(
+            Update,
+            (
+                (toggle_range_lock, toggle_shading).run_if(shortcuts_enabled),
+                update_field_display,
+                update_color_legend,
+                follow_light,
+            )
+                .chain(),
+        );
+    if let Some(path) = config_file {
+        app.insert_resource(ConfigFile(path));
+    }
+    app.run()
+}
+
+/// Runs only the CPU-side simulation, as fast as possible, until the configured number of steps.
+fn run_headless(config: SimulationConfig, display: FieldDisplay) -> AppExit {
+    App::new()
+        .add_plugins(MinimalPlugins)
+        .add_plugins(LogPlugin::default())
+        .add_plugins(SimulationPlugin)
+        .insert_resource(config)
+        .insert_resource(display)
+        .add_systems(
+            Startup,
+            (
+                setup_simulation,
+                (start_time_series, start_plate_statistics),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                record_time_series.run_if(resource_exists::<TimeSeriesWriter>),
+                record_plate_statistics.run_if(resource_exists::<PlateStatisticsWriter>),
+                (
+                    write_raster_exports,
+                    write_mesh_exports,
+                    write_geojson_exports,
+                    exit_after_steps,
+                )
+                    .chain()
+                    .run_if(run_finished),
+            )
+                .chain()
+                .after(run_simulation_steps),
+        )
+        .run()
 }
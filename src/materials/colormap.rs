@@ -0,0 +1,154 @@
+//! Colormaps for the planet material, uploaded as one-dimensional textures.
+//!
+//! Continuous colormaps are interpolated from evenly spaced samples of the matplotlib colormaps
+//! of the same name into a 256-texel ramp. The categorical colormap holds one texel per colour
+//! and is indexed by the rounded value, so it suits identifiers such as plate ids.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::mantle_grid::CellField;
+
+/// Number of texels of a continuous colormap texture.
+const RAMP_TEXELS: usize = 256;
+
+const VIRIDIS: [u32; 9] = [
+    0x44_01_54, 0x47_2d_7b, 0x3b_52_8b, 0x2c_72_8e, 0x21_91_8c, 0x28_ae_80, 0x5e_c9_62, 0xad_dc_30,
+    0xfd_e7_25,
+];
+
+const CIVIDIS: [u32; 9] = [
+    0x00_22_4e, 0x12_35_70, 0x3b_49_6c, 0x57_5d_6d, 0x70_71_73, 0x8a_86_78, 0xa5_9c_74, 0xc3_b3_69,
+    0xfe_e8_38,
+];
+
+/// Blue to white to red, from matplotlib's `RdBu` reversed.
+const DIVERGING: [u32; 9] = [
+    0x21_66_ac, 0x43_93_c3, 0x92_c5_de, 0xd1_e5_f0, 0xf7_f7_f7, 0xfd_db_c7, 0xf4_a5_82, 0xd6_60_4d,
+    0xb2_18_2b,
+];
+
+/// Matplotlib's `tab20`, darker shades first so that neighbouring ids differ in hue.
+const CATEGORICAL: [u32; 20] = [
+    0x1f_77_b4, 0xff_7f_0e, 0x2c_a0_2c, 0xd6_27_28, 0x94_67_bd, 0x8c_56_4b, 0xe3_77_c2, 0x7f_7f_7f,
+    0xbc_bd_22, 0x17_be_cf, 0xae_c7_e8, 0xff_bb_78, 0x98_df_8a, 0xff_98_96, 0xc5_b0_d5, 0xc4_9c_94,
+    0xf7_b6_d2, 0xc7_c7_c7, 0xdb_db_8d, 0x9e_da_e5,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Cividis,
+    /// Centred on the middle of the range, for signed fields such as elevation.
+    Diverging,
+    /// Distinct colours for integer identifiers.
+    Categorical,
+}
+
+impl Colormap {
+    pub const ALL: [Self; 4] = [
+        Self::Viridis,
+        Self::Cividis,
+        Self::Diverging,
+        Self::Categorical,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Viridis => "viridis",
+            Self::Cividis => "cividis",
+            Self::Diverging => "diverging",
+            Self::Categorical => "categorical",
+        }
+    }
+
+    /// The colormap that suits a field best.
+    #[must_use]
+    pub fn default_for(field: CellField) -> Self {
+        match field {
+            CellField::Elevation => Self::Diverging,
+            CellField::Plate => Self::Categorical,
+            CellField::Pressure | CellField::Temperature | CellField::Age => Self::Viridis,
+        }
+    }
+
+    #[must_use]
+    pub fn is_categorical(self) -> bool {
+        self == Self::Categorical
+    }
+
+    /// Number of texels of the colormap's texture.
+    #[must_use]
+    pub fn texels(self) -> usize {
+        match self {
+            Self::Categorical => CATEGORICAL.len(),
+            _ => RAMP_TEXELS,
+        }
+    }
+
+    /// Colour at `t` in [0, 1] for continuous colormaps, or of category `t * texels` for the
+    /// categorical one.
+    #[must_use]
+    pub fn color(self, t: f32) -> Srgba {
+        let samples: &[u32] = match self {
+            Self::Viridis => &VIRIDIS,
+            Self::Cividis => &CIVIDIS,
+            Self::Diverging => &DIVERGING,
+            Self::Categorical => {
+                let index = (t * CATEGORICAL.len() as f32) as usize;
+                return srgb(CATEGORICAL[index.min(CATEGORICAL.len() - 1)]);
+            }
+        };
+        let position = t.clamp(0.0, 1.0) * (samples.len() - 1) as f32;
+        let index = (position as usize).min(samples.len() - 2);
+        srgb(samples[index]).mix(&srgb(samples[index + 1]), position - index as f32)
+    }
+
+    /// One-dimensional sRGB texture of the colormap, sampled linearly for continuous colormaps and
+    /// at texel centres for the categorical one.
+    #[must_use]
+    pub fn image(self) -> Image {
+        let texels = self.texels();
+        let data = (0..texels)
+            .flat_map(|texel| {
+                let t = if self.is_categorical() {
+                    (texel as f32 + 0.5) / texels as f32
+                } else {
+                    texel as f32 / (texels - 1) as f32
+                };
+                self.color(t).to_u8_array()
+            })
+            .collect();
+        let mut image = Image::new(
+            Extent3d {
+                width: texels as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D1,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        image.sampler = if self.is_categorical() {
+            ImageSampler::nearest()
+        } else {
+            ImageSampler::linear()
+        };
+        image
+    }
+}
+
+fn srgb(hex: u32) -> Srgba {
+    let [_, red, green, blue] = hex.to_be_bytes();
+    Srgba::rgb_u8(red, green, blue)
+}
@@ -0,0 +1,43 @@
+use bevy::{
+    prelude::*,
+    render::{render_resource::AsBindGroup, storage::ShaderStorageBuffer},
+    shader::ShaderRef,
+};
+
+use crate::materials::pressure_material::ColorScale;
+
+/// Colours the flat map from the same field buffers, colour scale and colormap as the planet's
+/// `PressureMaterial`. The map has vertices of its own, so it carries which grid vertex and cell
+/// each of them samples.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct MapMaterial {
+    /// Values of the displayed field at each grid vertex, shared with the planet.
+    #[storage(0, read_only, visibility(vertex))]
+    pub vertex_values: Handle<ShaderStorageBuffer>,
+    #[uniform(1)]
+    pub color_scale: ColorScale,
+    #[texture(2, dimension = "1d")]
+    #[sampler(3)]
+    pub colormap: Handle<Image>,
+    /// Values of the displayed field in each cell, shared with the planet.
+    #[storage(4, read_only, visibility(vertex))]
+    pub cell_values: Handle<ShaderStorageBuffer>,
+    /// Grid vertex behind each vertex of the map mesh.
+    #[storage(5, read_only, visibility(vertex))]
+    pub map_vertices: Handle<ShaderStorageBuffer>,
+    /// Cell each vertex of the map mesh belongs to.
+    #[storage(6, read_only, visibility(vertex))]
+    pub map_cells: Handle<ShaderStorageBuffer>,
+    /// 1 to colour each cell by its own value, 0 to interpolate the values at its corners.
+    #[uniform(7)]
+    pub flat_shading: u32,
+}
+
+impl Material for MapMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/map_material.wgsl".into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        "shaders/map_material.wgsl".into()
+    }
+}
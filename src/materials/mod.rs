@@ -1 +1,4 @@
+pub mod colormap;
+pub mod map_material;
 pub mod pressure_material;
+pub mod velocity_arrow_material;
@@ -1,13 +1,68 @@
 use bevy::{
     prelude::*,
-    render::{render_resource::AsBindGroup, storage::ShaderStorageBuffer},
+    render::{
+        render_resource::{AsBindGroup, ShaderType},
+        storage::ShaderStorageBuffer,
+    },
     shader::ShaderRef,
 };
 
+/// Vertical exaggeration applied to elevation when displacing the sphere, so that relief of a few
+/// kilometres is visible on a unit-radius planet.
+pub const DEFAULT_ELEVATION_EXAGGERATION: f32 = 40.0;
+
+/// How the shader maps a field value onto the colormap texture.
+#[derive(Debug, Clone, Copy, PartialEq, ShaderType)]
+pub struct ColorScale {
+    /// Values at the two ends of a continuous colormap.
+    pub min: f32,
+    pub max: f32,
+    /// Number of colours of a categorical colormap, indexed by the rounded value, or 0 for a
+    /// continuous one.
+    pub categories: u32,
+}
+
+impl ColorScale {
+    /// Position of `value` along the colormap in [0, 1], as `colormap_color` in
+    /// `pressure_material.wgsl` computes it before sampling.
+    #[must_use]
+    pub fn position(&self, value: f32) -> f32 {
+        if self.categories > 0 {
+            let category = value.round().max(0.0) as u32 % self.categories;
+            (category as f32 + 0.5) / self.categories as f32
+        } else {
+            let span = (self.max - self.min).max(1e-6);
+            ((value - self.min) / span).clamp(0.0, 1.0)
+        }
+    }
+}
+
 #[derive(Asset, TypePath, AsBindGroup, Clone)]
 pub struct PressureMaterial {
+    /// Values of the displayed field at each vertex.
     #[storage(0, read_only, visibility(vertex))]
-    pub vertex_pressure: Handle<ShaderStorageBuffer>,
+    pub vertex_values: Handle<ShaderStorageBuffer>,
+    #[storage(1, read_only, visibility(vertex))]
+    pub vertex_elevation: Handle<ShaderStorageBuffer>,
+    #[uniform(2)]
+    pub elevation_exaggeration: f32,
+    #[uniform(3)]
+    pub color_scale: ColorScale,
+    #[texture(4, dimension = "1d")]
+    #[sampler(5)]
+    pub colormap: Handle<Image>,
+    /// Values of the displayed field in each cell, for flat shading.
+    #[storage(6, read_only, visibility(vertex))]
+    pub cell_values: Handle<ShaderStorageBuffer>,
+    /// Grid vertex behind each vertex of the mesh, which has three of its own per cell.
+    #[storage(7, read_only, visibility(vertex))]
+    pub mesh_vertices: Handle<ShaderStorageBuffer>,
+    /// 1 to colour each cell by its own value, 0 to interpolate the values at its corners.
+    #[uniform(8)]
+    pub flat_shading: u32,
+    /// World-space position of the point light, kept in step with it by `follow_light`.
+    #[uniform(9)]
+    pub light_position: Vec3,
 }
 
 impl Material for PressureMaterial {
@@ -0,0 +1,23 @@
+use bevy::{prelude::*, render::render_resource::AsBindGroup, shader::ShaderRef};
+
+/// `MeshTag` of an arrow at the top of the colour scale. Arrows carry their speed, relative to the
+/// fastest one, as a fraction of this in their tag.
+pub const ARROW_TAG_SCALE: u32 = u16::MAX as u32;
+
+/// Unlit material shared by every velocity arrow, so that all of them are drawn as instances of a
+/// single mesh. Each arrow's colour comes from its `MeshTag`.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct VelocityArrowMaterial {
+    #[texture(0, dimension = "1d")]
+    #[sampler(1)]
+    pub colormap: Handle<Image>,
+}
+
+impl Material for VelocityArrowMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/velocity_arrow.wgsl".into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        "shaders/velocity_arrow.wgsl".into()
+    }
+}
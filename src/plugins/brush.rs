@@ -0,0 +1,185 @@
+use bevy::{
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        extract_resource::ExtractResourcePlugin,
+        render_resource::{
+            BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, BufferBindingType,
+            BufferInitDescriptor, BufferUsages, CachedComputePipelineId, ComputePipelineDescriptor,
+            PipelineCache, ShaderStages,
+        },
+        renderer::{RenderDevice, RenderQueue},
+    },
+};
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+
+use crate::{
+    plugins::{planet_cursor::PlanetCursorPlugin, pressure_solver::dispatch_pressure_solver},
+    resources::{
+        brush::{Brush, PressureEdits},
+        mantle_grid::MantleGrid,
+        pressure_buffers::{PressureBuffers, prepare_buffers},
+    },
+    systems::{
+        brush::{brush_window, draw_brush, paint_with_brush, toggle_brush},
+        shortcuts::shortcuts_enabled,
+    },
+};
+
+/// Brush tools for authoring scenarios: with the brush switched on with B, dragging over the
+/// planet paints pressure, plate membership, crust or heat into the cells under it.
+pub struct BrushPlugin;
+
+impl Plugin for BrushPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        if !app.is_plugin_added::<PlanetCursorPlugin>() {
+            app.add_plugins(PlanetCursorPlugin);
+        }
+
+        app.add_plugins(ExtractResourcePlugin::<PressureEdits>::default())
+            .init_resource::<Brush>()
+            .init_resource::<PressureEdits>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                (brush_window, paint_with_brush)
+                    .chain()
+                    .run_if(resource_exists::<MantleGrid>),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_brush.run_if(shortcuts_enabled),
+                    draw_brush.run_if(|brush: Res<Brush>| brush.active),
+                )
+                    .chain()
+                    .run_if(resource_exists::<MantleGrid>),
+            );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_systems(
+            Render,
+            (prepare_pressure_edit_pipeline, apply_pressure_edits)
+                .chain()
+                .after(prepare_buffers)
+                .before(dispatch_pressure_solver)
+                .in_set(RenderSystems::Prepare),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct PressureEditPipeline {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline_id: CachedComputePipelineId,
+}
+
+fn prepare_pressure_edit_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    asset_server: Res<AssetServer>,
+    pipeline: Option<Res<PressureEditPipeline>>,
+) {
+    if pipeline.is_some() {
+        return;
+    }
+
+    let shader = asset_server.load("shaders/pressure_edits.wgsl");
+    let storage = |binding: u32, read_only: bool| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    let bind_group_layout = render_device.create_bind_group_layout(
+        "pressure_edit_bind_group_layout",
+        // pressure, edited cells, pressure changes
+        &[storage(0, false), storage(1, true), storage(2, true)],
+    );
+
+    let pipeline_id = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("pressure_edit_pipeline".into()),
+        layout: vec![bind_group_layout.clone()],
+        push_constant_ranges: vec![],
+        shader,
+        shader_defs: vec![],
+        entry_point: Some("main".into()),
+        zero_initialize_workgroup_memory: true,
+    });
+
+    commands.insert_resource(PressureEditPipeline {
+        bind_group_layout,
+        pipeline_id,
+    });
+}
+
+/// Adds the pressure changes painted this frame to the buffer the solver reads next. The change
+/// is applied on the GPU, to the latest pressures, because those read back lag a frame or more
+/// behind them.
+fn apply_pressure_edits(
+    mut edits: ResMut<PressureEdits>,
+    pipeline: Res<PressureEditPipeline>,
+    buffers: Res<PressureBuffers>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if edits.0.is_empty() {
+        return;
+    }
+    let Some(compute_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.pipeline_id) else {
+        return;
+    };
+
+    let (cells, deltas): (Vec<u32>, Vec<f32>) = edits.0.drain(..).unzip();
+    let create_buffer = |label: &'static str, contents: &[u8]| {
+        render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage: BufferUsages::STORAGE,
+        })
+    };
+    let cells_buffer = create_buffer("pressure_edit_cells", bytemuck::cast_slice(&cells));
+    let deltas_buffer = create_buffer("pressure_edit_deltas", bytemuck::cast_slice(&deltas));
+    let pressure_buffer = if buffers.current_read {
+        &buffers.pressure_buffer_a
+    } else {
+        &buffers.pressure_buffer_b
+    };
+
+    let bind_group = render_device.create_bind_group(
+        "pressure_edit_bind_group",
+        &pipeline.bind_group_layout,
+        &[
+            BindGroupEntry {
+                binding: 0,
+                resource: pressure_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: cells_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: deltas_buffer.as_entire_binding(),
+            },
+        ],
+    );
+
+    let mut encoder = render_device.create_command_encoder(&Default::default());
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+        compute_pass.set_pipeline(compute_pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups((cells.len() as u32).div_ceil(64), 1, 1);
+    }
+
+    render_queue.submit(std::iter::once(encoder.finish()));
+}
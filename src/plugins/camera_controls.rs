@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+
+use crate::{
+    resources::camera_settings::{CameraFlight, CameraSettings},
+    systems::camera::{
+        apply_camera_settings, auto_rotate_camera, camera_window, fly_camera,
+        keyboard_camera_controls,
+    },
+};
+
+/// Bookmarks, fly-to animations, auto-rotation and keyboard navigation for the globe's
+/// `PanOrbitCamera`, configured by `CameraSettings`.
+pub struct CameraControlsPlugin;
+
+impl Plugin for CameraControlsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+
+        app.init_resource::<CameraSettings>()
+            .init_resource::<CameraFlight>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                (camera_window, keyboard_camera_controls).chain(),
+            )
+            .add_systems(
+                Update,
+                (apply_camera_settings, fly_camera, auto_rotate_camera).chain(),
+            );
+    }
+}
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+
+use crate::{
+    plugins::planet_cursor::PlanetCursorPlugin,
+    resources::{cell_inspector::CellInspector, mantle_grid::MantleGrid},
+    systems::inspector::{draw_inspected_cells, inspect_cells},
+};
+
+/// Hovering over the planet shows the cell under the cursor in a tooltip; clicking it pins the
+/// cell in a window of its own.
+pub struct CellInspectorPlugin;
+
+impl Plugin for CellInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        if !app.is_plugin_added::<PlanetCursorPlugin>() {
+            app.add_plugins(PlanetCursorPlugin);
+        }
+
+        app.init_resource::<CellInspector>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                inspect_cells.run_if(resource_exists::<MantleGrid>),
+            )
+            .add_systems(
+                Update,
+                draw_inspected_cells.run_if(resource_exists::<MantleGrid>),
+            );
+    }
+}
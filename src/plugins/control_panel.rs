@@ -0,0 +1,38 @@
+use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+
+use crate::{
+    resources::{mantle_grid::MantleGrid, overlays::Overlays},
+    systems::{
+        control_panel::control_panel,
+        gizmos::{draw_hotspots, draw_plate_boundaries},
+    },
+};
+
+/// Side panel controlling the windowed viewer, and the gizmo overlays it toggles.
+pub struct ControlPanelPlugin;
+
+impl Plugin for ControlPanelPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+
+        app.init_resource::<Overlays>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                control_panel.run_if(resource_exists::<MantleGrid>),
+            )
+            .add_systems(
+                Update,
+                (
+                    draw_plate_boundaries.run_if(|overlays: Res<Overlays>| overlays.boundaries),
+                    draw_hotspots.run_if(|overlays: Res<Overlays>| overlays.hotspots),
+                )
+                    .run_if(resource_exists::<MantleGrid>),
+            );
+    }
+}
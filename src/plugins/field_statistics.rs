@@ -0,0 +1,201 @@
+use bevy::{
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        extract_resource::ExtractResourcePlugin,
+        gpu_readback::{Readback, ReadbackComplete},
+        render_asset::RenderAssets,
+        render_resource::{
+            BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, BufferBindingType,
+            BufferUsages, CachedComputePipelineId, ComputePipelineDescriptor, PipelineCache,
+            ShaderStages,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        storage::{GpuShaderStorageBuffer, ShaderStorageBuffer},
+    },
+};
+
+use crate::{
+    plugins::pressure_solver::dispatch_pressure_solver,
+    resources::{
+        field_display::FieldDisplay,
+        field_statistics::{FieldStatistics, FieldStatisticsBufferHandle, STATISTICS_WORDS},
+        pressure_buffers::PressureBuffers,
+    },
+};
+
+/// Reduces the displayed field to its range and histogram on the GPU every frame, and reads the
+/// result back into the main-world `FieldStatistics` resource once it is available.
+pub struct FieldStatisticsPlugin;
+
+impl Plugin for FieldStatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ExtractResourcePlugin::<FieldStatisticsBufferHandle>::default())
+            .add_systems(Startup, setup_field_statistics);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_systems(
+            Render,
+            (prepare_field_statistics_pipeline, dispatch_field_statistics)
+                .chain()
+                .after(dispatch_pressure_solver)
+                .in_set(RenderSystems::Prepare),
+        );
+    }
+}
+
+fn setup_field_statistics(
+    mut commands: Commands,
+    mut storage_buffers: ResMut<Assets<ShaderStorageBuffer>>,
+) {
+    let mut statistics_buffer = ShaderStorageBuffer::from(vec![0u32; STATISTICS_WORDS]);
+    statistics_buffer.buffer_description.usage |=
+        BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC;
+    let statistics_buffer = storage_buffers.add(statistics_buffer);
+    commands.insert_resource(FieldStatisticsBufferHandle(statistics_buffer.clone()));
+
+    // The readback stays on the entity, so the buffer is copied back every frame.
+    commands.spawn(Readback::buffer(statistics_buffer)).observe(
+        |event: On<ReadbackComplete>,
+         mut commands: Commands,
+         statistics: Option<ResMut<FieldStatistics>>| {
+            let words: Vec<u32> = event.to_shader_type();
+            let Some(latest) = FieldStatistics::from_words(&words) else {
+                return;
+            };
+            if latest.is_empty() {
+                return;
+            }
+            match statistics {
+                Some(mut statistics) => {
+                    statistics.set_if_neq(latest);
+                }
+                None => commands.insert_resource(latest),
+            }
+        },
+    );
+}
+
+#[derive(Resource)]
+pub struct FieldStatisticsPipeline {
+    pub bind_group_layout: BindGroupLayout,
+    pub range_pipeline_id: CachedComputePipelineId,
+    pub histogram_pipeline_id: CachedComputePipelineId,
+}
+
+fn prepare_field_statistics_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    asset_server: Res<AssetServer>,
+    pipeline: Option<Res<FieldStatisticsPipeline>>,
+) {
+    if pipeline.is_some() {
+        return;
+    }
+
+    let shader = asset_server.load("shaders/field_statistics.wgsl");
+    let bind_group_layout = render_device.create_bind_group_layout(
+        "field_statistics_bind_group_layout",
+        &[
+            // cell values
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // statistics
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    );
+
+    let queue_pipeline = |label: &'static str, entry_point: &'static str| {
+        pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some(label.into()),
+            layout: vec![bind_group_layout.clone()],
+            push_constant_ranges: vec![],
+            shader: shader.clone(),
+            shader_defs: vec![],
+            entry_point: Some(entry_point.into()),
+            zero_initialize_workgroup_memory: true,
+        })
+    };
+    let range_pipeline_id = queue_pipeline("field_range_pipeline", "range");
+    let histogram_pipeline_id = queue_pipeline("field_histogram_pipeline", "histogram");
+
+    commands.insert_resource(FieldStatisticsPipeline {
+        bind_group_layout,
+        range_pipeline_id,
+        histogram_pipeline_id,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dispatch_field_statistics(
+    pipeline: Res<FieldStatisticsPipeline>,
+    buffers: Res<PressureBuffers>,
+    display: Res<FieldDisplay>,
+    statistics_buffer_handle: Res<FieldStatisticsBufferHandle>,
+    gpu_buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let (Some(range_pipeline), Some(histogram_pipeline)) = (
+        pipeline_cache.get_compute_pipeline(pipeline.range_pipeline_id),
+        pipeline_cache.get_compute_pipeline(pipeline.histogram_pipeline_id),
+    ) else {
+        return;
+    };
+
+    let Some(statistics_gpu_buffer) = gpu_buffers.get(&statistics_buffer_handle.0) else {
+        return;
+    };
+
+    render_queue.write_buffer(
+        &statistics_gpu_buffer.buffer,
+        0,
+        bytemuck::cast_slice(&FieldStatistics::reset_words(display.field)),
+    );
+
+    let bind_group = render_device.create_bind_group(
+        "field_statistics_bind_group",
+        &pipeline.bind_group_layout,
+        &[
+            BindGroupEntry {
+                binding: 0,
+                resource: buffers.cell_values(display.field).as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: statistics_gpu_buffer.buffer.as_entire_binding(),
+            },
+        ],
+    );
+
+    // The histogram bins need the final range, so each reduction gets a pass of its own.
+    let workgroups = buffers.num_cells.div_ceil(64);
+    let mut encoder = render_device.create_command_encoder(&Default::default());
+    for compute_pipeline in [range_pipeline, histogram_pipeline] {
+        let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+        compute_pass.set_pipeline(compute_pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(workgroups, 1, 1);
+    }
+
+    render_queue.submit(std::iter::once(encoder.finish()));
+}
@@ -0,0 +1,140 @@
+//! Debug gizmos for the grid's triangles, centres and neighbour links.
+//!
+//! Each overlay is a gizmo config group of its own, so it can be toggled and styled through
+//! `GizmoConfigStore` from the control panel or with the G, C and N keys. Drawing every line of a
+//! fine grid each frame is too slow, so only the cells facing the camera, or those near the cursor,
+//! are drawn; V switches between the two.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::{
+    plugins::planet_cursor::PlanetCursorPlugin,
+    resources::{mantle_grid::MantleGrid, planet_cursor::PlanetCursor},
+    systems::{
+        gizmos::{draw_triangle_grid, draw_triangle_grid_centers, draw_triangle_grid_neighbors},
+        shortcuts::shortcuts_enabled,
+    },
+};
+
+/// Angular radius around the cursor within which cells are drawn by default, in radians.
+pub const DEFAULT_CURSOR_RADIUS: f32 = 0.2;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct GridLineGizmos;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct CellCenterGizmos;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct NeighborGizmos;
+
+/// Which cells the grid overlays are drawn for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayCulling {
+    /// Cells on the hemisphere seen by the camera.
+    FacingCamera,
+    /// Cells within an angular radius, in radians, of the point under the cursor.
+    NearCursor { radius: f32 },
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GridDebugOverlay {
+    pub culling: OverlayCulling,
+}
+
+impl Default for GridDebugOverlay {
+    fn default() -> Self {
+        Self {
+            culling: OverlayCulling::FacingCamera,
+        }
+    }
+}
+
+pub struct GridDebugOverlayPlugin;
+
+impl Plugin for GridDebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<PlanetCursorPlugin>() {
+            app.add_plugins(PlanetCursorPlugin);
+        }
+
+        // Drawn through the planet, so that lines on sunken ocean floor stay visible.
+        let hidden = || GizmoConfig {
+            enabled: false,
+            depth_bias: -0.5,
+            ..default()
+        };
+        app.insert_gizmo_config(GridLineGizmos, hidden())
+            .insert_gizmo_config(CellCenterGizmos, hidden())
+            .insert_gizmo_config(NeighborGizmos, hidden())
+            .init_resource::<GridDebugOverlay>()
+            .add_systems(
+                Update,
+                (
+                    toggle_grid_overlays.run_if(shortcuts_enabled),
+                    (
+                        draw_triangle_grid.run_if(gizmos_enabled::<GridLineGizmos>),
+                        draw_triangle_grid_centers.run_if(gizmos_enabled::<CellCenterGizmos>),
+                        draw_triangle_grid_neighbors.run_if(gizmos_enabled::<NeighborGizmos>),
+                    )
+                        .run_if(resource_exists::<MantleGrid>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn gizmos_enabled<T: GizmoConfigGroup>(store: Res<GizmoConfigStore>) -> bool {
+    store.config::<T>().0.enabled
+}
+
+/// Toggles the grid overlays with G, C and N, and their culling with V.
+fn toggle_grid_overlays(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut store: ResMut<GizmoConfigStore>,
+    mut overlay: ResMut<GridDebugOverlay>,
+) {
+    let toggle = |enabled: &mut bool| *enabled = !*enabled;
+    if keys.just_pressed(KeyCode::KeyG) {
+        toggle(&mut store.config_mut::<GridLineGizmos>().0.enabled);
+    }
+    if keys.just_pressed(KeyCode::KeyC) {
+        toggle(&mut store.config_mut::<CellCenterGizmos>().0.enabled);
+    }
+    if keys.just_pressed(KeyCode::KeyN) {
+        toggle(&mut store.config_mut::<NeighborGizmos>().0.enabled);
+    }
+    if keys.just_pressed(KeyCode::KeyV) {
+        overlay.culling = match overlay.culling {
+            OverlayCulling::FacingCamera => OverlayCulling::NearCursor {
+                radius: DEFAULT_CURSOR_RADIUS,
+            },
+            OverlayCulling::NearCursor { .. } => OverlayCulling::FacingCamera,
+        };
+    }
+}
+
+/// The part of the planet the grid overlays are drawn over this frame.
+#[derive(SystemParam)]
+pub struct OverlayView<'w, 's> {
+    overlay: Res<'w, GridDebugOverlay>,
+    cursor: Res<'w, PlanetCursor>,
+    cameras: Query<'w, 's, &'static GlobalTransform, With<PanOrbitCamera>>,
+}
+
+impl OverlayView<'_, '_> {
+    /// Whether a point on the unit sphere is drawn, or `None` if nothing is this frame.
+    pub fn filter(&self) -> Option<impl Fn(Vec3) -> bool + use<>> {
+        let (direction, threshold) = match self.overlay.culling {
+            // A point on the unit sphere is above the horizon of a camera at `c` when `p · c > 1`.
+            OverlayCulling::FacingCamera => {
+                let camera = self.cameras.iter().next()?.translation();
+                let distance = camera.length();
+                (camera / distance, 1.0 / distance)
+            }
+            OverlayCulling::NearCursor { radius } => (self.cursor.point?, radius.cos()),
+        };
+        Some(move |point: Vec3| point.dot(direction) > threshold)
+    }
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::{
+    materials::map_material::MapMaterial,
+    resources::{mantle_grid::MantleGrid, map_view::MapView},
+    systems::{
+        map_view::{cycle_map_projection, layout_map_view, sync_map_material, update_map_view},
+        shortcuts::shortcuts_enabled,
+    },
+};
+
+/// A flat map of the whole planet drawn over a corner of the window, from the same field buffers
+/// as the globe. M cycles through the projections; the control panel picks one directly.
+pub struct MapViewPlugin;
+
+impl Plugin for MapViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<MapMaterial>::default())
+            .init_resource::<MapView>()
+            .add_systems(
+                Update,
+                (
+                    cycle_map_projection.run_if(shortcuts_enabled),
+                    update_map_view,
+                    sync_map_material,
+                    layout_map_view,
+                )
+                    .chain()
+                    .run_if(resource_exists::<MantleGrid>),
+            );
+    }
+}
@@ -1 +1,11 @@
+pub mod brush;
+pub mod camera_controls;
+pub mod cell_inspector;
+pub mod control_panel;
+pub mod field_statistics;
+pub mod grid_debug_overlay;
+pub mod map_view;
+pub mod planet_cursor;
 pub mod pressure_solver;
+pub mod simulation;
+pub mod velocity_field;
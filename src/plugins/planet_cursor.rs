@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+use crate::{resources::planet_cursor::PlanetCursor, systems::cursor::track_planet_cursor};
+
+/// Keeps `PlanetCursor` pointing at the part of the planet under the mouse, before any `Update`
+/// system reads it.
+pub struct PlanetCursorPlugin;
+
+impl Plugin for PlanetCursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlanetCursor>()
+            .add_systems(PreUpdate, track_planet_cursor);
+    }
+}
@@ -5,9 +5,9 @@ use bevy::{
         extract_resource::ExtractResourcePlugin,
         render_asset::RenderAssets,
         render_resource::{
-            BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, BufferBindingType,
-            BufferDescriptor, BufferUsages, CachedComputePipelineId, ComputePipelineDescriptor,
-            MapMode, PipelineCache, PollType, ShaderStages,
+            BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, Buffer,
+            BufferBindingType, CachedComputePipelineId, ComputePipeline, ComputePipelineDescriptor,
+            PipelineCache, ShaderStages,
         },
         renderer::{RenderDevice, RenderQueue},
         storage::GpuShaderStorageBuffer,
@@ -15,16 +15,24 @@ use bevy::{
 };
 
 use crate::resources::{
-    mantle_grid::MantleGrid,
+    cell_field_buffer::CellFieldBufferHandle,
+    cell_pressures::{CellPressures, PressureReadbackBufferHandle},
+    field_display::{FieldDisplay, Shading},
+    mantle_grid::{CellField, MantleGrid},
     pressure_buffers::{PressureBuffers, prepare_buffers},
-    vertex_pressure_buffer::VertexPressureBufferHandle,
+    vertex_elevation_buffer::VertexElevationBufferHandle,
+    vertex_field_buffer::VertexFieldBufferHandle,
 };
 
 pub struct PressureSolverPlugin;
 
 impl Plugin for PressureSolverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ExtractResourcePlugin::<MantleGrid>::default());
+        app.add_plugins(ExtractResourcePlugin::<MantleGrid>::default())
+            .add_plugins(ExtractResourcePlugin::<FieldDisplay>::default())
+            .add_plugins(ExtractResourcePlugin::<PressureReadbackBufferHandle>::default())
+            .init_resource::<FieldDisplay>()
+            .init_resource::<CellPressures>();
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app.add_systems(
@@ -33,9 +41,12 @@ impl Plugin for PressureSolverPlugin {
                 prepare_pipeline,
                 prepare_vertex_pressure_pipeline,
                 prepare_buffers,
+                upload_fields,
                 dispatch_pressure_solver,
-                dispatch_vertex_pressure_solver,
-                readback_pressure,
+                dispatch_vertex_field_solver,
+                copy_cell_field,
+                dispatch_vertex_elevation_solver,
+                copy_pressure_for_readback,
             )
                 .chain()
                 .in_set(RenderSystems::Prepare),
@@ -238,10 +249,12 @@ fn prepare_vertex_pressure_pipeline(
     });
 }
 
-fn dispatch_vertex_pressure_solver(
+#[allow(clippy::too_many_arguments)]
+fn dispatch_vertex_field_solver(
     pipeline: Res<VertexPressurePipeline>,
     buffers: Res<PressureBuffers>,
-    vertex_buffer_handle: Res<VertexPressureBufferHandle>,
+    display: Res<FieldDisplay>,
+    vertex_buffer_handle: Res<VertexFieldBufferHandle>,
     gpu_buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
     pipeline_cache: Res<PipelineCache>,
     render_device: Res<RenderDevice>,
@@ -255,23 +268,100 @@ fn dispatch_vertex_pressure_solver(
         return;
     };
 
-    let read_buffer = if buffers.current_read {
-        &buffers.pressure_buffer_b
-    } else {
-        &buffers.pressure_buffer_a
+    dispatch_vertex_average(
+        "vertex_field_bind_group",
+        &pipeline,
+        compute_pipeline,
+        buffers.cell_values(display.field),
+        &vertex_gpu_buffer.buffer,
+        &buffers,
+        &render_device,
+        &render_queue,
+    );
+}
+
+/// Copies the cell values of the displayed field into the planet material's buffer while it is
+/// shaded flat.
+fn copy_cell_field(
+    buffers: Res<PressureBuffers>,
+    display: Res<FieldDisplay>,
+    cell_buffer_handle: Res<CellFieldBufferHandle>,
+    gpu_buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if display.shading != Shading::Flat {
+        return;
+    }
+
+    let Some(cell_gpu_buffer) = gpu_buffers.get(&cell_buffer_handle.0) else {
+        return;
     };
 
+    let mut encoder = render_device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(
+        buffers.cell_values(display.field),
+        0,
+        &cell_gpu_buffer.buffer,
+        0,
+        u64::from(buffers.num_cells) * 4,
+    );
+    render_queue.submit(std::iter::once(encoder.finish()));
+}
+
+fn dispatch_vertex_elevation_solver(
+    pipeline: Res<VertexPressurePipeline>,
+    buffers: Res<PressureBuffers>,
+    vertex_buffer_handle: Res<VertexElevationBufferHandle>,
+    gpu_buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let Some(compute_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.pipeline_id) else {
+        return;
+    };
+
+    let Some(vertex_gpu_buffer) = gpu_buffers.get(&vertex_buffer_handle.0) else {
+        return;
+    };
+
+    dispatch_vertex_average(
+        "vertex_elevation_bind_group",
+        &pipeline,
+        compute_pipeline,
+        &buffers.elevation_buffer,
+        &vertex_gpu_buffer.buffer,
+        &buffers,
+        &render_device,
+        &render_queue,
+    );
+}
+
+/// Averages a per-triangle field onto the vertices of the sphere using the vertex pressure
+/// pipeline, which is agnostic to the field being averaged.
+#[allow(clippy::too_many_arguments)]
+fn dispatch_vertex_average(
+    label: &str,
+    pipeline: &VertexPressurePipeline,
+    compute_pipeline: &ComputePipeline,
+    triangle_buffer: &Buffer,
+    vertex_buffer: &Buffer,
+    buffers: &PressureBuffers,
+    render_device: &RenderDevice,
+    render_queue: &RenderQueue,
+) {
     let bind_group = render_device.create_bind_group(
-        "vertex_pressure_bind_group",
+        label,
         &pipeline.bind_group_layout,
         &[
             BindGroupEntry {
                 binding: 0,
-                resource: read_buffer.as_entire_binding(),
+                resource: triangle_buffer.as_entire_binding(),
             },
             BindGroupEntry {
                 binding: 1,
-                resource: vertex_gpu_buffer.buffer.as_entire_binding(),
+                resource: vertex_buffer.as_entire_binding(),
             },
             BindGroupEntry {
                 binding: 2,
@@ -292,50 +382,52 @@ fn dispatch_vertex_pressure_solver(
     render_queue.submit(std::iter::once(encoder.finish()));
 }
 
-fn readback_pressure(
+/// Copies the main-world elevations, and the displayed field if it needs a buffer of its own,
+/// to the GPU whenever the grid or the selection changes.
+fn upload_fields(
+    grid: Res<MantleGrid>,
+    display: Res<FieldDisplay>,
     buffers: Res<PressureBuffers>,
-    render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
 ) {
-    static mut COUNTER: u32 = 0;
-    unsafe {
-        COUNTER += 1;
-        if COUNTER % 60 != 0 {
-            return;
-        }
+    if grid.is_changed() {
+        render_queue.write_buffer(
+            &buffers.elevation_buffer,
+            0,
+            bytemuck::cast_slice(&grid.field_values(CellField::Elevation)),
+        );
     }
 
-    let staging_buffer = render_device.create_buffer(&BufferDescriptor {
-        label: Some("pressure_readback"),
-        size: u64::from(buffers.num_cells) * 4,
-        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
-        mapped_at_creation: false,
-    });
+    if (grid.is_changed() || display.is_changed())
+        && !matches!(display.field, CellField::Pressure | CellField::Elevation)
+    {
+        render_queue.write_buffer(
+            &buffers.field_buffer,
+            0,
+            bytemuck::cast_slice(&grid.field_values(display.field)),
+        );
+    }
+}
 
-    let read_buffer = if buffers.current_read {
-        &buffers.pressure_buffer_b
-    } else {
-        &buffers.pressure_buffer_a
+/// Copies the latest pressures into the buffer that is read back into `CellPressures`.
+fn copy_pressure_for_readback(
+    buffers: Res<PressureBuffers>,
+    readback_buffer_handle: Res<PressureReadbackBufferHandle>,
+    gpu_buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let Some(readback_gpu_buffer) = gpu_buffers.get(&readback_buffer_handle.0) else {
+        return;
     };
 
     let mut encoder = render_device.create_command_encoder(&Default::default());
     encoder.copy_buffer_to_buffer(
-        read_buffer,
+        buffers.cell_values(CellField::Pressure),
         0,
-        &staging_buffer,
+        &readback_gpu_buffer.buffer,
         0,
         u64::from(buffers.num_cells) * 4,
     );
     render_queue.submit(std::iter::once(encoder.finish()));
-    let buffer_slice = staging_buffer.slice(..);
-    buffer_slice.map_async(MapMode::Read, |_| {});
-    render_device.poll(PollType::Wait).expect("Failed to wait");
-
-    let data = buffer_slice.get_mapped_range();
-    let pressures: &[f32] = bytemuck::cast_slice(&data);
-
-    println!("{:?}", &pressures[..10]);
-
-    drop(data);
-    staging_buffer.unmap();
 }
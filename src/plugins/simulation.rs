@@ -0,0 +1,51 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{
+    resources::{
+        mantle_grid::MantleGrid,
+        plates::Plates,
+        simulation_clock::{SimulationClock, SimulationControl},
+    },
+    simulation::{erosion::ErosionParameters, plate_dynamics::PlateDynamicsParameters},
+    systems::{
+        erosion::apply_erosion,
+        hotspots::{apply_hotspots, drift_hotspots},
+        plates::{advect_plates, solve_plate_dynamics, update_mantle_flow},
+        simulation_clock::{advance_clock, run_simulation_steps},
+    },
+};
+
+/// Runs the CPU-side surface simulation on the main-world `MantleGrid`, as many steps per frame
+/// as `SimulationControl` asks for.
+pub struct SimulationPlugin;
+
+/// One step of the surface simulation, run from `Update` by `run_simulation_steps`.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationStep;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationClock>()
+            .init_resource::<ErosionParameters>()
+            .init_resource::<PlateDynamicsParameters>()
+            .init_resource::<Plates>()
+            .init_resource::<SimulationControl>()
+            .add_systems(
+                SimulationStep,
+                (
+                    drift_hotspots,
+                    update_mantle_flow,
+                    solve_plate_dynamics,
+                    advect_plates,
+                    apply_hotspots,
+                    apply_erosion,
+                    advance_clock,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                run_simulation_steps.run_if(resource_exists::<MantleGrid>),
+            );
+    }
+}
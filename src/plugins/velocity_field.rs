@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{
+    materials::velocity_arrow_material::VelocityArrowMaterial,
+    resources::{
+        cell_locator::CellLocator, mantle_grid::MantleGrid, overlays::Overlays,
+        velocity_field::VelocityField,
+    },
+    systems::velocity_field::{
+        setup_velocity_field, show_velocity_overlays, spawn_velocity_arrows, update_streamlines,
+        update_velocity_arrows,
+    },
+};
+
+/// Plate velocity arrows and streamlines of mantle flow, shown through `Overlays`.
+///
+/// The arrows are entities sharing one mesh and material, with their colour in their `MeshTag`,
+/// so the renderer draws them all as instances of a single mesh however many there are.
+pub struct VelocityFieldPlugin;
+
+impl Plugin for VelocityFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<VelocityArrowMaterial>::default())
+            .init_resource::<Overlays>()
+            .init_resource::<VelocityField>()
+            .add_systems(Startup, setup_velocity_field)
+            .add_systems(
+                Update,
+                (
+                    spawn_velocity_arrows.run_if(resource_changed::<VelocityField>),
+                    update_velocity_arrows.run_if(|overlays: Res<Overlays>| overlays.velocities),
+                    update_streamlines.run_if(|overlays: Res<Overlays>| overlays.streamlines),
+                    show_velocity_overlays,
+                )
+                    .chain()
+                    .run_if(resource_exists::<MantleGrid>)
+                    .run_if(resource_exists::<CellLocator>),
+            );
+    }
+}
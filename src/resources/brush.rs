@@ -0,0 +1,102 @@
+use bevy::{prelude::*, render::extract_resource::ExtractResource};
+
+/// Angular radius of a new brush, in radians.
+pub const DEFAULT_BRUSH_RADIUS: f32 = 0.1;
+
+/// What painting with the brush does to the cells under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushTool {
+    /// Raises the mantle pressure, or lowers it while Shift is held.
+    #[default]
+    Pressure,
+    /// Moves cells to the brush's plate.
+    Plate,
+    /// Turns the crust continental or oceanic by pulling its elevation towards that of a
+    /// continental interior or the abyssal plain.
+    Crust,
+    /// Heats the mantle, or cools it while Shift is held.
+    Heat,
+}
+
+impl BrushTool {
+    pub const ALL: [Self; 4] = [Self::Pressure, Self::Plate, Self::Crust, Self::Heat];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pressure => "Pressure",
+            Self::Plate => "Plate",
+            Self::Crust => "Crust",
+            Self::Heat => "Heat",
+        }
+    }
+
+    /// Strength a brush starts with when this tool is picked: pressure units and kelvin per
+    /// second for pressure and heat, and the fraction of the way to the target elevation covered
+    /// per second for crust. Plate painting ignores the strength.
+    #[must_use]
+    pub fn default_strength(self) -> f32 {
+        match self {
+            Self::Pressure => 1.0,
+            Self::Plate => 1.0,
+            Self::Crust => 2.0,
+            Self::Heat => 200.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrustType {
+    #[default]
+    Continental,
+    Oceanic,
+}
+
+/// Brush used to paint fields onto the planet by dragging over it.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Brush {
+    /// Whether dragging over the planet paints rather than orbits the camera.
+    pub active: bool,
+    pub tool: BrushTool,
+    /// Angular radius of the brush, in radians.
+    pub radius: f32,
+    /// Rate at which the tool changes the cells at the brush's centre; see
+    /// `BrushTool::default_strength`.
+    pub strength: f32,
+    /// Plate painted by `BrushTool::Plate`.
+    pub plate: usize,
+    /// Crust painted by `BrushTool::Crust`.
+    pub crust: CrustType,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            active: false,
+            tool: BrushTool::default(),
+            radius: DEFAULT_BRUSH_RADIUS,
+            strength: BrushTool::default().default_strength(),
+            plate: 0,
+            crust: CrustType::default(),
+        }
+    }
+}
+
+impl Brush {
+    /// Weight of the brush at a point on the unit sphere when centred on `center`, falling
+    /// smoothly from one at the centre to zero at its radius.
+    #[must_use]
+    pub fn weight(&self, center: Vec3, point: Vec3) -> f32 {
+        let distance = center.dot(point).clamp(-1.0, 1.0).acos();
+        if distance >= self.radius {
+            return 0.0;
+        }
+        let t = distance / self.radius;
+        (1.0 - t * t).powi(2)
+    }
+}
+
+/// Pressure changes painted this frame, as cell indices and the amounts to add. Pressure lives on
+/// the GPU, so these are added to the solver's latest pressures in the render world.
+#[derive(Resource, ExtractResource, Debug, Clone, Default)]
+pub struct PressureEdits(pub Vec<(u32, f32)>);
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::geography::LatLon;
+
+/// A saved view of the planet: the camera looks straight down at a position from a distance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    /// Latitude of the point at the centre of the view, in degrees.
+    pub latitude: f64,
+    /// Longitude of the point at the centre of the view, in degrees.
+    pub longitude: f64,
+    /// Distance of the camera from the planet's centre, in planet radii.
+    pub distance: f32,
+}
+
+impl CameraBookmark {
+    #[must_use]
+    pub fn position(&self) -> LatLon {
+        LatLon::new(self.latitude, self.longitude)
+    }
+}
+
+/// How the globe's camera can be moved, and the views saved for it. Read from the `[camera]`
+/// section of the configuration file, and written back to it from the camera window.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Closest the camera may come to the planet's centre, in planet radii.
+    pub min_distance: f32,
+    /// Farthest the camera may move from the planet's centre, in planet radii.
+    pub max_distance: f32,
+    /// Whether dragging with the right button pans the camera off the planet's centre.
+    pub panning: bool,
+    /// Whether the camera circles the planet on its own, for presentations.
+    pub auto_rotate: bool,
+    /// Speed of the automatic rotation, in degrees per second; negative to turn the other way.
+    pub auto_rotate_speed: f32,
+    /// Length of the flight to a bookmark, in seconds.
+    pub fly_duration: f32,
+    /// Speed at which the arrow keys orbit the camera, in degrees per second.
+    pub key_orbit_speed: f32,
+    /// Factor by which holding Page Up or Page Down changes the distance each second.
+    pub key_zoom_speed: f32,
+    /// Views the number keys fly to, in order.
+    pub bookmarks: Vec<CameraBookmark>,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            min_distance: 2.0,
+            max_distance: 10.0,
+            panning: false,
+            auto_rotate: false,
+            auto_rotate_speed: 6.0,
+            fly_duration: 2.0,
+            key_orbit_speed: 60.0,
+            key_zoom_speed: 2.0,
+            bookmarks: Vec::new(),
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Nearest and farthest distances the camera may take. A maximum below the minimum, as a
+    /// hand-edited configuration may hold, is raised to it.
+    #[must_use]
+    pub fn distance_limits(&self) -> (f32, f32) {
+        (self.min_distance, self.max_distance.max(self.min_distance))
+    }
+
+    /// `distance` brought within the camera's distance limits.
+    #[must_use]
+    pub fn clamp_distance(&self, distance: f32) -> f32 {
+        let (min, max) = self.distance_limits();
+        distance.clamp(min, max)
+    }
+}
+
+/// Orientation and distance of a camera orbiting the planet's centre, as `PanOrbitCamera` holds
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    /// Rotation about the planet's axis, in radians.
+    pub yaw: f32,
+    /// Elevation above the equatorial plane, in radians.
+    pub pitch: f32,
+    /// Distance from the planet's centre, in planet radii.
+    pub distance: f32,
+}
+
+impl CameraPose {
+    /// Pose looking straight down at `position` from `distance`.
+    #[must_use]
+    pub fn looking_at(position: LatLon, distance: f32) -> Self {
+        Self::from_direction(position.to_unit_vector(), distance)
+    }
+
+    /// Pose of a camera in `direction` from the planet's centre.
+    #[must_use]
+    pub fn from_direction(direction: Vec3, distance: f32) -> Self {
+        let direction = direction.normalize_or(Vec3::Z);
+        Self {
+            yaw: direction.x.atan2(direction.z),
+            pitch: direction.y.clamp(-1.0, 1.0).asin(),
+            distance,
+        }
+    }
+
+    /// Unit vector from the planet's centre towards the camera.
+    #[must_use]
+    pub fn direction(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw)
+    }
+
+    /// Position at the centre of the view.
+    #[must_use]
+    pub fn position(&self) -> LatLon {
+        LatLon::from_unit_vector(self.direction())
+    }
+}
+
+/// Animated flight of the camera from one pose to another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flight {
+    pub from: CameraPose,
+    pub to: CameraPose,
+    /// Seconds since the flight began.
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+/// The flight the camera is on, if any.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct CameraFlight(pub Option<Flight>);
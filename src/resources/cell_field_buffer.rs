@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::storage::ShaderStorageBuffer;
+
+/// Per-cell values of the field selected by `FieldDisplay`, copied from the GPU's cell buffers
+/// for flat shading.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct CellFieldBufferHandle(pub Handle<ShaderStorageBuffer>);
@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+/// Cells picked on the planet with the mouse.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct CellInspector {
+    /// Cell under the cursor, unless the cursor is over the user interface.
+    pub hovered: Option<usize>,
+    /// Cell pinned by clicking it, shown until it is clicked again or closed.
+    pub selected: Option<usize>,
+}
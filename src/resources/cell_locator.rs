@@ -0,0 +1,194 @@
+//! Point location on the grid, exploiting the icosahedral subdivision.
+//!
+//! A lookup first picks which of the icosahedron's 20 faces the point lies on, then the bucket of
+//! that face's barycentric coordinates it falls in. Buckets are about the size of a cell and
+//! remember the cell nearest their centre, so the final walk to the containing triangle takes a
+//! step or two and a lookup costs the same whatever the resolution of the grid.
+
+use bevy::prelude::*;
+use hexasphere::{BaseShape, shapes::IcoSphereBase};
+
+use crate::{resources::mantle_grid::MantleGrid, simulation::geography::LatLon};
+
+/// The cell containing a point and the point's position within the cell's triangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLocation {
+    pub cell: usize,
+    /// Weights of the vertices returned by `MantleGrid::triangle`, summing to one. They are the
+    /// barycentric coordinates of the point's projection from the centre of the sphere onto the
+    /// flat triangle, and are all non-negative when the point lies inside it.
+    pub barycentric: Vec3,
+}
+
+impl PointLocation {
+    /// Interpolates per-vertex `values`, such as those of `MantleGrid::vertex_values`, at the
+    /// located point.
+    #[must_use]
+    pub fn interpolate(&self, grid: &MantleGrid, values: &[f32]) -> f32 {
+        let [a, b, c] = grid
+            .triangle(self.cell)
+            .map(|vertex| values[vertex as usize]);
+        Vec3::new(a, b, c).dot(self.barycentric)
+    }
+}
+
+/// Finds the cell containing a point without scanning every cell. The grid's topology never
+/// changes, so the locator stays valid for the whole run.
+#[derive(Resource, Debug, Clone)]
+pub struct CellLocator {
+    /// Corners of each face of the base icosahedron.
+    faces: Vec<[Vec3; 3]>,
+    /// Centre of each face, projected onto the sphere.
+    face_centers: Vec<Vec3>,
+    /// Buckets along each side of a face.
+    resolution: usize,
+    /// Starting cell of every bucket, face by face, indexed by the first two barycentric
+    /// coordinates.
+    starts: Vec<usize>,
+}
+
+impl CellLocator {
+    #[must_use]
+    pub fn new(grid: &MantleGrid) -> Self {
+        let base = IcoSphereBase;
+        let corners = base.initial_points();
+        let faces: Vec<[Vec3; 3]> = base
+            .triangles()
+            .iter()
+            .map(|face| [face.a, face.b, face.c].map(|corner| corners[corner as usize].into()))
+            .collect();
+        let face_centers = faces
+            .iter()
+            .map(|&[a, b, c]| (a + b + c).normalize())
+            .collect();
+        let resolution = grid.subdivisions() + 1;
+
+        let mut starts = Vec::with_capacity(faces.len() * resolution * resolution);
+        let mut cell = 0;
+        for &[a, b, c] in &faces {
+            for i in 0..resolution {
+                for j in 0..resolution {
+                    // Buckets beyond the face's third edge are never looked up, but keep the
+                    // table rectangular.
+                    let u = (i as f32 + 0.5) / resolution as f32;
+                    let v = (j as f32 + 0.5) / resolution as f32;
+                    let point = (a * u + b * v + c * (1.0 - u - v).max(0.0)).normalize();
+                    cell = grid.nearest_cell_from(cell, point);
+                    starts.push(cell);
+                }
+            }
+        }
+
+        Self {
+            faces,
+            face_centers,
+            resolution,
+            starts,
+        }
+    }
+
+    /// Cell whose triangle contains `point`, which need not be normalised.
+    #[must_use]
+    pub fn locate(&self, grid: &MantleGrid, point: Vec3) -> usize {
+        self.find(grid, point).cell
+    }
+
+    /// Cell containing a geographic position.
+    #[must_use]
+    pub fn cell_at(&self, grid: &MantleGrid, position: LatLon) -> usize {
+        self.locate(grid, position.to_unit_vector())
+    }
+
+    /// Cell containing `point`, which need not be normalised, with the point's barycentric
+    /// coordinates in it.
+    #[must_use]
+    pub fn find(&self, grid: &MantleGrid, point: Vec3) -> PointLocation {
+        let point = point.normalize_or(Vec3::Y);
+        // The faces are congruent, so the one with the nearest centre contains the point.
+        let face = (0..self.faces.len())
+            .max_by(|&a, &b| {
+                let similarity = |face: usize| self.face_centers[face].dot(point);
+                similarity(a).total_cmp(&similarity(b))
+            })
+            .unwrap_or(0);
+        let [u, v, _] = barycentric(self.faces[face], point).to_array();
+        let bucket = |weight: f32| {
+            ((weight * self.resolution as f32).max(0.0) as usize).min(self.resolution - 1)
+        };
+        let start = self.starts[(face * self.resolution + bucket(u)) * self.resolution + bucket(v)];
+
+        let nearest = grid.nearest_cell_from(start, point);
+        // Near a vertex the containing triangle may only share that vertex with the cell whose
+        // centre is nearest.
+        let cell = std::iter::once(nearest)
+            .chain(
+                grid.triangle(nearest)
+                    .into_iter()
+                    .flat_map(|vertex| grid.vertex_triangles[vertex as usize].iter().copied()),
+            )
+            .find(|&cell| grid.contains(cell, point))
+            .unwrap_or(nearest);
+
+        let points = grid.sphere.raw_points();
+        let triangle = grid
+            .triangle(cell)
+            .map(|vertex| Vec3::from(points[vertex as usize]));
+        PointLocation {
+            cell,
+            barycentric: barycentric(triangle, point),
+        }
+    }
+}
+
+/// Barycentric coordinates of the central projection of `point` onto the plane of a triangle,
+/// from the volumes of the tetrahedra it forms with the centre of the sphere and each edge.
+fn barycentric([a, b, c]: [Vec3; 3], point: Vec3) -> Vec3 {
+    let weights = Vec3::new(
+        point.dot(b.cross(c)),
+        point.dot(c.cross(a)),
+        point.dot(a.cross(b)),
+    );
+    let total = weights.x + weights.y + weights.z;
+    if total == 0.0 {
+        Vec3::splat(1.0 / 3.0)
+    } else {
+        weights / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn find_returns_the_containing_triangle() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for subdivisions in [0, 1, 4, 9, 16] {
+            let grid = MantleGrid::new(subdivisions);
+            let locator = CellLocator::new(&grid);
+            for _ in 0..500 {
+                let point = Vec3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                );
+                if point.length_squared() < 1.0e-3 {
+                    continue;
+                }
+
+                let location = locator.find(&grid, point);
+                assert!(
+                    grid.contains(location.cell, point.normalize()),
+                    "{point} is not in cell {} at {subdivisions} subdivisions",
+                    location.cell,
+                );
+                let weights = location.barycentric;
+                assert!((weights.x + weights.y + weights.z - 1.0).abs() < 1.0e-5);
+                assert!(weights.min_element() > -1.0e-4, "{weights} at {point}");
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use bevy::{
+    prelude::*,
+    render::{extract_resource::ExtractResource, storage::ShaderStorageBuffer},
+};
+
+use crate::resources::mantle_grid::MantleGrid;
+
+/// Latest pressure of every cell read back from the GPU, where the pressure solver keeps it.
+/// Empty until the first readback completes.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CellPressures(pub Vec<f32>);
+
+impl CellPressures {
+    /// Pressure of a cell, falling back to the value held by the grid before the first readback.
+    #[must_use]
+    pub fn get(&self, grid: &MantleGrid, cell: usize) -> f32 {
+        self.0
+            .get(cell)
+            .copied()
+            .unwrap_or(grid.cells[cell].pressure)
+    }
+}
+
+/// Buffer the latest pressures are copied into every frame to be read back.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct PressureReadbackBufferHandle(pub Handle<ShaderStorageBuffer>);
@@ -0,0 +1,117 @@
+use bevy::{prelude::*, render::extract_resource::ExtractResource};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::{colormap::Colormap, pressure_material::ColorScale},
+    resources::{field_statistics::FieldStatistics, mantle_grid::CellField},
+};
+
+/// Which field the planet shows and how its values map to colours. Changing it from any system
+/// updates the planet material on the next frame.
+#[derive(Resource, ExtractResource, Debug, Clone, PartialEq)]
+pub struct FieldDisplay {
+    pub field: CellField,
+    pub colormap: Colormap,
+    pub range: ValueRange,
+    pub shading: Shading,
+    /// Keeps the colour scale where it is while the field evolves, so that changes show against
+    /// a fixed reference. The scale is resolved again whenever the display itself changes.
+    pub locked: bool,
+}
+
+impl Default for FieldDisplay {
+    fn default() -> Self {
+        Self::new(CellField::Pressure)
+    }
+}
+
+/// How values are spread over each triangle of the planet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Shading {
+    /// Interpolates between the values averaged at the corners, which suits continuous fields.
+    #[default]
+    Smooth,
+    /// Colours each triangle by its own cell's value, keeping discontinuities such as plate
+    /// boundaries sharp.
+    Flat,
+}
+
+impl Shading {
+    /// The shading that suits a field best.
+    #[must_use]
+    pub fn default_for(field: CellField) -> Self {
+        match field {
+            CellField::Plate => Self::Flat,
+            _ => Self::Smooth,
+        }
+    }
+
+    #[must_use]
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Smooth => Self::Flat,
+            Self::Flat => Self::Smooth,
+        }
+    }
+}
+
+/// Values mapped to the two ends of a continuous colormap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRange {
+    /// Follows the smallest and largest value of the field as the simulation evolves.
+    Auto,
+    /// Follows two percentiles of the field's values, so that a few outliers do not wash out
+    /// the rest of the planet.
+    Percentile {
+        low: f32,
+        high: f32,
+    },
+    Manual {
+        min: f32,
+        max: f32,
+    },
+}
+
+impl FieldDisplay {
+    /// Shows `field` with its preferred colormap and shading and an automatic range.
+    #[must_use]
+    pub fn new(field: CellField) -> Self {
+        Self {
+            field,
+            colormap: Colormap::default_for(field),
+            range: ValueRange::Auto,
+            shading: Shading::default_for(field),
+            locked: false,
+        }
+    }
+
+    /// Range of values the colormap spans given the current statistics of the field.
+    #[must_use]
+    pub fn resolve_range(&self, statistics: &FieldStatistics) -> (f32, f32) {
+        match self.range {
+            ValueRange::Manual { min, max } => (min, max),
+            ValueRange::Auto => (statistics.min, statistics.max),
+            ValueRange::Percentile { low, high } => {
+                (statistics.percentile(low), statistics.percentile(high))
+            }
+        }
+    }
+
+    /// Uniform telling the shader how to map values onto the colormap texture.
+    #[must_use]
+    pub fn color_scale(&self, statistics: &FieldStatistics) -> ColorScale {
+        let (min, max) = self.resolve_range(statistics);
+        let (min, max) = if min <= max { (min, max) } else { (0.0, 1.0) };
+        ColorScale {
+            min,
+            max,
+            categories: if self.colormap.is_categorical() {
+                self.colormap.texels() as u32
+            } else {
+                0
+            },
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use bevy::{
+    prelude::*,
+    render::{extract_resource::ExtractResource, storage::ShaderStorageBuffer},
+};
+
+use crate::resources::mantle_grid::{CellField, MantleGrid};
+
+/// Bins of the histogram `field_statistics.wgsl` builds between the smallest and largest value.
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// Words of the statistics buffer: the field, the ordered minimum and maximum, then the
+/// histogram.
+pub const STATISTICS_WORDS: usize = 3 + HISTOGRAM_BINS;
+
+/// Range and distribution of the displayed field's finite values, reduced on the GPU every frame
+/// and read back asynchronously.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FieldStatistics {
+    pub field: CellField,
+    pub min: f32,
+    pub max: f32,
+    /// Number of values in each of `HISTOGRAM_BINS` equal bins spanning `min` to `max`.
+    pub histogram: Vec<u32>,
+}
+
+impl FieldStatistics {
+    /// Same reduction as the GPU over the values held by the grid, for fields the GPU has not
+    /// reported on yet.
+    #[must_use]
+    pub fn compute(grid: &MantleGrid, field: CellField) -> Self {
+        let values: Vec<f32> = grid
+            .cells
+            .iter()
+            .map(|cell| field.value(cell))
+            .filter(|value| value.is_finite())
+            .collect();
+        let (min, max) = values
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+
+        let mut histogram = vec![0; HISTOGRAM_BINS];
+        let span = max - min;
+        for value in values {
+            let bin = if span > 0.0 {
+                (((value - min) / span * HISTOGRAM_BINS as f32) as usize).min(HISTOGRAM_BINS - 1)
+            } else {
+                0
+            };
+            histogram[bin] += 1;
+        }
+        Self {
+            field,
+            min,
+            max,
+            histogram,
+        }
+    }
+
+    /// Decodes the statistics buffer, or `None` if it is too short or names no known field.
+    #[must_use]
+    pub fn from_words(words: &[u32]) -> Option<Self> {
+        if words.len() < STATISTICS_WORDS {
+            return None;
+        }
+        Some(Self {
+            field: *CellField::ALL.get(words[0] as usize)?,
+            min: from_ordered(words[1]),
+            max: from_ordered(words[2]),
+            histogram: words[3..STATISTICS_WORDS].to_vec(),
+        })
+    }
+
+    /// Initial contents of the statistics buffer before a reduction over `field`, which the
+    /// atomics narrow down from.
+    #[must_use]
+    pub fn reset_words(field: CellField) -> Vec<u32> {
+        let mut words = vec![0; STATISTICS_WORDS];
+        words[0] = CellField::ALL
+            .iter()
+            .position(|&candidate| candidate == field)
+            .unwrap_or(0) as u32;
+        words[1] = u32::MAX;
+        words
+    }
+
+    /// Whether no finite value was reduced.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.min.is_nan() || self.max.is_nan() || self.min > self.max
+    }
+
+    /// Value below which `percent` of the values lie, interpolated within the histogram bin it
+    /// falls in.
+    #[must_use]
+    pub fn percentile(&self, percent: f32) -> f32 {
+        let total: u64 = self.histogram.iter().map(|&count| u64::from(count)).sum();
+        if total == 0 {
+            return self.min;
+        }
+        let target = f64::from(percent.clamp(0.0, 100.0)) / 100.0 * total as f64;
+        let bin_width = (self.max - self.min) / self.histogram.len() as f32;
+        let mut below = 0.0;
+        for (bin, &count) in self.histogram.iter().enumerate() {
+            let count = f64::from(count);
+            if count > 0.0 && below + count >= target {
+                let fraction = ((target - below) / count) as f32;
+                return self.min + (bin as f32 + fraction) * bin_width;
+            }
+            below += count;
+        }
+        self.max
+    }
+}
+
+/// Buffer the GPU reduces the displayed field into, read back every frame.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct FieldStatisticsBufferHandle(pub Handle<ShaderStorageBuffer>);
+
+/// Inverse of the order-preserving mapping of floats onto unsigned integers in
+/// `field_statistics.wgsl`.
+fn from_ordered(ordered: u32) -> f32 {
+    if ordered & 0x8000_0000 != 0 {
+        f32::from_bits(ordered & 0x7fff_ffff)
+    } else {
+        f32::from_bits(!ordered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The mapping `field_statistics.wgsl` applies before its atomic minimum and maximum.
+    fn to_ordered(value: f32) -> u32 {
+        let bits = value.to_bits();
+        if bits & 0x8000_0000 != 0 {
+            !bits
+        } else {
+            bits | 0x8000_0000
+        }
+    }
+
+    fn grid_with_pressures(pressures: impl Fn(usize) -> f32) -> MantleGrid {
+        let mut grid = MantleGrid::new(1);
+        for (index, cell) in grid.cells.iter_mut().enumerate() {
+            cell.pressure = pressures(index);
+        }
+        grid
+    }
+
+    #[test]
+    fn ordered_words_round_trip_and_keep_the_order_of_floats() {
+        let values = [
+            f32::NEG_INFINITY,
+            f32::MIN,
+            -1.0,
+            -f32::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            1.0e-40,
+            1.0,
+            8800.0,
+            f32::MAX,
+            f32::INFINITY,
+        ];
+        for pair in values.windows(2) {
+            assert!(to_ordered(pair[0]) < to_ordered(pair[1]), "{pair:?}");
+        }
+        for value in values {
+            assert_eq!(from_ordered(to_ordered(value)).to_bits(), value.to_bits());
+        }
+    }
+
+    #[test]
+    fn percentiles_interpolate_within_bins() {
+        let statistics = FieldStatistics {
+            field: CellField::Pressure,
+            min: 0.0,
+            max: 4.0,
+            histogram: vec![1, 1, 1, 1],
+        };
+        for (percent, expected) in [
+            (0.0, 0.0),
+            (25.0, 1.0),
+            (50.0, 2.0),
+            (62.5, 2.5),
+            (100.0, 4.0),
+        ] {
+            assert_eq!(statistics.percentile(percent), expected, "{percent}%");
+        }
+        assert_eq!(statistics.percentile(-10.0), 0.0);
+        assert_eq!(statistics.percentile(150.0), 4.0);
+
+        let empty = FieldStatistics {
+            histogram: vec![0; 4],
+            ..statistics
+        };
+        assert_eq!(empty.percentile(50.0), 0.0);
+    }
+
+    #[test]
+    fn compute_matches_known_percentiles_of_a_small_field() {
+        let grid = grid_with_pressures(|index| index as f32);
+        let statistics = FieldStatistics::compute(&grid, CellField::Pressure);
+        let last = (grid.cells.len() - 1) as f32;
+        assert_eq!((statistics.min, statistics.max), (0.0, last));
+        assert_eq!(
+            statistics.histogram.iter().sum::<u32>() as usize,
+            grid.cells.len()
+        );
+        let bin_width = last / HISTOGRAM_BINS as f32;
+        for percent in [10.0, 50.0, 90.0] {
+            let expected = percent / 100.0 * last;
+            let value = statistics.percentile(percent);
+            assert!(
+                (value - expected).abs() <= 1.0 + bin_width,
+                "{percent}%: {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn compute_ignores_values_that_are_not_finite() {
+        let grid = grid_with_pressures(|index| match index % 4 {
+            0 => f32::NAN,
+            1 => f32::INFINITY,
+            _ => index as f32,
+        });
+        let statistics = FieldStatistics::compute(&grid, CellField::Pressure);
+        assert_eq!((statistics.min, statistics.max), (2.0, 79.0));
+        assert_eq!(statistics.histogram.iter().sum::<u32>(), 40);
+        assert!(!statistics.is_empty());
+
+        let all_nan = grid_with_pressures(|_| f32::NAN);
+        let statistics = FieldStatistics::compute(&all_nan, CellField::Pressure);
+        assert!(statistics.is_empty());
+        assert!(statistics.histogram.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn words_decode_to_the_reduced_statistics() {
+        let reset = FieldStatistics::reset_words(CellField::Elevation);
+        assert_eq!(reset.len(), STATISTICS_WORDS);
+        let statistics = FieldStatistics::from_words(&reset).unwrap();
+        assert_eq!(statistics.field, CellField::Elevation);
+        assert!(statistics.is_empty(), "nothing has been reduced yet");
+
+        let mut words = reset;
+        words[1] = to_ordered(-250.0);
+        words[2] = to_ordered(4000.0);
+        words[3] = 7;
+        let statistics = FieldStatistics::from_words(&words).unwrap();
+        assert_eq!((statistics.min, statistics.max), (-250.0, 4000.0));
+        assert_eq!(statistics.histogram.len(), HISTOGRAM_BINS);
+        assert_eq!(statistics.histogram[0], 7);
+
+        assert!(FieldStatistics::from_words(&words[..STATISTICS_WORDS - 1]).is_none());
+        words[0] = CellField::ALL.len() as u32;
+        assert!(FieldStatistics::from_words(&words).is_none());
+    }
+}
@@ -1,16 +1,22 @@
 use std::collections::HashMap;
 
 use bevy::{
-    asset::RenderAssetUsages,
-    mesh::{Indices, PrimitiveTopology},
-    prelude::*,
+    asset::RenderAssetUsages, mesh::PrimitiveTopology, prelude::*,
     render::extract_resource::ExtractResource,
 };
+use clap::ValueEnum;
 use hexasphere::shapes::IcoSphere;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::geography::LatLon;
+
+/// Radius of the planet in metres. The grid itself lives on the unit sphere.
+pub const PLANET_RADIUS: f32 = 6_371_000.0;
 
 #[derive(Resource, Clone)]
 pub struct MantleGrid {
     pub sphere: IcoSphere<()>,
+    pub indices: Vec<u32>,
     pub cells: Vec<CellData>,
     pub neighbors: Vec<Vec<usize>>,
     pub vertex_triangles: Vec<Vec<usize>>,
@@ -32,9 +38,13 @@ impl MantleGrid {
         let num_triangles = indices.len() / 3;
         let cells = (0..num_triangles)
             .map(|x| CellData {
-                pressure: x as f32,
-                center: triangle_center(&sphere, x),
+                pressure: 0.0,
+                center: triangle_center(&sphere, &indices, x),
                 flux: vec![0.0; 3],
+                elevation: 0.0,
+                temperature: 0.0,
+                age: 0.0,
+                plate: 0,
             })
             .collect();
 
@@ -61,7 +71,7 @@ impl MantleGrid {
 
         // Build neighbor list for each triangle
         let mut neighbors = vec![Vec::new(); num_triangles];
-        for tri_idx in 0..num_triangles {
+        for (tri_idx, tri_neighbors) in neighbors.iter_mut().enumerate() {
             let base = tri_idx * 3;
             let v0 = indices[base];
             let v1 = indices[base + 1];
@@ -77,7 +87,7 @@ impl MantleGrid {
                 if let Some(tris) = edge_to_triangles.get(&edge) {
                     for &neighbor_idx in tris {
                         if neighbor_idx != tri_idx {
-                            neighbors[tri_idx].push(neighbor_idx);
+                            tri_neighbors.push(neighbor_idx);
                         }
                     }
                 }
@@ -99,34 +109,183 @@ impl MantleGrid {
 
         Self {
             sphere,
+            indices,
             cells,
             neighbors,
             vertex_triangles,
         }
     }
 
+    /// Number of points added along each edge of the base icosahedron. Recovered from the
+    /// `(subdivisions + 1)²` cells of each of the 20 faces because hexasphere 16's
+    /// `Subdivided::new_custom_shape` initialises its `subdivisions` field to 1 and never stores
+    /// the requested count, so `IcoSphere::subdivisions` is wrong for every other count.
+    #[must_use]
+    pub fn subdivisions(&self) -> usize {
+        (((self.cells.len() / 20) as f64).sqrt().round() as usize).saturating_sub(1)
+    }
+
+    /// Finds the cell whose centre is closest to `point` by walking across neighbours from
+    /// `start`, which is cheap when `start` is already near `point`.
+    #[must_use]
+    pub fn nearest_cell_from(&self, start: usize, point: Vec3) -> usize {
+        let mut cell = start;
+        let mut best = self.cells[cell].center.dot(point);
+        loop {
+            let next = self.neighbors[cell]
+                .iter()
+                .map(|&n| (n, self.cells[n].center.dot(point)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            match next {
+                Some((n, similarity)) if similarity > best => {
+                    cell = n;
+                    best = similarity;
+                }
+                _ => return cell,
+            }
+        }
+    }
+
+    /// Values of `field` for every cell, in cell order.
+    #[must_use]
+    pub fn field_values(&self, field: CellField) -> Vec<f32> {
+        self.cells.iter().map(|cell| field.value(cell)).collect()
+    }
+
+    /// Values of `field` at every vertex, averaged over the cells sharing the vertex as the
+    /// vertex shaders do.
+    #[must_use]
+    pub fn vertex_values(&self, field: CellField) -> Vec<f32> {
+        self.vertex_triangles
+            .iter()
+            .map(|triangles| {
+                let sum: f32 = triangles
+                    .iter()
+                    .map(|&cell| field.value(&self.cells[cell]))
+                    .sum();
+                sum / triangles.len().max(1) as f32
+            })
+            .collect()
+    }
+
+    /// FNV-1a hash over the bits of every per-cell field, used to check that two runs produced
+    /// bit-identical results.
+    #[must_use]
+    pub fn checksum(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        let mut hash = OFFSET;
+        let mut write = |bits: u64| {
+            for byte in bits.to_le_bytes() {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+            }
+        };
+        for cell in &self.cells {
+            write(u64::from(cell.pressure.to_bits()));
+            write(u64::from(cell.elevation.to_bits()));
+            write(u64::from(cell.temperature.to_bits()));
+            write(u64::from(cell.age.to_bits()));
+            write(cell.plate as u64);
+            for flux in &cell.flux {
+                write(u64::from(flux.to_bits()));
+            }
+        }
+        hash
+    }
+
+    /// Vertex indices of the triangle backing a cell.
+    #[must_use]
+    pub fn triangle(&self, cell: usize) -> [u32; 3] {
+        let base = cell * 3;
+        [
+            self.indices[base],
+            self.indices[base + 1],
+            self.indices[base + 2],
+        ]
+    }
+
+    /// Geographic position of a cell's centre.
+    #[must_use]
+    pub fn cell_lat_lon(&self, cell: usize) -> LatLon {
+        LatLon::from_unit_vector(self.cells[cell].center)
+    }
+
+    /// Geographic position of a vertex of the grid.
+    #[must_use]
+    pub fn vertex_lat_lon(&self, vertex: usize) -> LatLon {
+        LatLon::from_unit_vector(self.sphere.raw_points()[vertex].into())
+    }
+
+    /// Whether the triangle of `cell` contains the direction `point`, edges included.
+    #[must_use]
+    pub fn contains(&self, cell: usize, point: Vec3) -> bool {
+        let points = self.sphere.raw_points();
+        let [a, b, c] = self.triangle(cell).map(|v| Vec3::from(points[v as usize]));
+        // Compare against the winding so that the antipodal triangle does not match as well.
+        let winding = a.dot(b.cross(c)).signum();
+        [a.cross(b), b.cross(c), c.cross(a)]
+            .into_iter()
+            .all(|normal| normal.dot(point) * winding >= 0.0)
+    }
+
+    /// Length of the edge shared by two neighbouring cells, on the unit sphere.
+    #[must_use]
+    pub fn shared_edge_length(&self, cell: usize, neighbor: usize) -> f32 {
+        let points = self.sphere.raw_points();
+        let vertices = self.triangle(cell);
+        let mut shared = self
+            .triangle(neighbor)
+            .into_iter()
+            .filter(|v| vertices.contains(v))
+            .map(|v| Vec3::from(points[v as usize]));
+        match (shared.next(), shared.next()) {
+            (Some(a), Some(b)) => a.distance(b),
+            _ => 0.0,
+        }
+    }
+
+    /// Area of a cell on the unit sphere, in steradians.
+    #[must_use]
+    pub fn cell_area(&self, cell: usize) -> f32 {
+        let points = self.sphere.raw_points();
+        let [a, b, c] = self.triangle(cell).map(|v| Vec3::from(points[v as usize]));
+
+        // Spherical excess of the triangle (Van Oosterom & Strackee).
+        let numerator = a.dot(b.cross(c)).abs();
+        let denominator = 1.0 + a.dot(b) + b.dot(c) + c.dot(a);
+        2.0 * numerator.atan2(denominator)
+    }
+
+    /// Area of a cell on a planet of `radius` metres, in km².
+    #[must_use]
+    pub fn cell_area_km2(&self, cell: usize, radius: f32) -> f32 {
+        let radius_km = radius / 1000.0;
+        self.cell_area(cell) * radius_km * radius_km
+    }
+
+    /// Render mesh with three vertices of its own per cell, in cell order, so that shaders can
+    /// tell a triangle's cell from `vertex_index / 3` and colour it by the cell's own value. The
+    /// grid vertex behind `vertex_index` is `indices[vertex_index]`.
     #[must_use]
     pub fn mesh(&self) -> Mesh {
         let points = self.sphere.raw_points();
-        let indices = self.sphere.get_all_indices();
+        let corners = self.indices.iter().map(|&vertex| points[vertex as usize]);
 
-        let positions = points.iter().map(|&p| p.into()).collect::<Vec<[f32; 3]>>();
-        let normals = points
-            .iter()
-            .map(|&p| p.normalize().into())
+        let positions = corners.clone().map(|p| p.into()).collect::<Vec<[f32; 3]>>();
+        let normals = corners
+            .map(|p| p.normalize().into())
             .collect::<Vec<[f32; 3]>>();
 
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
 
         mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
         mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.insert_indices(Indices::U32(indices));
         mesh
     }
 }
 
-fn triangle_center(sphere: &IcoSphere<()>, triangle_idx: usize) -> Vec3 {
-    let indices = sphere.get_all_indices();
+fn triangle_center(sphere: &IcoSphere<()>, indices: &[u32], triangle_idx: usize) -> Vec3 {
     let base = triangle_idx * 3;
     let points = sphere.raw_points();
 
@@ -142,4 +301,66 @@ pub struct CellData {
     pub center: Vec3,
     pub flux: Vec<f32>,
     pub pressure: f32,
+    /// Surface elevation relative to sea level, in metres.
+    pub elevation: f32,
+    /// Temperature anomaly of the mantle beneath the cell, in kelvin.
+    pub temperature: f32,
+    /// Time since the crust in the cell was emplaced, in years.
+    pub age: f32,
+    /// Index of the plate the cell belongs to in `Plates`.
+    pub plate: usize,
+}
+
+/// Selects one of the scalar fields stored per cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CellField {
+    Pressure,
+    Elevation,
+    Temperature,
+    Age,
+    Plate,
+}
+
+impl CellField {
+    pub const ALL: [Self; 5] = [
+        Self::Pressure,
+        Self::Elevation,
+        Self::Temperature,
+        Self::Age,
+        Self::Plate,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pressure => "pressure",
+            Self::Elevation => "elevation",
+            Self::Temperature => "temperature",
+            Self::Age => "age",
+            Self::Plate => "plate",
+        }
+    }
+
+    /// Units of the field, empty for dimensionless fields and identifiers.
+    #[must_use]
+    pub fn units(self) -> &'static str {
+        match self {
+            Self::Pressure | Self::Plate => "",
+            Self::Elevation => "m",
+            Self::Temperature => "K",
+            Self::Age => "yr",
+        }
+    }
+
+    #[must_use]
+    pub fn value(self, cell: &CellData) -> f32 {
+        match self {
+            Self::Pressure => cell.pressure,
+            Self::Elevation => cell.elevation,
+            Self::Temperature => cell.temperature,
+            Self::Age => cell.age,
+            Self::Plate => cell.plate as f32,
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::f32::consts::{FRAC_PI_2, PI, SQRT_2};
+
+use bevy::prelude::*;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Fraction of the window's width the map takes by default.
+pub const DEFAULT_MAP_WIDTH: f32 = 0.4;
+
+/// Longitudes of the centres of the two hemispheres of the orthographic map, west then east.
+const ORTHOGRAPHIC_CENTERS: [f32; 2] = [-FRAC_PI_2, FRAC_PI_2];
+/// Distance between the centre of the orthographic map and each hemisphere's disc.
+const ORTHOGRAPHIC_OFFSET: f32 = 1.05;
+
+/// Robinson's tabulated parallel lengths and distances from the equator, every 5° of latitude.
+const ROBINSON_X: [f32; 19] = [
+    1.0000, 0.9986, 0.9954, 0.9900, 0.9822, 0.9730, 0.9600, 0.9427, 0.9216, 0.8962, 0.8679, 0.8350,
+    0.7986, 0.7597, 0.7186, 0.6732, 0.6213, 0.5722, 0.5322,
+];
+const ROBINSON_Y: [f32; 19] = [
+    0.0000, 0.0620, 0.1240, 0.1860, 0.2480, 0.3100, 0.3720, 0.4340, 0.4958, 0.5571, 0.6176, 0.6769,
+    0.7346, 0.7903, 0.8435, 0.8936, 0.9394, 0.9761, 1.0000,
+];
+
+/// How the planet's surface is laid out flat on the map view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum MapProjection {
+    /// Longitude and latitude as plain x and y.
+    Equirectangular,
+    /// Equal-area, within an ellipse twice as wide as it is tall.
+    Mollweide,
+    /// Compromise between equal area and conformality, with flattened poles.
+    Robinson,
+    /// The western and eastern hemispheres as seen from far away, side by side.
+    Orthographic,
+}
+
+impl MapProjection {
+    pub const ALL: [Self; 4] = [
+        Self::Equirectangular,
+        Self::Mollweide,
+        Self::Robinson,
+        Self::Orthographic,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Equirectangular => "Equirectangular",
+            Self::Mollweide => "Mollweide",
+            Self::Robinson => "Robinson",
+            Self::Orthographic => "Orthographic",
+        }
+    }
+
+    /// Half the width and height of the whole map.
+    #[must_use]
+    pub fn extent(self) -> Vec2 {
+        match self {
+            Self::Equirectangular => Vec2::new(PI, FRAC_PI_2),
+            Self::Mollweide => Vec2::new(2.0 * SQRT_2, SQRT_2),
+            Self::Robinson => Vec2::new(0.8487 * PI, 1.3523),
+            Self::Orthographic => Vec2::new(ORTHOGRAPHIC_OFFSET + 1.0, 1.0),
+        }
+    }
+
+    /// Whether the map is cut along the antimeridian, so that features crossing it are drawn on
+    /// both edges.
+    #[must_use]
+    pub fn wraps(self) -> bool {
+        self != Self::Orthographic
+    }
+
+    /// Position on the map of a point at a latitude and longitude, in radians.
+    ///
+    /// Longitudes may run past ±π so that a feature crossing the antimeridian stays in one piece.
+    /// `reference` is the longitude of the feature being drawn, which picks the hemisphere it is
+    /// drawn on by the orthographic projection.
+    #[must_use]
+    pub fn project(self, latitude: f32, longitude: f32, reference: f32) -> Vec2 {
+        match self {
+            Self::Equirectangular => Vec2::new(longitude, latitude),
+            Self::Mollweide => {
+                let theta = mollweide_angle(latitude);
+                Vec2::new(
+                    2.0 * SQRT_2 / PI * longitude * theta.cos(),
+                    SQRT_2 * theta.sin(),
+                )
+            }
+            Self::Robinson => {
+                let position = (latitude.abs().to_degrees() / 5.0).min(18.0);
+                let row = (position as usize).min(17);
+                let t = position - row as f32;
+                let lerp = |table: &[f32; 19]| table[row] + (table[row + 1] - table[row]) * t;
+                Vec2::new(
+                    0.8487 * lerp(&ROBINSON_X) * longitude,
+                    1.3523 * lerp(&ROBINSON_Y) * latitude.signum(),
+                )
+            }
+            Self::Orthographic => {
+                let (center, offset) = if reference < 0.0 {
+                    (ORTHOGRAPHIC_CENTERS[0], -ORTHOGRAPHIC_OFFSET)
+                } else {
+                    (ORTHOGRAPHIC_CENTERS[1], ORTHOGRAPHIC_OFFSET)
+                };
+                Vec2::new(
+                    offset + latitude.cos() * (longitude - center).sin(),
+                    latitude.sin(),
+                )
+            }
+        }
+    }
+}
+
+/// Auxiliary angle θ of the Mollweide projection, solving `2θ + sin 2θ = π sin φ` by Newton's
+/// method.
+fn mollweide_angle(latitude: f32) -> f32 {
+    if FRAC_PI_2 - latitude.abs() < 1.0e-4 {
+        return latitude;
+    }
+    let target = PI * latitude.sin();
+    let mut theta = latitude;
+    for _ in 0..16 {
+        let step = (2.0 * theta + (2.0 * theta).sin() - target) / (2.0 + 2.0 * (2.0 * theta).cos());
+        theta -= step;
+        if step.abs() < 1.0e-6 {
+            break;
+        }
+    }
+    theta
+}
+
+/// The flat map drawn alongside the globe.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct MapView {
+    /// Projection of the map, or `None` to hide it.
+    pub projection: Option<MapProjection>,
+    /// Width of the map as a fraction of the window's.
+    pub width: f32,
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        Self {
+            projection: None,
+            width: DEFAULT_MAP_WIDTH,
+        }
+    }
+}
+
+impl MapView {
+    /// The next projection in `MapProjection::ALL`, hiding the map after the last one.
+    #[must_use]
+    pub fn cycled(&self) -> Option<MapProjection> {
+        match self.projection {
+            None => Some(MapProjection::ALL[0]),
+            Some(projection) => MapProjection::ALL
+                .iter()
+                .skip_while(|&&candidate| candidate != projection)
+                .nth(1)
+                .copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec2, expected: Vec2, projection: MapProjection) {
+        assert!(
+            actual.distance(expected) < 1.0e-4,
+            "{}: {actual} instead of {expected}",
+            projection.name()
+        );
+    }
+
+    #[test]
+    fn origin_maps_to_the_centre() {
+        for projection in MapProjection::ALL {
+            if projection == MapProjection::Orthographic {
+                // Each hemisphere is centred on its own disc.
+                for (index, center) in ORTHOGRAPHIC_CENTERS.into_iter().enumerate() {
+                    let offset = if index == 0 { -1.0 } else { 1.0 } * ORTHOGRAPHIC_OFFSET;
+                    let position = projection.project(0.0, center, center);
+                    assert_near(position, Vec2::new(offset, 0.0), projection);
+                }
+            } else {
+                assert_near(projection.project(0.0, 0.0, 0.0), Vec2::ZERO, projection);
+            }
+        }
+    }
+
+    #[test]
+    fn poles_and_antimeridian_land_on_the_edges() {
+        for projection in MapProjection::ALL {
+            let extent = projection.extent();
+            for sign in [-1.0, 1.0] {
+                let pole = projection.project(sign * FRAC_PI_2, sign * 0.3, sign);
+                assert!(
+                    (pole.y - sign * extent.y).abs() < 1.0e-4,
+                    "{}: pole at {pole}",
+                    projection.name()
+                );
+                let antimeridian = projection.project(0.0, sign * PI, sign);
+                assert_near(antimeridian, Vec2::new(sign * extent.x, 0.0), projection);
+            }
+
+            // Everything else lies within the map.
+            for latitude in (-18..=18).map(|step| step as f32 * 5.0_f32.to_radians()) {
+                for longitude in (-36..=36).map(|step| step as f32 * 5.0_f32.to_radians()) {
+                    let position = projection.project(latitude, longitude, longitude);
+                    assert!(
+                        position.abs().cmple(extent + 1.0e-4).all(),
+                        "{}: {position} outside {extent}",
+                        projection.name()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mollweide_angle_converges_near_the_poles() {
+        for degrees in [
+            0.0, 30.0, 60.0, 85.0, 89.0, 89.9, 89.99, 89.993, -45.0, -89.9, -89.99, -89.993,
+        ] {
+            let latitude = f32::to_radians(degrees);
+            let theta = mollweide_angle(latitude);
+            let residual = 2.0 * theta + (2.0 * theta).sin() - PI * latitude.sin();
+            assert!(residual.abs() < 1.0e-5, "{degrees}°: residual {residual}");
+            assert!(theta.abs() <= FRAC_PI_2 && theta * latitude >= 0.0);
+        }
+        assert_eq!(mollweide_angle(FRAC_PI_2), FRAC_PI_2);
+        assert_eq!(mollweide_angle(-FRAC_PI_2), -FRAC_PI_2);
+    }
+
+    #[test]
+    fn cycling_visits_every_projection_then_hides_the_map() {
+        let mut view = MapView::default();
+        let mut visited = Vec::new();
+        for _ in 0..MapProjection::ALL.len() {
+            view.projection = view.cycled();
+            visited.extend(view.projection);
+        }
+        assert_eq!(visited, MapProjection::ALL);
+        view.projection = view.cycled();
+        assert_eq!(view.projection, None);
+        assert_eq!(view.cycled(), Some(MapProjection::ALL[0]));
+    }
+}
@@ -1,3 +1,19 @@
+pub mod brush;
+pub mod camera_settings;
+pub mod cell_field_buffer;
+pub mod cell_inspector;
+pub mod cell_locator;
+pub mod cell_pressures;
+pub mod field_display;
+pub mod field_statistics;
 pub mod mantle_grid;
+pub mod map_view;
+pub mod overlays;
+pub mod planet_cursor;
+pub mod plates;
 pub mod pressure_buffers;
-pub mod vertex_pressure_buffer;
+pub mod simulation_clock;
+pub mod simulation_config;
+pub mod velocity_field;
+pub mod vertex_elevation_buffer;
+pub mod vertex_field_buffer;
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+/// Overlays drawn over the planet, toggled from the control panel. The grid's own debug overlays
+/// are gizmo config groups of `GridDebugOverlayPlugin`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Overlays {
+    pub boundaries: bool,
+    /// Plate velocity arrows.
+    pub velocities: bool,
+    /// Streamlines of mantle flow.
+    pub streamlines: bool,
+    pub hotspots: bool,
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Self {
+            boundaries: false,
+            velocities: false,
+            streamlines: false,
+            hotspots: true,
+        }
+    }
+}
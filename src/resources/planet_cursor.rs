@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+/// Where the mouse cursor points at the planet.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct PlanetCursor {
+    /// Point under the cursor on the unit sphere, or `None` when the cursor is off the planet,
+    /// over the map or outside the window.
+    pub point: Option<Vec3>,
+    /// Cell containing `point`.
+    pub cell: Option<usize>,
+    /// Position of the cursor in the window, in logical pixels.
+    pub screen_position: Option<Vec2>,
+    /// Whether the cursor is over the map's viewport, which hides the globe beneath it.
+    pub over_map: bool,
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::resources::mantle_grid::MantleGrid;
+
+/// Rigid lithospheric plates. Each cell refers to one of them through `CellData::plate`.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Plates(pub Vec<Plate>);
+
+#[derive(Debug, Clone)]
+pub struct Plate {
+    /// Unit vector through the plate's Euler pole.
+    pub euler_pole: Vec3,
+    /// Rotation rate about the Euler pole, in radians per year.
+    pub angular_speed: f32,
+}
+
+impl Default for Plate {
+    /// A plate at rest.
+    fn default() -> Self {
+        Self {
+            euler_pole: Vec3::Y,
+            angular_speed: 0.0,
+        }
+    }
+}
+
+impl Plate {
+    /// Rotation of the plate over `dt` years.
+    #[must_use]
+    pub fn rotation(&self, dt: f32) -> Quat {
+        Quat::from_axis_angle(self.euler_pole, self.angular_speed * dt)
+    }
+
+    /// Velocity of the plate at `point` on the unit sphere, in radians per year.
+    #[must_use]
+    pub fn surface_velocity(&self, point: Vec3) -> Vec3 {
+        self.angular_speed * self.euler_pole.cross(point)
+    }
+}
+
+impl Plates {
+    /// Creates one plate at rest per seed and assigns every cell to the plate with the nearest
+    /// seed.
+    pub fn from_seeds(grid: &mut MantleGrid, seeds: &[Vec3]) -> Self {
+        for cell in &mut grid.cells {
+            cell.plate = seeds
+                .iter()
+                .enumerate()
+                .max_by(|a, b| cell.center.dot(*a.1).total_cmp(&cell.center.dot(*b.1)))
+                .map_or(0, |(plate, _)| plate);
+        }
+        Self(vec![Plate::default(); seeds.len()])
+    }
+}
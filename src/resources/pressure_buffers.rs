@@ -6,13 +6,18 @@ use bevy::{
     },
 };
 
-use crate::resources::mantle_grid::MantleGrid;
+use crate::resources::mantle_grid::{CellField, MantleGrid};
+
 const MAX_TRIANGLES_PER_VERTEX: usize = 8;
 
 #[derive(Resource)]
 pub struct PressureBuffers {
     pub pressure_buffer_a: Buffer,
     pub pressure_buffer_b: Buffer,
+    pub elevation_buffer: Buffer,
+    /// Per-cell values of the displayed field when it is neither pressure nor elevation, which
+    /// already have buffers of their own.
+    pub field_buffer: Buffer,
     pub neighbors_buffer: Buffer,
     pub vertex_triangles_buffer: Buffer,
     pub num_cells: u32,
@@ -20,6 +25,20 @@ pub struct PressureBuffers {
     pub current_read: bool,
 }
 
+impl PressureBuffers {
+    /// Per-cell values of `field`. Pressure only lives on the GPU; the other fields are uploaded
+    /// from the main world.
+    #[must_use]
+    pub fn cell_values(&self, field: CellField) -> &Buffer {
+        match field {
+            CellField::Pressure if self.current_read => &self.pressure_buffer_b,
+            CellField::Pressure => &self.pressure_buffer_a,
+            CellField::Elevation => &self.elevation_buffer,
+            _ => &self.field_buffer,
+        }
+    }
+}
+
 pub fn prepare_buffers(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
@@ -34,6 +53,7 @@ pub fn prepare_buffers(
     let num_vertices = grid.sphere.raw_points().len() as u32;
 
     let pressures: Vec<f32> = grid.cells.iter().map(|c| c.pressure).collect();
+    let elevations: Vec<f32> = grid.cells.iter().map(|c| c.elevation).collect();
     let neighbors: Vec<u32> = grid
         .neighbors
         .iter()
@@ -63,6 +83,18 @@ pub fn prepare_buffers(
         usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
     });
 
+    let elevation_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("elevation_buffer"),
+        contents: bytemuck::cast_slice(&elevations),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    });
+
+    let field_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("field_buffer"),
+        contents: bytemuck::cast_slice(&elevations),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    });
+
     let vertex_triangles_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
         label: Some("vertex_triangles_buffer"),
         contents: bytemuck::cast_slice(&vertex_triangles_flat),
@@ -78,6 +110,8 @@ pub fn prepare_buffers(
     commands.insert_resource(PressureBuffers {
         pressure_buffer_a,
         pressure_buffer_b,
+        elevation_buffer,
+        field_buffer,
         neighbors_buffer,
         vertex_triangles_buffer,
         num_cells,
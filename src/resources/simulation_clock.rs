@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+/// Tracks the progress of the CPU-side simulation.
+#[derive(Resource, Debug, Clone)]
+pub struct SimulationClock {
+    /// Number of steps taken since the start of the run.
+    pub step: u64,
+    /// Simulated time since the start of the run, in years.
+    pub elapsed: f64,
+    /// Simulated years advanced by each step.
+    pub time_step: f32,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self {
+            step: 0,
+            elapsed: 0.0,
+            time_step: 1.0e6,
+        }
+    }
+}
+
+/// Whether and how fast the simulation advances, as set from the control panel.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SimulationControl {
+    pub paused: bool,
+    /// Steps taken every frame while the simulation runs.
+    pub steps_per_frame: u32,
+    /// Single steps requested while paused, taken on the next frame.
+    pub pending_steps: u32,
+}
+
+impl Default for SimulationControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            steps_per_frame: 1,
+            pending_steps: 0,
+        }
+    }
+}
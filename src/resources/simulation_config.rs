@@ -0,0 +1,102 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::{
+        geojson::GeoJsonExport, import::ImportSettings, mesh::MeshExport,
+        plate_statistics::PlateStatisticsSettings, raster::RasterExport,
+        time_series::TimeSeriesSettings,
+    },
+    resources::camera_settings::CameraSettings,
+    simulation::{initial_conditions::InitialConditions, scenario::ScenarioSettings},
+};
+
+/// Settings of a run, read from a TOML file and overridden from the command line.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub scenario: ScenarioSettings,
+    pub initial_conditions: InitialConditions,
+    /// Real elevation and plate data replacing the scenario's.
+    pub import: ImportSettings,
+    /// Number of steps after which a headless run stops, or 0 to run forever.
+    pub steps: u64,
+    /// Rasters written when a headless run stops.
+    pub raster_exports: Vec<RasterExport>,
+    /// Meshes written when a headless run stops.
+    pub mesh_exports: Vec<MeshExport>,
+    /// Plate boundaries and coastlines written as GeoJSON when a headless run stops.
+    pub geojson_exports: Vec<GeoJsonExport>,
+    /// Store that headless runs append the per-cell fields to every few steps.
+    pub time_series: Option<TimeSeriesSettings>,
+    /// Table that headless runs append plate statistics to every few steps.
+    pub plate_statistics: Option<PlateStatisticsSettings>,
+    /// Limits, controls and bookmarks of the windowed viewer's camera.
+    pub camera: CameraSettings,
+}
+
+impl SimulationConfig {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Replaces the `[camera]` section of the file at `path`, leaving the rest of its settings
+    /// as they are, though not its comments or layout. The file is created if it is missing.
+    pub fn save_camera(path: &Path, camera: &CameraSettings) -> Result<(), ConfigError> {
+        let mut table = match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str::<toml::Table>(&contents)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+            Err(error) => return Err(error.into()),
+        };
+        table.insert("camera".to_owned(), toml::Value::try_from(camera)?);
+        fs::write(path, toml::to_string(&table)?)?;
+        Ok(())
+    }
+}
+
+/// File the configuration was read from, which the windowed viewer saves its camera settings to.
+#[derive(Resource, Debug, Clone)]
+pub struct ConfigFile(pub PathBuf);
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to access configuration: {error}"),
+            Self::Parse(error) => write!(f, "invalid configuration: {error}"),
+            Self::Serialize(error) => write!(f, "failed to write configuration: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::Serialize(error)
+    }
+}
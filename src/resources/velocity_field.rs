@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+/// Default number of plate velocity arrows spread over the planet.
+pub const DEFAULT_ARROW_COUNT: usize = 2000;
+/// Default number of mantle flow streamlines.
+pub const DEFAULT_STREAMLINE_COUNT: usize = 300;
+
+/// How densely the velocity overlays sample the planet. Whether they are shown is up to
+/// `Overlays`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct VelocityField {
+    /// Number of plate velocity arrows, placed evenly over the sphere.
+    pub arrow_count: usize,
+    /// Number of streamlines of mantle flow, seeded evenly over the sphere.
+    pub streamline_count: usize,
+}
+
+impl Default for VelocityField {
+    fn default() -> Self {
+        Self {
+            arrow_count: DEFAULT_ARROW_COUNT,
+            streamline_count: DEFAULT_STREAMLINE_COUNT,
+        }
+    }
+}
@@ -3,4 +3,4 @@ use bevy::render::extract_resource::ExtractResource;
 use bevy::render::storage::ShaderStorageBuffer;
 
 #[derive(Resource, ExtractResource, Clone)]
-pub struct VertexPressureBufferHandle(pub Handle<ShaderStorageBuffer>);
+pub struct VertexElevationBufferHandle(pub Handle<ShaderStorageBuffer>);
@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::storage::ShaderStorageBuffer;
+
+/// Per-vertex values of the field selected by `FieldDisplay`, averaged from the cells on the GPU.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct VertexFieldBufferHandle(pub Handle<ShaderStorageBuffer>);
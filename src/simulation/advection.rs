@@ -0,0 +1,83 @@
+//! Semi-Lagrangian transport of the crustal fields by rigid plate rotation.
+
+use bevy::prelude::*;
+
+use crate::resources::{
+    cell_locator::CellLocator,
+    mantle_grid::{CellData, MantleGrid},
+    plates::Plates,
+};
+
+/// Moves elevation, age and plate membership with the plates over `dt` years, and ages the crust.
+///
+/// Each cell looks up the point its plate carried onto it and reconstructs the crustal fields
+/// there from a linear fit over the cells of the same plate around the cell containing that
+/// departure point. Mantle fields such as temperature and pressure stay in place.
+pub fn advect(grid: &mut MantleGrid, locator: &CellLocator, plates: &Plates, dt: f32) {
+    let mut advected = Vec::with_capacity(grid.cells.len());
+    for data in &grid.cells {
+        let Some(plate) = plates.0.get(data.plate) else {
+            advected.push((data.elevation, data.age, data.plate));
+            continue;
+        };
+
+        let departure = plate.rotation(-dt) * data.center;
+        let source = locator.locate(grid, departure);
+        let source_plate = grid.cells[source].plate;
+        let stencil: Vec<usize> = grid.neighbors[source]
+            .iter()
+            .copied()
+            .filter(|&n| grid.cells[n].plate == source_plate)
+            .collect();
+
+        let elevation = reconstruct(grid, source, &stencil, departure, |c| c.elevation);
+        let age = reconstruct(grid, source, &stencil, departure, |c| c.age);
+        advected.push((elevation, age, source_plate));
+    }
+
+    for (data, (elevation, age, plate)) in grid.cells.iter_mut().zip(advected) {
+        data.elevation = elevation;
+        data.age = age + dt;
+        data.plate = plate;
+    }
+}
+
+/// Evaluates a field at `point` from a least-squares gradient fitted in the tangent plane of
+/// `cell` to its `stencil`. The result is clamped to the stencil's range so that the
+/// reconstruction never creates new extrema.
+fn reconstruct(
+    grid: &MantleGrid,
+    cell: usize,
+    stencil: &[usize],
+    point: Vec3,
+    field: impl Fn(&CellData) -> f32,
+) -> f32 {
+    let center = grid.cells[cell].center;
+    let value = field(&grid.cells[cell]);
+    if stencil.len() < 2 {
+        return value;
+    }
+
+    let (east, north) = center.any_orthonormal_pair();
+    let project = |p: Vec3| Vec2::new((p - center).dot(east), (p - center).dot(north));
+
+    // Normal equations of the fit `value_n - value = gradient . offset_n`.
+    let mut normal = Mat2::ZERO;
+    let mut rhs = Vec2::ZERO;
+    let mut min = value;
+    let mut max = value;
+    for &n in stencil {
+        let offset = project(grid.cells[n].center);
+        let difference = field(&grid.cells[n]) - value;
+        normal += Mat2::from_cols(offset * offset.x, offset * offset.y);
+        rhs += offset * difference;
+        min = min.min(value + difference);
+        max = max.max(value + difference);
+    }
+    if normal.determinant().abs() <= f32::EPSILON {
+        return value;
+    }
+
+    let gradient = normal.inverse() * rhs;
+    (value + gradient.dot(project(point))).clamp(min, max)
+}
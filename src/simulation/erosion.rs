@@ -0,0 +1,314 @@
+//! Surface processes acting on the elevation field: hillslope diffusion, stream-power fluvial
+//! incision along steepest-descent flow paths, and deposition of the eroded sediment in closed
+//! basins and on the continental shelves.
+
+use bevy::prelude::*;
+
+use crate::resources::mantle_grid::{MantleGrid, PLANET_RADIUS};
+
+/// Maximum number of cells sediment may spill across before it is dumped where it stands.
+const MAX_DEPOSITION_HOPS: usize = 32;
+/// Largest stable coefficient for a single explicit diffusion sub-step.
+const MAX_DIFFUSION_COEFFICIENT: f32 = 0.25;
+
+#[derive(Resource, Debug, Clone)]
+pub struct ErosionParameters {
+    /// Hillslope diffusivity, in m²/yr. Effective values are large because a cell spans hundreds
+    /// of kilometres.
+    pub hillslope_diffusivity: f32,
+    /// Stream-power erodibility `K` in `E = K A^m S^n`.
+    pub erodibility: f32,
+    /// Drainage area exponent `m`.
+    pub area_exponent: f32,
+    /// Slope exponent `n`.
+    pub slope_exponent: f32,
+    /// Elevation of the sea surface, in metres.
+    pub sea_level: f32,
+    /// Depth below sea level up to which sediment builds the continental shelf, in metres.
+    pub shelf_depth: f32,
+}
+
+impl Default for ErosionParameters {
+    fn default() -> Self {
+        Self {
+            hillslope_diffusivity: 1.0e4,
+            erodibility: 1.0e-7,
+            area_exponent: 0.5,
+            slope_exponent: 1.0,
+            sea_level: 0.0,
+            shelf_depth: 200.0,
+        }
+    }
+}
+
+/// Geometry of the cells in metres, shared by the diffusion and incision steps. The grid's
+/// topology never changes, so it is computed once with the grid.
+#[derive(Resource, Debug, Clone)]
+pub struct CellGeometry {
+    areas: Vec<f32>,
+    /// Distance between cell centres, parallel to `MantleGrid::neighbors`.
+    distances: Vec<Vec<f32>>,
+    /// Length of the shared edge, parallel to `MantleGrid::neighbors`.
+    edge_lengths: Vec<Vec<f32>>,
+}
+
+impl CellGeometry {
+    #[must_use]
+    pub fn new(grid: &MantleGrid) -> Self {
+        let areas = (0..grid.cells.len())
+            .map(|cell| grid.cell_area(cell) * PLANET_RADIUS * PLANET_RADIUS)
+            .collect();
+
+        let mut distances = Vec::with_capacity(grid.cells.len());
+        let mut edge_lengths = Vec::with_capacity(grid.cells.len());
+        for (cell, neighbors) in grid.neighbors.iter().enumerate() {
+            let center = grid.cells[cell].center;
+            distances.push(
+                neighbors
+                    .iter()
+                    .map(|&n| center.angle_between(grid.cells[n].center) * PLANET_RADIUS)
+                    .collect(),
+            );
+            edge_lengths.push(
+                neighbors
+                    .iter()
+                    .map(|&n| grid.shared_edge_length(cell, n) * PLANET_RADIUS)
+                    .collect(),
+            );
+        }
+
+        Self {
+            areas,
+            distances,
+            edge_lengths,
+        }
+    }
+}
+
+/// Steepest-descent drainage network over the current elevations.
+struct FlowRouting {
+    /// Downstream neighbour of each cell, `None` for ocean cells and closed basins.
+    receivers: Vec<Option<usize>>,
+    /// Cells sorted from highest to lowest, so every cell precedes its receiver.
+    order: Vec<usize>,
+    /// Upstream drainage area of each cell including itself, in m².
+    drainage_areas: Vec<f32>,
+}
+
+impl FlowRouting {
+    fn new(grid: &MantleGrid, geometry: &CellGeometry, sea_level: f32) -> Self {
+        let receivers: Vec<Option<usize>> = (0..grid.cells.len())
+            .map(|cell| {
+                let elevation = grid.cells[cell].elevation;
+                if elevation <= sea_level {
+                    return None;
+                }
+                grid.neighbors[cell]
+                    .iter()
+                    .zip(&geometry.distances[cell])
+                    .map(|(&n, &d)| (n, (elevation - grid.cells[n].elevation) / d))
+                    .filter(|&(_, slope)| slope > 0.0)
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(n, _)| n)
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..grid.cells.len()).collect();
+        order.sort_by(|&a, &b| grid.cells[b].elevation.total_cmp(&grid.cells[a].elevation));
+
+        let mut drainage_areas = geometry.areas.clone();
+        for &cell in &order {
+            if let Some(receiver) = receivers[cell] {
+                drainage_areas[receiver] += drainage_areas[cell];
+            }
+        }
+
+        Self {
+            receivers,
+            order,
+            drainage_areas,
+        }
+    }
+}
+
+/// Advances the surface processes by `dt` years.
+pub fn erode(
+    grid: &mut MantleGrid,
+    geometry: &CellGeometry,
+    parameters: &ErosionParameters,
+    dt: f32,
+) {
+    diffuse_hillslopes(grid, geometry, parameters.hillslope_diffusivity, dt);
+
+    let routing = FlowRouting::new(grid, geometry, parameters.sea_level);
+    let mut sediment = vec![0.0f32; grid.cells.len()];
+    for &cell in &routing.order {
+        let Some(receiver) = routing.receivers[cell] else {
+            deposit(grid, geometry, parameters, cell, sediment[cell]);
+            continue;
+        };
+
+        let neighbor = grid.neighbors[cell]
+            .iter()
+            .position(|&n| n == receiver)
+            .expect("receiver is a neighbour");
+        // Deposition upstream may have raised the receiver since the flow was routed.
+        let drop = (grid.cells[cell].elevation - grid.cells[receiver].elevation).max(0.0);
+        let slope = drop / geometry.distances[cell][neighbor];
+        let incision = parameters.erodibility
+            * routing.drainage_areas[cell].powf(parameters.area_exponent)
+            * slope.powf(parameters.slope_exponent)
+            * dt;
+        // Never cut below the receiver, which would reverse the flow direction.
+        let incision = incision.min(drop);
+
+        grid.cells[cell].elevation -= incision;
+        sediment[receiver] += sediment[cell] + incision * geometry.areas[cell];
+    }
+}
+
+/// Explicit finite-volume diffusion of elevation, sub-stepped to remain stable.
+fn diffuse_hillslopes(grid: &mut MantleGrid, geometry: &CellGeometry, diffusivity: f32, dt: f32) {
+    let coefficients: Vec<Vec<f32>> = (0..grid.cells.len())
+        .map(|cell| {
+            geometry.edge_lengths[cell]
+                .iter()
+                .zip(&geometry.distances[cell])
+                .map(|(&l, &d)| diffusivity * l / (d * geometry.areas[cell]))
+                .collect()
+        })
+        .collect();
+
+    let max_coefficient = coefficients
+        .iter()
+        .map(|c| c.iter().sum::<f32>() * dt)
+        .fold(0.0f32, f32::max);
+    let substeps = (max_coefficient / MAX_DIFFUSION_COEFFICIENT)
+        .ceil()
+        .max(1.0) as usize;
+    let substep_dt = dt / substeps as f32;
+
+    let mut elevations: Vec<f32> = grid.cells.iter().map(|c| c.elevation).collect();
+    for _ in 0..substeps {
+        let previous = elevations.clone();
+        for (cell, elevation) in elevations.iter_mut().enumerate() {
+            let flux: f32 = grid.neighbors[cell]
+                .iter()
+                .zip(&coefficients[cell])
+                .map(|(&n, &c)| c * (previous[n] - previous[cell]))
+                .sum();
+            *elevation += flux * substep_dt;
+        }
+    }
+
+    for (cell, elevation) in grid.cells.iter_mut().zip(elevations) {
+        cell.elevation = elevation;
+    }
+}
+
+/// Places `volume` m³ of sediment starting at `cell`.
+///
+/// On land the sediment fills each cell up to its lowest unvisited neighbour before spilling into
+/// it, so closed basins fill up and overflow downhill. In the ocean it fills cells up to the shelf
+/// depth and spills towards deeper water. Whatever is left once the sediment stops moving is
+/// spread along the path it took so that mass is conserved.
+fn deposit(
+    grid: &mut MantleGrid,
+    geometry: &CellGeometry,
+    parameters: &ErosionParameters,
+    mut cell: usize,
+    mut volume: f32,
+) {
+    if volume <= 0.0 {
+        return;
+    }
+
+    let mut visited = Vec::with_capacity(MAX_DEPOSITION_HOPS);
+    for _ in 0..MAX_DEPOSITION_HOPS {
+        visited.push(cell);
+        let elevation = grid.cells[cell].elevation;
+        let is_ocean = elevation <= parameters.sea_level;
+        let lowest = grid.neighbors[cell]
+            .iter()
+            .copied()
+            .filter(|n| !visited.contains(n))
+            .min_by(|&a, &b| grid.cells[a].elevation.total_cmp(&grid.cells[b].elevation));
+        let ceiling = match lowest {
+            _ if is_ocean => parameters.sea_level - parameters.shelf_depth,
+            Some(lowest) => grid.cells[lowest].elevation,
+            None => elevation,
+        };
+
+        let capacity = (ceiling - elevation).max(0.0) * geometry.areas[cell];
+        let placed = volume.min(capacity);
+        grid.cells[cell].elevation += placed / geometry.areas[cell];
+        volume -= placed;
+        if volume <= 0.0 {
+            return;
+        }
+
+        match lowest {
+            Some(next) if !is_ocean || grid.cells[next].elevation < elevation => cell = next,
+            _ => break,
+        }
+    }
+
+    // Spread the remainder over the path so a long spill does not build a single spike.
+    let path_area: f32 = visited.iter().map(|&c| geometry.areas[c]).sum();
+    for &visited_cell in &visited {
+        grid.cells[visited_cell].elevation += volume / path_area;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total_volume(grid: &MantleGrid, geometry: &CellGeometry) -> f64 {
+        grid.cells
+            .iter()
+            .zip(&geometry.areas)
+            .map(|(cell, &area)| f64::from(cell.elevation) * f64::from(area))
+            .sum()
+    }
+
+    #[test]
+    fn erosion_conserves_mass() {
+        let mut grid = MantleGrid::new(6);
+        for cell in &mut grid.cells {
+            // Ridges and basins on land around an ocean, so that every process takes part.
+            let Vec3 { x, y, z } = cell.center;
+            cell.elevation = 3000.0 * (5.0 * x).sin() * (4.0 * y).cos() + 1500.0 * z;
+        }
+        let geometry = CellGeometry::new(&grid);
+        let parameters = ErosionParameters {
+            erodibility: 1.0e-5,
+            ..default()
+        };
+
+        let before = total_volume(&grid, &geometry);
+        let previous = grid.clone();
+        for _ in 0..3 {
+            erode(&mut grid, &geometry, &parameters, 1.0e6);
+        }
+        let after = total_volume(&grid, &geometry);
+
+        let changed = grid
+            .cells
+            .iter()
+            .zip(&previous.cells)
+            .filter(|(a, b)| a.elevation != b.elevation)
+            .count();
+        assert!(changed > 0, "erosion left the surface untouched");
+        let scale: f64 = grid
+            .cells
+            .iter()
+            .zip(&geometry.areas)
+            .map(|(cell, &area)| f64::from(cell.elevation.abs()) * f64::from(area))
+            .sum();
+        assert!(
+            (after - before).abs() <= 1.0e-4 * scale,
+            "volume went from {before} to {after}"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Geographic coordinates on the grid's unit sphere, with the north pole on +Y and longitude
+//! increasing eastwards from +X towards -Z.
+
+use bevy::{math::DVec3, prelude::*};
+
+/// A position on the planet, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLon {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl LatLon {
+    #[must_use]
+    pub const fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Coordinates of a direction, which need not be normalised. Longitude lies in [-180, 180].
+    #[must_use]
+    pub fn from_unit_vector(point: Vec3) -> Self {
+        let point = point.as_dvec3().normalize_or_zero();
+        Self {
+            latitude: point.y.clamp(-1.0, 1.0).asin().to_degrees(),
+            longitude: (-point.z).atan2(point.x).to_degrees(),
+        }
+    }
+
+    #[must_use]
+    pub fn to_unit_vector(self) -> Vec3 {
+        let (sin_latitude, cos_latitude) = self.latitude.to_radians().sin_cos();
+        let (sin_longitude, cos_longitude) = self.longitude.to_radians().sin_cos();
+        DVec3::new(
+            cos_latitude * cos_longitude,
+            sin_latitude,
+            -cos_latitude * sin_longitude,
+        )
+        .as_vec3()
+    }
+
+    /// Great-circle angle to `other`, in radians, by the haversine formula.
+    #[must_use]
+    pub fn angular_distance(self, other: Self) -> f64 {
+        let (latitude_a, latitude_b) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let half_latitude = (latitude_b - latitude_a) / 2.0;
+        let half_longitude = (other.longitude - self.longitude).to_radians() / 2.0;
+        let haversine = half_latitude.sin().powi(2)
+            + latitude_a.cos() * latitude_b.cos() * half_longitude.sin().powi(2);
+        2.0 * haversine.sqrt().min(1.0).asin()
+    }
+
+    /// Great-circle distance to `other` on a sphere of `radius`, in the units of `radius`.
+    #[must_use]
+    pub fn distance(self, other: Self, radius: f64) -> f64 {
+        self.angular_distance(other) * radius
+    }
+
+    /// Initial bearing of the great circle towards `other`, in degrees clockwise from north in
+    /// [0, 360).
+    #[must_use]
+    pub fn bearing(self, other: Self) -> f64 {
+        let (latitude_a, latitude_b) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_longitude = (other.longitude - self.longitude).to_radians();
+        let y = delta_longitude.sin() * latitude_b.cos();
+        let x = latitude_a.cos() * latitude_b.sin()
+            - latitude_a.sin() * latitude_b.cos() * delta_longitude.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use crate::resources::mantle_grid::{MantleGrid, PLANET_RADIUS};
+
+    use super::*;
+
+    #[test]
+    fn axes_have_the_documented_coordinates() {
+        let cases = [
+            (Vec3::Y, 90.0, None),
+            (Vec3::NEG_Y, -90.0, None),
+            (Vec3::X, 0.0, Some(0.0)),
+            (Vec3::NEG_Z, 0.0, Some(90.0)),
+            (Vec3::Z, 0.0, Some(-90.0)),
+        ];
+        for (point, latitude, longitude) in cases {
+            let position = LatLon::from_unit_vector(point * 3.0);
+            assert!((position.latitude - latitude).abs() < 1.0e-9, "{point}");
+            if let Some(longitude) = longitude {
+                assert!((position.longitude - longitude).abs() < 1.0e-9, "{point}");
+            }
+        }
+    }
+
+    #[test]
+    fn unit_vectors_round_trip() {
+        for latitude in [-89.0, -45.0, 0.0, 30.0, 89.0] {
+            for longitude in [-179.0, -90.0, 0.0, 45.0, 179.0] {
+                let position = LatLon::new(latitude, longitude);
+                let point = position.to_unit_vector();
+                assert!((point.length() - 1.0).abs() < 1.0e-6);
+                let back = LatLon::from_unit_vector(point);
+                assert!((back.latitude - latitude).abs() < 1.0e-4, "{position:?}");
+                assert!((back.longitude - longitude).abs() < 1.0e-4, "{position:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn distances_and_bearings_follow_great_circles() {
+        let origin = LatLon::new(0.0, 0.0);
+        let north_pole = LatLon::new(90.0, 0.0);
+        let east = LatLon::new(0.0, 90.0);
+        assert!((origin.angular_distance(north_pole) - FRAC_PI_2).abs() < 1.0e-12);
+        assert!((origin.angular_distance(LatLon::new(0.0, 180.0)) - PI).abs() < 1.0e-12);
+        assert!((east.distance(north_pole, 2.0) - PI).abs() < 1.0e-12);
+        // Crossing the antimeridian takes the short way round.
+        let across = LatLon::new(0.0, 179.0).angular_distance(LatLon::new(0.0, -179.0));
+        assert!((across - 2.0f64.to_radians()).abs() < 1.0e-12);
+
+        assert!(origin.bearing(north_pole).abs() < 1.0e-9);
+        assert!((origin.bearing(east) - 90.0).abs() < 1.0e-9);
+        assert!((origin.bearing(LatLon::new(-10.0, 0.0)) - 180.0).abs() < 1.0e-9);
+        assert!((origin.bearing(LatLon::new(0.0, -10.0)) - 270.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn cell_areas_cover_the_planet() {
+        let grid = MantleGrid::new(4);
+        let total: f64 = (0..grid.cells.len())
+            .map(|cell| f64::from(grid.cell_area_km2(cell, PLANET_RADIUS)))
+            .sum();
+        let radius_km = f64::from(PLANET_RADIUS) / 1000.0;
+        let sphere = 4.0 * PI * radius_km * radius_km;
+        assert!(
+            (total - sphere).abs() < 1.0e-4 * sphere,
+            "{total} km² of {sphere}"
+        );
+
+        for cell in [0, grid.cells.len() / 2] {
+            let position = grid.cell_lat_lon(cell);
+            assert!(grid.contains(cell, position.to_unit_vector()));
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Volcanic uplift and mantle heating above hotspots.
+
+use crate::{components::hotspot::Hotspot, resources::mantle_grid::MantleGrid};
+
+/// Fraction of the peak uplift above which a cell is considered freshly resurfaced.
+const RESURFACING_THRESHOLD: f32 = 0.4;
+/// E-folding time over which temperature anomalies decay, in years.
+const THERMAL_RELAXATION_TIME: f32 = 5.0e7;
+
+/// Uplifts and heats the cells above `hotspot` over `dt` years, tapering linearly to zero at the
+/// edge of the plume. Cells near the centre are resurfaced, so their crustal age restarts, which
+/// is what makes the island chains age-progressive as the plate moves on.
+pub fn apply_hotspot(grid: &mut MantleGrid, hotspot: &Hotspot, dt: f32) {
+    for cell in &mut grid.cells {
+        let distance = cell.center.angle_between(hotspot.position);
+        if distance >= hotspot.radius {
+            continue;
+        }
+        let strength = 1.0 - distance / hotspot.radius;
+        cell.elevation += hotspot.uplift_rate * strength * dt;
+        cell.temperature += hotspot.heating_rate * strength * dt;
+        if strength > RESURFACING_THRESHOLD {
+            cell.age = 0.0;
+        }
+    }
+}
+
+/// Relaxes mantle temperature anomalies towards zero over `dt` years.
+pub fn cool_mantle(grid: &mut MantleGrid, dt: f32) {
+    let decay = (-dt / THERMAL_RELAXATION_TIME).exp();
+    for cell in &mut grid.cells {
+        cell.temperature *= decay;
+    }
+}
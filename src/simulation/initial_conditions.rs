@@ -0,0 +1,392 @@
+//! Pluggable generators for the initial values of per-cell fields.
+//!
+//! Every generator is deterministic: random generators draw from a ChaCha stream seeded
+//! explicitly in the configuration, so the same configuration yields bit-identical fields on a
+//! given platform.
+
+use std::{fmt, fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resources::mantle_grid::MantleGrid, simulation::spherical_harmonics::HarmonicCoefficients,
+};
+
+/// Produces one value per cell of a grid.
+pub trait FieldGenerator {
+    fn generate(&self, grid: &MantleGrid) -> Result<Vec<f32>, InitialConditionError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum InitialCondition {
+    /// The same value everywhere.
+    Constant { value: f32 },
+    /// Independent uniform values in `min..max` for every cell.
+    Random { seed: u64, min: f32, max: f32 },
+    /// Random spherical harmonic coefficients whose power per degree follows
+    /// `degree^spectral_exponent`, scaled to span `offset ± amplitude`.
+    SphericalHarmonics {
+        seed: u64,
+        min_degree: usize,
+        max_degree: usize,
+        spectral_exponent: f32,
+        amplitude: f32,
+        offset: f32,
+    },
+    /// Fractal Perlin noise sampled on the sphere, spanning roughly `offset ± amplitude`.
+    Noise {
+        seed: u64,
+        frequency: f32,
+        octaves: u32,
+        amplitude: f32,
+        offset: f32,
+    },
+    /// Synthesised from harmonic coefficients read with [`HarmonicCoefficients::read`],
+    /// optionally truncated to `max_degree`.
+    HarmonicFile {
+        path: PathBuf,
+        max_degree: Option<usize>,
+    },
+    /// One value per cell read from a file laid out as `format` says.
+    File {
+        path: PathBuf,
+        #[serde(default)]
+        format: ValueFormat,
+    },
+}
+
+/// Layout of a file of per-cell values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValueFormat {
+    /// One decimal value per line, blank lines being skipped.
+    #[default]
+    Text,
+    /// Raw little-endian `f32`s.
+    F32le,
+}
+
+impl Default for InitialCondition {
+    fn default() -> Self {
+        Self::Constant { value: 0.0 }
+    }
+}
+
+impl FieldGenerator for InitialCondition {
+    fn generate(&self, grid: &MantleGrid) -> Result<Vec<f32>, InitialConditionError> {
+        let centers = grid.cells.iter().map(|cell| cell.center);
+        let values = match self {
+            Self::Constant { value } => vec![*value; grid.cells.len()],
+            Self::Random { seed, min, max } => {
+                if min > max || min.is_nan() || max.is_nan() {
+                    return Err(InitialConditionError::InvalidRange {
+                        min: *min,
+                        max: *max,
+                    });
+                }
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                (0..grid.cells.len())
+                    .map(|_| rng.random_range(*min..=*max))
+                    .collect()
+            }
+            Self::SphericalHarmonics {
+                seed,
+                min_degree,
+                max_degree,
+                spectral_exponent,
+                amplitude,
+                offset,
+            } => {
+                let field =
+                    random_coefficients(*seed, *min_degree, *max_degree, *spectral_exponent)
+                        .synthesize(grid);
+                let peak = field.iter().fold(0.0f32, |peak, v| peak.max(v.abs()));
+                let scale = if peak > 0.0 { 1.0 / peak } else { 0.0 };
+                field
+                    .into_iter()
+                    .map(|v| offset + amplitude * v * scale)
+                    .collect()
+            }
+            Self::Noise {
+                seed,
+                frequency,
+                octaves,
+                amplitude,
+                offset,
+            } => {
+                let noise = PerlinNoise::new(*seed);
+                centers
+                    .map(|center| offset + amplitude * noise.fractal(center * *frequency, *octaves))
+                    .collect()
+            }
+            Self::HarmonicFile { path, max_degree } => {
+                HarmonicCoefficients::read(path, *max_degree)?.synthesize(grid)
+            }
+            Self::File { path, format } => read_values(path, *format)?,
+        };
+
+        if values.len() != grid.cells.len() {
+            return Err(InitialConditionError::CellCountMismatch {
+                expected: grid.cells.len(),
+                found: values.len(),
+            });
+        }
+        Ok(values)
+    }
+}
+
+/// Initial conditions for the mantle fields that scenarios do not set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InitialConditions {
+    pub pressure: InitialCondition,
+    pub temperature: InitialCondition,
+}
+
+impl Default for InitialConditions {
+    fn default() -> Self {
+        Self {
+            pressure: InitialCondition::Noise {
+                seed: 0,
+                frequency: 2.0,
+                octaves: 4,
+                amplitude: 4400.0,
+                offset: 4400.0,
+            },
+            temperature: InitialCondition::default(),
+        }
+    }
+}
+
+impl InitialConditions {
+    pub fn apply(&self, grid: &mut MantleGrid) -> Result<(), InitialConditionError> {
+        let generate = |field: &'static str, condition: &InitialCondition| {
+            condition
+                .generate(grid)
+                .map_err(|error| InitialConditionError::Field {
+                    field,
+                    error: Box::new(error),
+                })
+        };
+        let pressure = generate("pressure", &self.pressure)?;
+        let temperature = generate("temperature", &self.temperature)?;
+        for ((cell, pressure), temperature) in grid.cells.iter_mut().zip(pressure).zip(temperature)
+        {
+            cell.pressure = pressure;
+            cell.temperature = temperature;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum InitialConditionError {
+    Io(io::Error),
+    Parse {
+        line: usize,
+        value: String,
+    },
+    CellCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    TruncatedValues(usize),
+    /// A random range whose lower bound is above its upper bound, or that is not a number.
+    InvalidRange {
+        min: f32,
+        max: f32,
+    },
+    /// An error in the initial condition of the named field.
+    Field {
+        field: &'static str,
+        error: Box<InitialConditionError>,
+    },
+}
+
+impl fmt::Display for InitialConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read initial condition: {error}"),
+            Self::Parse { line, value } => {
+                write!(
+                    f,
+                    "invalid value {value:?} on line {line} of initial condition"
+                )
+            }
+            Self::CellCountMismatch { expected, found } => write!(
+                f,
+                "initial condition has {found} values but the grid has {expected} cells"
+            ),
+            Self::TruncatedValues(bytes) => write!(
+                f,
+                "initial condition of raw f32s is {bytes} bytes long, not a multiple of 4"
+            ),
+            Self::InvalidRange { min, max } => write!(
+                f,
+                "random initial condition has min = {min} above max = {max}"
+            ),
+            Self::Field { field, error } => write!(f, "{field}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for InitialConditionError {}
+
+impl From<io::Error> for InitialConditionError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+fn read_values(path: &PathBuf, format: ValueFormat) -> Result<Vec<f32>, InitialConditionError> {
+    if format == ValueFormat::F32le {
+        let bytes = fs::read(path)?;
+        if !bytes.len().is_multiple_of(4) {
+            return Err(InitialConditionError::TruncatedValues(bytes.len()));
+        }
+        return Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect());
+    }
+
+    let text = fs::read_to_string(path)?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            line.trim()
+                .parse()
+                .map_err(|_| InitialConditionError::Parse {
+                    line: index + 1,
+                    value: line.to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// Random coefficients whose power per degree follows `degree^spectral_exponent`.
+fn random_coefficients(
+    seed: u64,
+    min_degree: usize,
+    max_degree: usize,
+    spectral_exponent: f32,
+) -> HarmonicCoefficients {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut coefficients = HarmonicCoefficients::zeros(max_degree);
+    for l in min_degree.max(1)..=max_degree {
+        // Spread the power of the degree evenly over its 2l + 1 coefficients.
+        let power = (l as f64).powf(f64::from(spectral_exponent));
+        let deviation = (power / (2 * l + 1) as f64).sqrt();
+        for m in 0..=l as i32 {
+            coefficients.set(l, m, rng.random_range(-1.0..=1.0) * deviation);
+            if m > 0 {
+                coefficients.set(l, -m, rng.random_range(-1.0..=1.0) * deviation);
+            }
+        }
+    }
+    coefficients
+}
+
+/// Improved Perlin gradient noise in three dimensions, which is seamless when sampled on the
+/// sphere.
+struct PerlinNoise {
+    permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+    fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut table: Vec<u8> = (0..=255).collect();
+        table.shuffle(&mut rng);
+        let mut permutation = [0; 512];
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = table[index % 256];
+        }
+        Self { permutation }
+    }
+
+    /// Sums `octaves` octaves of doubling frequency and halving amplitude, normalised to stay
+    /// roughly within `-1..1`.
+    fn fractal(&self, point: Vec3, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut norm = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves.max(1) {
+            total += amplitude * self.sample(point * frequency);
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / norm
+    }
+
+    fn sample(&self, point: Vec3) -> f32 {
+        let floor = point.floor();
+        let cell = floor.as_ivec3();
+        let local = point - floor;
+        let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+
+        let hash = |x: i32, y: i32, z: i32| {
+            let p = &self.permutation;
+            let x = p[(x & 255) as usize] as usize;
+            let y = p[x + (y & 255) as usize] as usize;
+            p[y + (z & 255) as usize]
+        };
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let offset = local - Vec3::new(dx as f32, dy as f32, dz as f32);
+            gradient(hash(cell.x + dx, cell.y + dy, cell.z + dz), offset)
+        };
+
+        let x00 = corner(0, 0, 0).lerp(corner(1, 0, 0), fade.x);
+        let x10 = corner(0, 1, 0).lerp(corner(1, 1, 0), fade.x);
+        let x01 = corner(0, 0, 1).lerp(corner(1, 0, 1), fade.x);
+        let x11 = corner(0, 1, 1).lerp(corner(1, 1, 1), fade.x);
+        let y0 = x00.lerp(x10, fade.y);
+        let y1 = x01.lerp(x11, fade.y);
+        y0.lerp(y1, fade.z)
+    }
+}
+
+/// Dot product of `offset` with one of the twelve edge gradients selected by `hash`.
+fn gradient(hash: u8, offset: Vec3) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { offset.x } else { offset.y };
+    let v = match h {
+        0..4 => offset.y,
+        12 | 14 => offset.x,
+        _ => offset.z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_range_must_be_ordered() {
+        let grid = MantleGrid::new(2);
+        let condition = InitialCondition::Random {
+            seed: 0,
+            min: 1.0,
+            max: 0.0,
+        };
+        assert!(matches!(
+            condition.generate(&grid),
+            Err(InitialConditionError::InvalidRange { .. })
+        ));
+
+        let conditions = InitialConditions {
+            temperature: condition,
+            ..default()
+        };
+        let error = conditions.apply(&mut MantleGrid::new(2)).unwrap_err();
+        assert!(error.to_string().starts_with("temperature: "), "{error}");
+    }
+}
@@ -0,0 +1,9 @@
+pub mod advection;
+pub mod erosion;
+pub mod geography;
+pub mod hotspots;
+pub mod initial_conditions;
+pub mod plate_dynamics;
+pub mod plate_statistics;
+pub mod scenario;
+pub mod spherical_harmonics;
@@ -0,0 +1,511 @@
+//! Plate motions from a torque balance of the forces acting on each plate.
+//!
+//! Ridge push, slab pull and collisional resistance act along the plate boundaries, and basal
+//! drag couples each plate to the mantle flowing beneath it, down the mantle's pressure gradient.
+//! The resulting torques update each plate's angular momentum, from which its Euler pole and
+//! angular speed follow.
+
+use bevy::{
+    math::{DMat3, DVec3},
+    prelude::*,
+};
+
+use crate::resources::{
+    mantle_grid::{MantleGrid, PLANET_RADIUS},
+    plates::Plates,
+};
+
+pub const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+#[derive(Resource, Debug, Clone)]
+pub struct PlateDynamicsParameters {
+    /// Ridge push per unit length of spreading ridge, in N/m.
+    pub ridge_push: f64,
+    /// Slab pull per unit length of trench, in N/m.
+    pub slab_pull: f64,
+    /// Resistance of continental collisions per unit length and unit convergence rate, in
+    /// N·s/m².
+    pub collision_resistance: f64,
+    /// Basal shear stress per unit velocity relative to the mantle, in Pa·s/m.
+    pub basal_drag: f64,
+    /// Density of the lithosphere, in kg/m³.
+    pub lithosphere_density: f64,
+    /// Thickness of the lithosphere, in metres.
+    pub lithosphere_thickness: f64,
+    /// Elevation above which crust is buoyant enough to resist subduction, in metres.
+    pub continental_elevation: f32,
+    /// Boundaries whose normal convergence is below this fraction of the relative speed are
+    /// treated as transform faults.
+    pub transform_fraction: f32,
+    /// Relative speed below which a boundary is inactive and exerts no force, in radians per
+    /// year. Without it, plates at rest would be set moving by boundaries classified from noise.
+    pub min_boundary_speed: f32,
+    /// Mantle flow per unit pressure gradient, in m²/(Pa·yr).
+    pub mantle_mobility: f32,
+}
+
+impl Default for PlateDynamicsParameters {
+    fn default() -> Self {
+        Self {
+            ridge_push: 3.0e12,
+            slab_pull: 2.0e13,
+            collision_resistance: 3.0e22,
+            basal_drag: 1.5e15,
+            lithosphere_density: 3300.0,
+            lithosphere_thickness: 1.0e5,
+            continental_elevation: -1500.0,
+            transform_fraction: 0.3,
+            min_boundary_speed: 1.0e-10,
+            mantle_mobility: 30.0,
+        }
+    }
+}
+
+/// How two plates interact across a boundary edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundaryKind {
+    /// The plates move apart at a spreading ridge.
+    Ridge,
+    /// The plates converge and the plate owning `subducting` dives beneath the other.
+    Subduction { subducting: usize },
+    /// Two buoyant plates converge and neither subducts.
+    Collision,
+    /// The plates slide past each other, or barely move relative to each other.
+    Transform,
+}
+
+/// An edge between two cells belonging to different plates.
+#[derive(Debug, Clone, Copy)]
+pub struct BoundaryEdge {
+    pub cell: usize,
+    pub neighbor: usize,
+    /// Midpoint of the edge on the unit sphere.
+    pub midpoint: Vec3,
+    /// Tangent unit vector at `midpoint` pointing from `cell` towards `neighbor`.
+    pub normal: Vec3,
+    /// Length of the edge on the unit sphere.
+    pub length: f32,
+    /// Velocity of the plate of `cell` relative to the plate of `neighbor`, in radians per year.
+    pub relative_velocity: Vec3,
+    pub kind: BoundaryKind,
+}
+
+/// Finds every plate boundary edge once, oriented from the lower to the higher cell index.
+#[must_use]
+pub fn boundary_edges(
+    grid: &MantleGrid,
+    plates: &Plates,
+    parameters: &PlateDynamicsParameters,
+) -> Vec<BoundaryEdge> {
+    let mut edges = Vec::new();
+    for (cell, neighbors) in grid.neighbors.iter().enumerate() {
+        for &neighbor in neighbors {
+            let (a, b) = (&grid.cells[cell], &grid.cells[neighbor]);
+            if neighbor <= cell || a.plate == b.plate {
+                continue;
+            }
+            let (Some(plate_a), Some(plate_b)) = (plates.0.get(a.plate), plates.0.get(b.plate))
+            else {
+                continue;
+            };
+
+            let midpoint = (a.center + b.center).normalize();
+            let chord = b.center - a.center;
+            let normal = (chord - chord.dot(midpoint) * midpoint).normalize();
+            let relative_velocity =
+                plate_a.surface_velocity(midpoint) - plate_b.surface_velocity(midpoint);
+
+            let convergence = relative_velocity.dot(normal);
+            let speed = relative_velocity.length();
+            let buoyant = |elevation: f32| elevation > parameters.continental_elevation;
+            let kind = if speed < parameters.min_boundary_speed
+                || convergence.abs() < parameters.transform_fraction * speed
+            {
+                BoundaryKind::Transform
+            } else if convergence < 0.0 {
+                BoundaryKind::Ridge
+            } else if buoyant(a.elevation) && buoyant(b.elevation) {
+                BoundaryKind::Collision
+            } else {
+                // The older, then lower, side is the denser one and sinks.
+                let a_sinks = (a.age, -a.elevation) >= (b.age, -b.elevation);
+                BoundaryKind::Subduction {
+                    subducting: if a_sinks { cell } else { neighbor },
+                }
+            };
+
+            edges.push(BoundaryEdge {
+                cell,
+                neighbor,
+                midpoint,
+                normal,
+                length: grid.shared_edge_length(cell, neighbor),
+                relative_velocity,
+                kind,
+            });
+        }
+    }
+    edges
+}
+
+/// Sets `CellData::flux` from the mantle pressure of every cell: the flow across each edge runs
+/// down the pressure gradient towards the neighbour, in m/yr.
+pub fn update_mantle_flux(
+    grid: &mut MantleGrid,
+    pressure: impl Fn(&MantleGrid, usize) -> f32,
+    parameters: &PlateDynamicsParameters,
+) {
+    let flux: Vec<Vec<f32>> = (0..grid.cells.len())
+        .map(|cell| {
+            let center = grid.cells[cell].center;
+            grid.neighbors[cell]
+                .iter()
+                .map(|&neighbor| {
+                    let distance =
+                        center.angle_between(grid.cells[neighbor].center) * PLANET_RADIUS;
+                    let drop = pressure(grid, cell) - pressure(grid, neighbor);
+                    parameters.mantle_mobility * drop / distance
+                })
+                .collect()
+        })
+        .collect();
+    for (data, flux) in grid.cells.iter_mut().zip(flux) {
+        data.flux = flux;
+    }
+}
+
+/// Mantle velocity beneath a cell in m/yr, interpreting `CellData::flux` as the outflow across the
+/// edge shared with each neighbour.
+#[must_use]
+pub fn mantle_velocity(grid: &MantleGrid, cell: usize) -> Vec3 {
+    let center = grid.cells[cell].center;
+    grid.neighbors[cell]
+        .iter()
+        .zip(&grid.cells[cell].flux)
+        .map(|(&n, &flux)| {
+            let direction = grid.cells[n].center - center;
+            flux * (direction - direction.dot(center) * center).normalize_or_zero()
+        })
+        .sum()
+}
+
+/// Updates every plate's Euler pole and angular speed over `dt` years.
+///
+/// Basal drag and collisional resistance make the equations of motion extremely stiff, so the
+/// angular momentum is integrated implicitly: `(I + dt D) ω' = I ω + dt τ`, where `I` is the
+/// moment of inertia of the plate's cells, `D` the tensor of the resistances to the plate's own
+/// rotation and `τ` the torque of all the other forces. A collision resists the relative rotation
+/// of the two plates at the end of the step, which couples their equations, so the plates are
+/// solved for together. Plates too small to have a well-defined rotation keep theirs.
+pub fn solve_plate_motions(
+    grid: &MantleGrid,
+    plates: &mut Plates,
+    parameters: &PlateDynamicsParameters,
+    dt: f32,
+) {
+    let radius = f64::from(PLANET_RADIUS);
+    let dt = f64::from(dt) * SECONDS_PER_YEAR;
+    let num_plates = plates.0.len();
+    let mut inertia = vec![DMat3::ZERO; num_plates];
+    let mut drag = vec![DMat3::ZERO; num_plates];
+    let mut torque = vec![DVec3::ZERO; num_plates];
+    let omegas: Vec<DVec3> = plates
+        .0
+        .iter()
+        .map(|plate| (plate.euler_pole * plate.angular_speed).as_dvec3() / SECONDS_PER_YEAR)
+        .collect();
+
+    for (cell, data) in grid.cells.iter().enumerate() {
+        if data.plate >= num_plates {
+            continue;
+        }
+        let r = data.center.as_dvec3();
+        let area = f64::from(grid.cell_area(cell)) * radius * radius;
+        let projection = DMat3::IDENTITY - outer(r, r);
+        let mass = parameters.lithosphere_density * parameters.lithosphere_thickness * area;
+
+        inertia[data.plate] += projection * (mass * radius * radius);
+        drag[data.plate] += projection * (parameters.basal_drag * area * radius * radius);
+
+        // The mantle drags the plate along with it.
+        let mantle = mantle_velocity(grid, cell).as_dvec3() / SECONDS_PER_YEAR;
+        torque[data.plate] += r.cross(mantle) * (parameters.basal_drag * area * radius);
+    }
+
+    let mut collisions = Vec::new();
+    for edge in boundary_edges(grid, plates, parameters) {
+        let plate_a = grid.cells[edge.cell].plate;
+        let plate_b = grid.cells[edge.neighbor].plate;
+        let length = f64::from(edge.length) * radius;
+        let normal = edge.normal.as_dvec3();
+        let arm = edge.midpoint.as_dvec3() * radius;
+        // Forces on the plates of `cell` and `neighbor` respectively, in newtons.
+        let (force_a, force_b) = match edge.kind {
+            BoundaryKind::Ridge => {
+                let push = normal * (parameters.ridge_push * length);
+                (-push, push)
+            }
+            BoundaryKind::Subduction { subducting } => {
+                let pull = normal * (parameters.slab_pull * length);
+                if subducting == edge.cell {
+                    (pull, DVec3::ZERO)
+                } else {
+                    (DVec3::ZERO, -pull)
+                }
+            }
+            BoundaryKind::Collision => {
+                // The resistance is proportional to the convergence `R (ω_a - ω_b) · (m × n)`, so
+                // its torque on each plate is linear in both plates' rotations.
+                let axis = edge.midpoint.as_dvec3().cross(normal);
+                let stiffness = parameters.collision_resistance * length * radius * radius;
+                collisions.push((plate_a, plate_b, outer(axis, axis) * stiffness));
+                (DVec3::ZERO, DVec3::ZERO)
+            }
+            BoundaryKind::Transform => (DVec3::ZERO, DVec3::ZERO),
+        };
+
+        torque[plate_a] += arm.cross(force_a);
+        torque[plate_b] += arm.cross(force_b);
+    }
+
+    // One block row of three equations per plate, for the three components of its rotation.
+    let size = 3 * num_plates;
+    let mut matrix = vec![0.0; size * size];
+    let mut rhs = vec![0.0; size];
+    let free: Vec<bool> = (0..num_plates)
+        .map(|plate| (inertia[plate] + drag[plate] * dt).determinant().abs() > f64::EPSILON)
+        .collect();
+    for plate in 0..num_plates {
+        let (block, value) = if free[plate] {
+            (
+                inertia[plate] + drag[plate] * dt,
+                inertia[plate] * omegas[plate] + torque[plate] * dt,
+            )
+        } else {
+            (DMat3::IDENTITY, omegas[plate])
+        };
+        add_block(&mut matrix, size, plate, plate, block);
+        rhs[3 * plate..3 * plate + 3].copy_from_slice(&value.to_array());
+    }
+    for (plate_a, plate_b, resistance) in collisions {
+        for (plate, other) in [(plate_a, plate_b), (plate_b, plate_a)] {
+            if !free[plate] {
+                continue;
+            }
+            add_block(&mut matrix, size, plate, plate, resistance * dt);
+            if free[other] {
+                add_block(&mut matrix, size, plate, other, resistance * -dt);
+            } else {
+                let push = resistance * omegas[other] * dt;
+                for (value, push) in rhs[3 * plate..3 * plate + 3]
+                    .iter_mut()
+                    .zip(push.to_array())
+                {
+                    *value += push;
+                }
+            }
+        }
+    }
+    let Some(solution) = solve_linear_system(matrix, rhs) else {
+        return;
+    };
+
+    for (index, plate) in plates.0.iter_mut().enumerate() {
+        if !free[index] {
+            continue;
+        }
+        let omega = DVec3::from_slice(&solution[3 * index..3 * index + 3]);
+        let omega = (omega * SECONDS_PER_YEAR).as_vec3();
+
+        let speed = omega.length();
+        if speed > 0.0 {
+            plate.euler_pole = omega / speed;
+        }
+        plate.angular_speed = speed;
+    }
+}
+
+fn outer(a: DVec3, b: DVec3) -> DMat3 {
+    DMat3::from_cols(a * b.x, a * b.y, a * b.z)
+}
+
+/// Adds `block` to the 3×3 block of a row-major `size`×`size` matrix at block `row` and `column`.
+fn add_block(matrix: &mut [f64], size: usize, row: usize, column: usize, block: DMat3) {
+    for i in 0..3 {
+        for j in 0..3 {
+            matrix[(3 * row + i) * size + 3 * column + j] += block.col(j)[i];
+        }
+    }
+}
+
+/// Solves the row-major square system `matrix x = rhs` by Gaussian elimination with partial
+/// pivoting, or returns `None` if the matrix is singular.
+fn solve_linear_system(mut matrix: Vec<f64>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let size = rhs.len();
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| {
+                let magnitude = |row: usize| matrix[row * size + column].abs();
+                magnitude(a).total_cmp(&magnitude(b))
+            })
+            .filter(|&row| matrix[row * size + column] != 0.0)?;
+        if pivot != column {
+            for j in 0..size {
+                matrix.swap(pivot * size + j, column * size + j);
+            }
+            rhs.swap(pivot, column);
+        }
+
+        for row in column + 1..size {
+            let factor = matrix[row * size + column] / matrix[column * size + column];
+            if factor == 0.0 {
+                continue;
+            }
+            for j in column..size {
+                matrix[row * size + j] -= factor * matrix[column * size + j];
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+
+    for row in (0..size).rev() {
+        let known: f64 = (row + 1..size)
+            .map(|j| matrix[row * size + j] * rhs[j])
+            .sum();
+        rhs[row] = (rhs[row] - known) / matrix[row * size + row];
+    }
+    Some(rhs)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::plates::Plate;
+
+    use super::*;
+
+    /// Kinetic energy of the plates' rotation, up to the constant factors shared by every cell.
+    fn rotational_energy(grid: &MantleGrid, plates: &Plates) -> f64 {
+        grid.cells
+            .iter()
+            .enumerate()
+            .map(|(cell, data)| {
+                let plate = &plates.0[data.plate];
+                let velocity = plate.surface_velocity(data.center).as_dvec3();
+                0.5 * f64::from(grid.cell_area(cell)) * velocity.length_squared()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn single_plate_reaches_the_terminal_velocity_of_the_mantle_flow() {
+        let mut grid = MantleGrid::new(6);
+        let mut plates = Plates(vec![Plate::default()]);
+        // Flux across each edge as if the mantle turned rigidly about the z axis at 1 cm/yr.
+        let spin = Vec3::Z * 0.01;
+        for cell in 0..grid.cells.len() {
+            let center = grid.cells[cell].center;
+            let velocity = spin.cross(center);
+            grid.cells[cell].flux = grid.neighbors[cell]
+                .iter()
+                .map(|&n| {
+                    let direction = grid.cells[n].center - center;
+                    velocity.dot((direction - direction.dot(center) * center).normalize())
+                })
+                .collect();
+        }
+
+        // With uniform drag the plate comes to rest relative to the mantle on average: its
+        // rotation is the least-squares fit of a rigid rotation to the mantle velocities.
+        let mut moment = DMat3::ZERO;
+        let mut momentum = DVec3::ZERO;
+        for cell in 0..grid.cells.len() {
+            let r = grid.cells[cell].center.as_dvec3();
+            let area = f64::from(grid.cell_area(cell));
+            moment += (DMat3::IDENTITY - outer(r, r)) * area;
+            momentum += r.cross(mantle_velocity(&grid, cell).as_dvec3()) * area;
+        }
+        let terminal = moment.inverse() * momentum / f64::from(PLANET_RADIUS);
+
+        let parameters = PlateDynamicsParameters::default();
+        for _ in 0..3 {
+            solve_plate_motions(&grid, &mut plates, &parameters, 1.0e6);
+        }
+        let plate = &plates.0[0];
+        let omega = (plate.euler_pole * plate.angular_speed).as_dvec3();
+        assert!(
+            (omega - terminal).length() <= 1.0e-4 * terminal.length(),
+            "plate turns at {omega}, expected {terminal}"
+        );
+    }
+
+    #[test]
+    fn collisions_do_not_inject_energy() {
+        let mut grid = MantleGrid::new(8);
+        for cell in &mut grid.cells {
+            // A buoyant cap around +x turning into the rest of the, equally buoyant, planet.
+            cell.plate = usize::from(cell.center.x > 0.7);
+            cell.elevation = 1000.0;
+        }
+
+        // Collision resistance alone acts on the plates, from about as strong as their inertia
+        // over a step to far stronger.
+        for collision_resistance in [1.0e3, 1.0e5, 1.0e7] {
+            let mut plates = Plates(vec![
+                Plate::default(),
+                Plate {
+                    euler_pole: Vec3::Z,
+                    angular_speed: 1.0e-8,
+                },
+            ]);
+            let parameters = PlateDynamicsParameters {
+                ridge_push: 0.0,
+                slab_pull: 0.0,
+                basal_drag: 0.0,
+                collision_resistance,
+                ..default()
+            };
+            assert!(
+                boundary_edges(&grid, &plates, &parameters)
+                    .iter()
+                    .any(|edge| edge.kind == BoundaryKind::Collision)
+            );
+
+            let mut energy = rotational_energy(&grid, &plates);
+            for _ in 0..5 {
+                solve_plate_motions(&grid, &mut plates, &parameters, 1.0e6);
+                let next = rotational_energy(&grid, &plates);
+                assert!(
+                    next <= energy * (1.0 + 1.0e-5),
+                    "energy rose from {energy} to {next} at resistance {collision_resistance}"
+                );
+                energy = next;
+            }
+        }
+    }
+
+    #[test]
+    fn plates_stay_at_rest_without_mantle_flow() {
+        let mut grid = MantleGrid::new(6);
+        let mut plates = Plates::from_seeds(&mut grid, &[Vec3::X, Vec3::NEG_X, Vec3::Z]);
+        for cell in &mut grid.cells {
+            cell.pressure = 5.0;
+            cell.elevation = if cell.plate == 0 { 500.0 } else { -4000.0 };
+        }
+        let parameters = PlateDynamicsParameters::default();
+        update_mantle_flux(
+            &mut grid,
+            |grid, cell| grid.cells[cell].pressure,
+            &parameters,
+        );
+        assert!(
+            grid.cells
+                .iter()
+                .all(|cell| cell.flux.iter().all(|&flux| flux == 0.0))
+        );
+
+        solve_plate_motions(&grid, &mut plates, &parameters, 1.0e6);
+        for plate in &plates.0 {
+            assert_eq!(plate.angular_speed, 0.0);
+        }
+    }
+}
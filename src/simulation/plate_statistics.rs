@@ -0,0 +1,191 @@
+//! Plate-level and global metrics summarising the state of a run.
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    resources::{
+        mantle_grid::{MantleGrid, PLANET_RADIUS},
+        plates::Plates,
+    },
+    simulation::{
+        geography::LatLon,
+        plate_dynamics::{BoundaryKind, PlateDynamicsParameters, boundary_edges},
+    },
+};
+
+/// Lengths of plate boundary, in kilometres, by kind of boundary.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BoundaryLengths {
+    pub ridge: f32,
+    pub subduction: f32,
+    pub collision: f32,
+    pub transform: f32,
+}
+
+impl BoundaryLengths {
+    fn add(&mut self, kind: BoundaryKind, length: f32) {
+        match kind {
+            BoundaryKind::Ridge => self.ridge += length,
+            BoundaryKind::Subduction { .. } => self.subduction += length,
+            BoundaryKind::Collision => self.collision += length,
+            BoundaryKind::Transform => self.transform += length,
+        }
+    }
+
+    #[must_use]
+    pub fn total(&self) -> f32 {
+        self.ridge + self.subduction + self.collision + self.transform
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlateStatistics {
+    pub plate: usize,
+    pub cells: usize,
+    /// Surface area, in km².
+    pub area: f32,
+    /// Length of the plate's boundary, in kilometres.
+    pub perimeter: f32,
+    /// Area-weighted centre of the plate, in degrees.
+    pub centroid_latitude: f32,
+    pub centroid_longitude: f32,
+    /// Euler pole of the plate's rotation, in degrees.
+    pub euler_pole_latitude: f32,
+    pub euler_pole_longitude: f32,
+    /// Rotation rate about the Euler pole, in degrees per million years.
+    pub angular_speed: f32,
+    /// Fraction of the plate's area covered by buoyant continental crust.
+    pub continental_fraction: f32,
+    /// Area-weighted mean elevation, in metres.
+    pub mean_elevation: f32,
+    /// Area-weighted root mean square surface velocity, in mm/yr.
+    pub rms_velocity: f32,
+    pub boundaries: BoundaryLengths,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalStatistics {
+    /// Number of plates owning at least one cell.
+    pub plates: usize,
+    /// Fraction of the surface covered by buoyant continental crust.
+    pub continental_fraction: f32,
+    /// Area-weighted mean elevation, in metres.
+    pub mean_elevation: f32,
+    /// Area-weighted root mean square plate velocity, in mm/yr.
+    pub rms_velocity: f32,
+    /// Length of every boundary counted once, in kilometres.
+    pub boundaries: BoundaryLengths,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    pub plates: Vec<PlateStatistics>,
+    pub global: GlobalStatistics,
+}
+
+impl Statistics {
+    /// Gathers the statistics of every plate and of the whole planet. Crust above
+    /// `PlateDynamicsParameters::continental_elevation` counts as continental, as it does for
+    /// subduction.
+    #[must_use]
+    pub fn compute(
+        grid: &MantleGrid,
+        plates: &Plates,
+        parameters: &PlateDynamicsParameters,
+    ) -> Self {
+        let radius_km = PLANET_RADIUS / 1000.0;
+        let num_plates = plates.0.len();
+        let mut cells = vec![0; num_plates];
+        let mut areas = vec![0.0f32; num_plates];
+        let mut continental_areas = vec![0.0f32; num_plates];
+        let mut centroids = vec![Vec3::ZERO; num_plates];
+        let mut elevation_sums = vec![0.0f32; num_plates];
+        let mut squared_velocity_sums = vec![0.0f32; num_plates];
+        let mut total_area = 0.0;
+        let mut elevation_sum = 0.0;
+        let mut squared_velocity_sum = 0.0;
+
+        for (cell, data) in grid.cells.iter().enumerate() {
+            let area = grid.cell_area_km2(cell, PLANET_RADIUS);
+            let continental = data.elevation > parameters.continental_elevation;
+            total_area += area;
+            elevation_sum += data.elevation * area;
+            let Some(plate) = plates.0.get(data.plate) else {
+                continue;
+            };
+            cells[data.plate] += 1;
+            areas[data.plate] += area;
+            centroids[data.plate] += data.center * area;
+            elevation_sums[data.plate] += data.elevation * area;
+            if continental {
+                continental_areas[data.plate] += area;
+            }
+            // Radians per year on the unit sphere to millimetres per year.
+            let velocity = plate.surface_velocity(data.center).length() * PLANET_RADIUS * 1000.0;
+            squared_velocity_sums[data.plate] += velocity * velocity * area;
+            squared_velocity_sum += velocity * velocity * area;
+        }
+
+        let mut boundaries = vec![BoundaryLengths::default(); num_plates];
+        let mut global_boundaries = BoundaryLengths::default();
+        for edge in boundary_edges(grid, plates, parameters) {
+            let length = edge.length * radius_km;
+            boundaries[grid.cells[edge.cell].plate].add(edge.kind, length);
+            boundaries[grid.cells[edge.neighbor].plate].add(edge.kind, length);
+            global_boundaries.add(edge.kind, length);
+        }
+
+        let plate_statistics = plates
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, plate)| {
+                let (centroid_latitude, centroid_longitude) =
+                    latitude_longitude(centroids[index].normalize_or_zero());
+                let (euler_pole_latitude, euler_pole_longitude) =
+                    latitude_longitude(plate.euler_pole);
+                PlateStatistics {
+                    plate: index,
+                    cells: cells[index],
+                    area: areas[index],
+                    perimeter: boundaries[index].total(),
+                    centroid_latitude,
+                    centroid_longitude,
+                    euler_pole_latitude,
+                    euler_pole_longitude,
+                    angular_speed: plate.angular_speed.to_degrees() * 1.0e6,
+                    continental_fraction: ratio(continental_areas[index], areas[index]),
+                    mean_elevation: ratio(elevation_sums[index], areas[index]),
+                    rms_velocity: ratio(squared_velocity_sums[index], areas[index]).sqrt(),
+                    boundaries: boundaries[index],
+                }
+            })
+            .collect();
+
+        Self {
+            plates: plate_statistics,
+            global: GlobalStatistics {
+                plates: cells.iter().filter(|&&count| count > 0).count(),
+                continental_fraction: ratio(continental_areas.iter().sum(), total_area),
+                mean_elevation: ratio(elevation_sum, total_area),
+                rms_velocity: ratio(squared_velocity_sum, total_area).sqrt(),
+                boundaries: global_boundaries,
+            },
+        }
+    }
+}
+
+fn ratio(numerator: f32, denominator: f32) -> f32 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+/// Latitude and longitude of a direction, in degrees.
+fn latitude_longitude(point: Vec3) -> (f32, f32) {
+    let position = LatLon::from_unit_vector(point);
+    (position.latitude as f32, position.longitude as f32)
+}
@@ -0,0 +1,162 @@
+//! Procedurally generated starting states for reproducible supercontinent cycle experiments.
+
+use bevy::prelude::*;
+use clap::ValueEnum;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::hotspot::Hotspot,
+    resources::{mantle_grid::MantleGrid, plates::Plates},
+};
+
+/// Depth of the abyssal ocean floor, in metres.
+pub const OCEAN_DEPTH: f32 = 4000.0;
+/// Elevation of the continental interiors, in metres.
+pub const CONTINENT_ELEVATION: f32 = 500.0;
+/// Angular width over which continents slope down to the abyssal plain, in radians.
+const MARGIN_WIDTH: f32 = 0.06;
+/// Angular radius of the supercontinent, covering roughly a third of the surface.
+const SUPERCONTINENT_RADIUS: f32 = 1.2;
+/// Number of random lobes perturbing continental outlines.
+const OUTLINE_LOBES: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScenarioPreset {
+    /// A single Pangaea-like continent surrounded by one ocean.
+    #[default]
+    Supercontinent,
+    /// Several smaller continents scattered across the globe.
+    ScatteredContinents,
+    /// No continents at all.
+    OceanWorld,
+}
+
+/// Everything needed to start a run: the grid with its initial fields, the plates and the
+/// hotspots.
+pub struct Scenario {
+    pub grid: MantleGrid,
+    pub plates: Plates,
+    pub hotspots: Vec<Hotspot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioSettings {
+    pub preset: ScenarioPreset,
+    pub seed: u64,
+    pub subdivisions: usize,
+    pub num_plates: usize,
+    pub num_hotspots: usize,
+}
+
+impl Default for ScenarioSettings {
+    fn default() -> Self {
+        Self {
+            preset: ScenarioPreset::default(),
+            seed: 0,
+            subdivisions: 20,
+            num_plates: 8,
+            num_hotspots: 4,
+        }
+    }
+}
+
+/// A continent outline: a cap around `center` whose radius is perturbed by a few lobes.
+struct Continent {
+    center: Vec3,
+    radius: f32,
+    lobes: Vec<(Vec3, f32)>,
+}
+
+impl Continent {
+    fn random(rng: &mut ChaCha8Rng, radius: f32) -> Self {
+        Self {
+            center: random_direction(rng),
+            radius,
+            lobes: (0..OUTLINE_LOBES)
+                .map(|_| {
+                    (
+                        random_direction(rng),
+                        rng.random_range(-0.25..0.25) * radius,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Signed angular distance from `point` to the coastline, negative inside the continent.
+    fn signed_distance(&self, point: Vec3) -> f32 {
+        let perturbation: f32 = self
+            .lobes
+            .iter()
+            .map(|&(direction, amplitude)| amplitude * point.dot(direction))
+            .sum();
+        point.angle_between(self.center) - (self.radius + perturbation)
+    }
+}
+
+impl Scenario {
+    /// Generates the scenario described by `settings`. The same settings always produce the same
+    /// scenario.
+    #[must_use]
+    pub fn generate(settings: &ScenarioSettings) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+        let mut grid = MantleGrid::new(settings.subdivisions);
+
+        let continents = match settings.preset {
+            ScenarioPreset::Supercontinent => {
+                vec![Continent::random(&mut rng, SUPERCONTINENT_RADIUS)]
+            }
+            ScenarioPreset::ScatteredContinents => {
+                let count = rng.random_range(4..=7);
+                // Keep the total land area close to that of the supercontinent.
+                let radius = SUPERCONTINENT_RADIUS / (count as f32).sqrt();
+                (0..count)
+                    .map(|_| {
+                        let scale = rng.random_range(0.8..1.2);
+                        Continent::random(&mut rng, radius * scale)
+                    })
+                    .collect()
+            }
+            ScenarioPreset::OceanWorld => Vec::new(),
+        };
+
+        for cell in &mut grid.cells {
+            let distance = continents
+                .iter()
+                .map(|continent| continent.signed_distance(cell.center))
+                .fold(f32::INFINITY, f32::min);
+            let land = (0.5 - distance / MARGIN_WIDTH).clamp(0.0, 1.0);
+            cell.elevation = -OCEAN_DEPTH + land * (OCEAN_DEPTH + CONTINENT_ELEVATION);
+        }
+
+        let seeds: Vec<Vec3> = (0..settings.num_plates)
+            .map(|_| random_direction(&mut rng))
+            .collect();
+        let plates = Plates::from_seeds(&mut grid, &seeds);
+
+        let hotspots = (0..settings.num_hotspots)
+            .map(|_| Hotspot {
+                position: random_direction(&mut rng),
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            grid,
+            plates,
+            hotspots,
+        }
+    }
+}
+
+/// Uniformly distributed unit vector.
+fn random_direction(rng: &mut ChaCha8Rng) -> Vec3 {
+    let z: f32 = rng.random_range(-1.0..=1.0);
+    let azimuth: f32 = rng.random_range(0.0..std::f32::consts::TAU);
+    let horizontal = (1.0 - z * z).sqrt();
+    Vec3::new(horizontal * azimuth.cos(), z, horizontal * azimuth.sin())
+}
@@ -0,0 +1,436 @@
+//! Real spherical harmonics on the unit sphere, and the analysis and synthesis of per-cell fields.
+//!
+//! The harmonics are 4π fully normalised as is customary in geodesy, so that the mean of
+//! `Y_lm²` over the sphere is one, and carry no Condon-Shortley phase. The pole is the +Y axis and
+//! longitude increases from +X towards -Z.
+
+use std::{collections::HashSet, fmt::Write as _, fs, io, path::Path};
+
+use bevy::prelude::*;
+
+use crate::resources::mantle_grid::MantleGrid;
+
+/// Index of `P̄_lm` in a table produced by [`legendre_table`].
+#[must_use]
+pub fn legendre_index(degree: usize, order: usize) -> usize {
+    degree * (degree + 1) / 2 + order
+}
+
+/// Fully normalised associated Legendre functions `P̄_lm(cos θ)` for every `0 <= m <= l <=
+/// max_degree`, using the standard column-wise recursion which is stable to high degree.
+#[must_use]
+pub fn legendre_table(max_degree: usize, cos_colatitude: f64, sin_colatitude: f64) -> Vec<f64> {
+    let mut table = vec![0.0; legendre_index(max_degree, max_degree) + 1];
+    fill_legendre_table(&mut table, max_degree, cos_colatitude, sin_colatitude);
+    table
+}
+
+/// Fills `table`, of at least `legendre_index(max_degree, max_degree) + 1` entries, as
+/// [`legendre_table`] would return it.
+fn fill_legendre_table(
+    table: &mut [f64],
+    max_degree: usize,
+    cos_colatitude: f64,
+    sin_colatitude: f64,
+) {
+    table[0] = 1.0;
+
+    for m in 0..=max_degree {
+        if m > 0 {
+            let factor = if m == 1 {
+                3.0f64.sqrt()
+            } else {
+                ((2 * m + 1) as f64 / (2 * m) as f64).sqrt()
+            };
+            table[legendre_index(m, m)] =
+                factor * sin_colatitude * table[legendre_index(m - 1, m - 1)];
+        }
+        if m < max_degree {
+            table[legendre_index(m + 1, m)] =
+                ((2 * m + 3) as f64).sqrt() * cos_colatitude * table[legendre_index(m, m)];
+        }
+        for l in m + 2..=max_degree {
+            let (lf, mf) = (l as f64, m as f64);
+            let a = ((2.0 * lf - 1.0) * (2.0 * lf + 1.0) / ((lf - mf) * (lf + mf))).sqrt();
+            let b = ((2.0 * lf + 1.0) * (lf + mf - 1.0) * (lf - mf - 1.0)
+                / ((lf - mf) * (lf + mf) * (2.0 * lf - 3.0)))
+                .sqrt();
+            table[legendre_index(l, m)] = a * cos_colatitude * table[legendre_index(l - 1, m)]
+                - b * table[legendre_index(l - 2, m)];
+        }
+    }
+}
+
+/// Cosine and sine of the colatitude, and the longitude of a point on the unit sphere.
+#[must_use]
+pub fn spherical_coordinates(point: Vec3) -> (f64, f64, f64) {
+    let point = point.as_dvec3().normalize();
+    let cos_colatitude = point.y;
+    let sin_colatitude = point.x.hypot(point.z);
+    let longitude = (-point.z).atan2(point.x);
+    (cos_colatitude, sin_colatitude, longitude)
+}
+
+/// Evaluates the real harmonic of `degree` and `order` at `point`. Negative orders select the
+/// sine terms.
+#[must_use]
+pub fn real_spherical_harmonic(degree: usize, order: i32, point: Vec3) -> f64 {
+    let m = order.unsigned_abs() as usize;
+    if m > degree {
+        return 0.0;
+    }
+    let (cos_colatitude, sin_colatitude, longitude) = spherical_coordinates(point);
+    let table = legendre_table(degree, cos_colatitude, sin_colatitude);
+    let legendre = table[legendre_index(degree, m)];
+    if order >= 0 {
+        legendre * (m as f64 * longitude).cos()
+    } else {
+        legendre * (m as f64 * longitude).sin()
+    }
+}
+
+/// Coefficients of a real spherical harmonic expansion, indexed by [`legendre_index`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HarmonicCoefficients {
+    pub max_degree: usize,
+    /// Coefficients `C_lm` of the `P̄_lm cos(mλ)` terms.
+    pub cosine: Vec<f64>,
+    /// Coefficients `S_lm` of the `P̄_lm sin(mλ)` terms. `S_l0` is always zero.
+    pub sine: Vec<f64>,
+}
+
+impl HarmonicCoefficients {
+    #[must_use]
+    pub fn zeros(max_degree: usize) -> Self {
+        let len = legendre_index(max_degree, max_degree) + 1;
+        Self {
+            max_degree,
+            cosine: vec![0.0; len],
+            sine: vec![0.0; len],
+        }
+    }
+
+    /// Projects a per-cell field onto the harmonics up to `max_degree`, integrating over the
+    /// cells with their areas as quadrature weights.
+    ///
+    /// The grid is not an exact quadrature, so high degrees alias slightly into each other. Each
+    /// of the `refinements` passes analyses the residual of the previous synthesis and adds it to
+    /// the coefficients, which removes most of that leakage.
+    #[must_use]
+    pub fn analyze(
+        grid: &MantleGrid,
+        values: &[f32],
+        max_degree: usize,
+        refinements: usize,
+    ) -> Self {
+        let cells = CellCoordinates::new(grid, max_degree);
+        let mut coefficients = cells.project(values.iter().map(|&v| f64::from(v)));
+        for _ in 0..refinements {
+            let residuals = cells
+                .synthesize(&coefficients)
+                .into_iter()
+                .zip(values)
+                .map(|(synthesized, &value)| f64::from(value) - synthesized);
+            let correction = cells.project(residuals);
+            for (c, d) in coefficients.cosine.iter_mut().zip(correction.cosine) {
+                *c += d;
+            }
+            for (s, d) in coefficients.sine.iter_mut().zip(correction.sine) {
+                *s += d;
+            }
+        }
+        coefficients
+    }
+
+    /// Evaluates the expansion at the centre of every cell.
+    #[must_use]
+    pub fn synthesize(&self, grid: &MantleGrid) -> Vec<f32> {
+        CellCoordinates::new(grid, self.max_degree)
+            .synthesize(self)
+            .into_iter()
+            .map(|value| value as f32)
+            .collect()
+    }
+
+    /// Evaluates the expansion at `point` on the unit sphere.
+    #[must_use]
+    pub fn evaluate(&self, point: Vec3) -> f64 {
+        let (cos_colatitude, sin_colatitude, longitude) = spherical_coordinates(point);
+        let table = legendre_table(self.max_degree, cos_colatitude, sin_colatitude);
+        self.sum(&table, longitude)
+    }
+
+    /// Coefficient of the harmonic of `degree` and `order`, with negative orders selecting the
+    /// sine terms.
+    #[must_use]
+    pub fn get(&self, degree: usize, order: i32) -> f64 {
+        let m = order.unsigned_abs() as usize;
+        if degree > self.max_degree || m > degree {
+            return 0.0;
+        }
+        let index = legendre_index(degree, m);
+        if order >= 0 {
+            self.cosine[index]
+        } else {
+            self.sine[index]
+        }
+    }
+
+    /// Sets the coefficient of the harmonic of `degree` and `order`, with negative orders
+    /// selecting the sine terms. Out of range harmonics are ignored.
+    pub fn set(&mut self, degree: usize, order: i32, value: f64) {
+        let m = order.unsigned_abs() as usize;
+        if degree > self.max_degree || m > degree {
+            return;
+        }
+        let index = legendre_index(degree, m);
+        if order >= 0 {
+            self.cosine[index] = value;
+        } else {
+            self.sine[index] = value;
+        }
+    }
+
+    /// Degree variances `Σ_m C_lm² + S_lm²`, the contribution of each degree to the mean square
+    /// of the field.
+    #[must_use]
+    pub fn power_spectrum(&self) -> Vec<f64> {
+        (0..=self.max_degree)
+            .map(|l| {
+                (0..=l)
+                    .map(|m| {
+                        let index = legendre_index(l, m);
+                        self.cosine[index].powi(2) + self.sine[index].powi(2)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Reads coefficients from whitespace separated `degree order cosine sine` lines, the layout
+    /// used by published geopotential and topography models. Blank lines and lines starting with
+    /// `#` are skipped, as are degrees above `max_degree` if one is given. A harmonic listed twice,
+    /// or a nonzero `S_l0`, is an error.
+    pub fn read(path: &Path, max_degree: Option<usize>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut rows = Vec::new();
+        let mut seen = HashSet::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid coefficient on line {}: {line:?}", number + 1),
+                )
+            };
+            let mut columns = line.split_whitespace();
+            let mut next = || columns.next().ok_or_else(invalid);
+            let degree: usize = next()?.parse().map_err(|_| invalid())?;
+            let order: usize = next()?.parse().map_err(|_| invalid())?;
+            // Published models often write exponents in Fortran style.
+            let cosine: f64 = next()?
+                .replace(['D', 'd'], "e")
+                .parse()
+                .map_err(|_| invalid())?;
+            let sine: f64 = next()?
+                .replace(['D', 'd'], "e")
+                .parse()
+                .map_err(|_| invalid())?;
+            if order > degree {
+                return Err(invalid());
+            }
+            if order == 0 && sine != 0.0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "nonzero sine coefficient of order 0 on line {}: {line:?}",
+                        number + 1
+                    ),
+                ));
+            }
+            if !seen.insert((degree, order)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "degree {degree} order {order} listed again on line {}",
+                        number + 1
+                    ),
+                ));
+            }
+            rows.push((degree, order, cosine, sine));
+        }
+
+        let file_degree = rows.iter().map(|row| row.0).max().unwrap_or(0);
+        let mut coefficients = Self::zeros(max_degree.unwrap_or(file_degree));
+        for (degree, order, cosine, sine) in rows {
+            if degree <= coefficients.max_degree {
+                let index = legendre_index(degree, order);
+                coefficients.cosine[index] = cosine;
+                coefficients.sine[index] = sine;
+            }
+        }
+        Ok(coefficients)
+    }
+
+    /// Writes the coefficients in the layout accepted by [`Self::read`].
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("# degree order cosine sine\n");
+        for l in 0..=self.max_degree {
+            for m in 0..=l {
+                let index = legendre_index(l, m);
+                let _ = writeln!(
+                    contents,
+                    "{l} {m} {:e} {:e}",
+                    self.cosine[index], self.sine[index]
+                );
+            }
+        }
+        fs::write(path, contents)
+    }
+
+    fn sum(&self, table: &[f64], longitude: f64) -> f64 {
+        let mut value = 0.0;
+        for m in 0..=self.max_degree {
+            let (sin, cos) = (m as f64 * longitude).sin_cos();
+            for l in m..=self.max_degree {
+                let index = legendre_index(l, m);
+                value += table[index] * (self.cosine[index] * cos + self.sine[index] * sin);
+            }
+        }
+        value
+    }
+}
+
+/// Spherical coordinates and quadrature weights of every cell centre. The Legendre functions are
+/// evaluated afresh for each cell rather than cached, which would take O(cells·L²) memory.
+struct CellCoordinates {
+    max_degree: usize,
+    /// Cosine and sine of the colatitude, and the longitude, of each cell centre.
+    coordinates: Vec<(f64, f64, f64)>,
+    /// Quadrature weights, the cell areas divided by 4π.
+    weights: Vec<f64>,
+}
+
+impl CellCoordinates {
+    fn new(grid: &MantleGrid, max_degree: usize) -> Self {
+        let coordinates = grid
+            .cells
+            .iter()
+            .map(|cell| spherical_coordinates(cell.center))
+            .collect();
+        let weights = (0..grid.cells.len())
+            .map(|cell| f64::from(grid.cell_area(cell)) / (4.0 * std::f64::consts::PI))
+            .collect();
+        Self {
+            max_degree,
+            coordinates,
+            weights,
+        }
+    }
+
+    /// Runs `visit` with the Legendre table and longitude of every cell in turn, reusing one
+    /// table.
+    fn for_each_cell(&self, mut visit: impl FnMut(usize, &[f64], f64)) {
+        let mut table = vec![0.0; legendre_index(self.max_degree, self.max_degree) + 1];
+        for (cell, &(cos_colatitude, sin_colatitude, longitude)) in
+            self.coordinates.iter().enumerate()
+        {
+            fill_legendre_table(&mut table, self.max_degree, cos_colatitude, sin_colatitude);
+            visit(cell, &table, longitude);
+        }
+    }
+
+    fn project(&self, values: impl Iterator<Item = f64>) -> HarmonicCoefficients {
+        let values: Vec<f64> = values.collect();
+        let mut coefficients = HarmonicCoefficients::zeros(self.max_degree);
+        self.for_each_cell(|cell, table, longitude| {
+            let weighted = values[cell] * self.weights[cell];
+            for m in 0..=self.max_degree {
+                let (sin, cos) = (m as f64 * longitude).sin_cos();
+                for l in m..=self.max_degree {
+                    let index = legendre_index(l, m);
+                    coefficients.cosine[index] += weighted * table[index] * cos;
+                    if m > 0 {
+                        coefficients.sine[index] += weighted * table[index] * sin;
+                    }
+                }
+            }
+        });
+        coefficients
+    }
+
+    fn synthesize(&self, coefficients: &HarmonicCoefficients) -> Vec<f64> {
+        let mut values = Vec::with_capacity(self.coordinates.len());
+        self.for_each_cell(|_, table, longitude| values.push(coefficients.sum(table, longitude)));
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analysis_recovers_band_limited_field() {
+        let grid = MantleGrid::new(12);
+        let mut expected = HarmonicCoefficients::zeros(6);
+        for (degree, order, value) in [
+            (0, 0, 2.0),
+            (1, 1, -0.5),
+            (2, 0, 1.0),
+            (3, -2, 0.75),
+            (4, 3, -0.25),
+            (6, -6, 0.5),
+        ] {
+            expected.set(degree, order, value);
+        }
+
+        let field = expected.synthesize(&grid);
+        let analyzed = HarmonicCoefficients::analyze(&grid, &field, 6, 4);
+        for (found, wanted) in analyzed
+            .cosine
+            .iter()
+            .chain(&analyzed.sine)
+            .zip(expected.cosine.iter().chain(&expected.sine))
+        {
+            assert!(
+                (found - wanted).abs() < 1.0e-3,
+                "found {found}, expected {wanted}"
+            );
+        }
+
+        let resynthesized = analyzed.synthesize(&grid);
+        for (found, wanted) in resynthesized.iter().zip(&field) {
+            assert!((found - wanted).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn read_rejects_duplicates_and_sine_terms_of_order_zero() {
+        let directory =
+            std::env::temp_dir().join(format!("spherical_harmonics_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let read = |name: &str, contents: &str| {
+            let path = directory.join(name);
+            fs::write(&path, contents).unwrap();
+            HarmonicCoefficients::read(&path, None)
+        };
+
+        let coefficients = read("valid.txt", "# l m C S\n0 0 1.0 0.0\n2 1 0.5D-1 -0.25\n").unwrap();
+        assert_eq!(coefficients.max_degree, 2);
+        assert_eq!(coefficients.get(2, 1), 0.05);
+        assert_eq!(coefficients.get(2, -1), -0.25);
+
+        for (name, contents) in [
+            ("duplicate.txt", "1 1 0.5 0.0\n1 1 0.25 0.0\n"),
+            ("sine.txt", "2 0 0.5 0.1\n"),
+        ] {
+            let error = read(name, contents).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{name}: {error}");
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
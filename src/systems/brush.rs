@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::{
+    resources::{
+        brush::{Brush, BrushTool, CrustType, PressureEdits},
+        cell_pressures::CellPressures,
+        mantle_grid::MantleGrid,
+        planet_cursor::PlanetCursor,
+        plates::Plates,
+    },
+    simulation::scenario::{CONTINENT_ELEVATION, OCEAN_DEPTH},
+    systems::gizmos::surface_radius,
+};
+
+/// Largest angular radius of the brush, in radians.
+const MAX_BRUSH_RADIUS: f32 = 1.0;
+
+/// Switches the brush on and off with B.
+pub fn toggle_brush(keys: Res<ButtonInput<KeyCode>>, mut brush: ResMut<Brush>) {
+    if keys.just_pressed(KeyCode::KeyB) {
+        brush.active = !brush.active;
+    }
+}
+
+/// Window with the brush's settings, shown while the brush is active.
+pub fn brush_window(
+    mut contexts: EguiContexts,
+    mut brush: ResMut<Brush>,
+    plates: Res<Plates>,
+) -> Result {
+    if !brush.active {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+
+    let mut edited = brush.clone();
+    egui::Window::new("Brush")
+        .open(&mut edited.active)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let mut tool = brush.tool;
+            egui::ComboBox::from_label("Tool")
+                .selected_text(tool.name())
+                .show_ui(ui, |ui| {
+                    for candidate in BrushTool::ALL {
+                        ui.selectable_value(&mut tool, candidate, candidate.name());
+                    }
+                });
+            if tool != brush.tool {
+                edited.tool = tool;
+                edited.strength = tool.default_strength();
+            }
+
+            ui.add(
+                egui::Slider::new(&mut edited.radius, 0.01..=MAX_BRUSH_RADIUS)
+                    .text("radius")
+                    .suffix(" rad"),
+            );
+            match edited.tool {
+                BrushTool::Plate => {
+                    let last_plate = plates.0.len().saturating_sub(1);
+                    ui.add(egui::Slider::new(&mut edited.plate, 0..=last_plate).text("plate"));
+                }
+                BrushTool::Crust => {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut edited.crust, CrustType::Continental, "continental");
+                        ui.radio_value(&mut edited.crust, CrustType::Oceanic, "oceanic");
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut edited.strength, 0.1..=10.0)
+                            .logarithmic(true)
+                            .text("strength"),
+                    );
+                }
+                BrushTool::Pressure | BrushTool::Heat => {
+                    let default = edited.tool.default_strength();
+                    ui.add(
+                        egui::Slider::new(&mut edited.strength, default * 0.01..=default * 100.0)
+                            .logarithmic(true)
+                            .text("strength"),
+                    );
+                    ui.label("Hold Shift to lower.");
+                }
+            }
+        });
+
+    brush.set_if_neq(edited);
+    Ok(())
+}
+
+/// Paints the cells under the brush while the left button is dragged over the planet, keeping
+/// the camera still meanwhile.
+///
+/// Fields held by the grid are edited in place and reach the GPU with the rest of the grid.
+/// Pressure is only kept on the GPU, so painted pressure changes are also queued as
+/// `PressureEdits` and added to the solver's pressures there.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_with_brush(
+    mut contexts: EguiContexts,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    brush: Res<Brush>,
+    cursor: Res<PlanetCursor>,
+    plates: Res<Plates>,
+    mut grid: ResMut<MantleGrid>,
+    mut pressures: ResMut<CellPressures>,
+    mut edits: ResMut<PressureEdits>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+    mut painting: Local<bool>,
+) -> Result {
+    if !edits.0.is_empty() {
+        edits.0.clear();
+    }
+
+    let ctx = contexts.ctx_mut()?;
+    if mouse.just_pressed(MouseButton::Left) {
+        *painting = brush.active && cursor.cell.is_some() && !ctx.is_pointer_over_area();
+    }
+    if !mouse.pressed(MouseButton::Left) || !brush.active {
+        *painting = false;
+    }
+    // The globe does not orbit while painting, nor when the mouse is over the map.
+    for mut camera in &mut cameras {
+        camera.enabled = !*painting && !cursor.over_map;
+    }
+
+    let Some(center) = cursor.point.filter(|_| *painting) else {
+        return Ok(());
+    };
+    if brush.tool == BrushTool::Plate && brush.plate >= plates.0.len() {
+        return Ok(());
+    }
+
+    let lowering = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let sign = if lowering { -1.0 } else { 1.0 };
+    let rate = sign * brush.strength * time.delta_secs();
+    for cell in 0..grid.cells.len() {
+        let weight = brush.weight(center, grid.cells[cell].center);
+        if weight == 0.0 {
+            continue;
+        }
+        let data = &mut grid.cells[cell];
+        match brush.tool {
+            BrushTool::Pressure => {
+                let delta = rate * weight;
+                // Keep the values shown until the next readback in step with the painting.
+                if let Some(latest) = pressures.0.get_mut(cell) {
+                    *latest += delta;
+                }
+                data.pressure += delta;
+                edits.0.push((cell as u32, delta));
+            }
+            BrushTool::Plate => data.plate = brush.plate,
+            BrushTool::Crust => {
+                let blend = (brush.strength * time.delta_secs() * weight).min(1.0);
+                let target = match brush.crust {
+                    CrustType::Continental => CONTINENT_ELEVATION,
+                    CrustType::Oceanic => -OCEAN_DEPTH,
+                };
+                data.elevation += (target - data.elevation) * blend;
+                // Crust turned oceanic is freshly formed.
+                if brush.crust == CrustType::Oceanic {
+                    data.age *= 1.0 - blend;
+                }
+            }
+            BrushTool::Heat => data.temperature += rate * weight,
+        }
+    }
+    Ok(())
+}
+
+/// Outlines the brush on the planet around the cursor.
+pub fn draw_brush(
+    mut gizmos: Gizmos,
+    brush: Res<Brush>,
+    cursor: Res<PlanetCursor>,
+    grid: Res<MantleGrid>,
+) {
+    let (Some(center), Some(cell)) = (cursor.point, cursor.cell) else {
+        return;
+    };
+    let radius = surface_radius(grid.cells[cell].elevation);
+    let (sin, cos) = brush.radius.sin_cos();
+    gizmos.circle(
+        Isometry3d::new(
+            center * cos * radius,
+            Quat::from_rotation_arc(Vec3::Z, center),
+        ),
+        sin * radius,
+        Color::srgb(1.0, 0.5, 0.0),
+    );
+}
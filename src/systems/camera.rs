@@ -0,0 +1,358 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::{
+    resources::{
+        camera_settings::{CameraBookmark, CameraFlight, CameraPose, CameraSettings, Flight},
+        simulation_config::{ConfigFile, SimulationConfig},
+    },
+    simulation::geography::LatLon,
+};
+
+/// Where the camera starts, and where Home flies back to.
+pub const HOME_POSITION: Vec3 = Vec3::new(0.0, 2.0, 5.0);
+
+/// Highest pitch the arrow keys tilt the camera to, short of the poles where yaw degenerates.
+const MAX_KEY_PITCH: f32 = FRAC_PI_2 - 0.01;
+/// How far a flight halfway round the planet backs away from it mid-way, in planet radii.
+const FLIGHT_ZOOM_OUT: f32 = 1.5;
+
+const BOOKMARK_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// Applies the limits and panning of `CameraSettings` to a camera.
+pub fn configure_camera(camera: &mut PanOrbitCamera, settings: &CameraSettings) {
+    let (min_distance, max_distance) = settings.distance_limits();
+    camera.zoom_lower_limit = min_distance;
+    camera.zoom_upper_limit = Some(max_distance);
+    camera.pan_sensitivity = if settings.panning { 1.0 } else { 0.0 };
+    if !settings.panning {
+        camera.target_focus = Vec3::ZERO;
+    }
+}
+
+/// Reapplies `CameraSettings` to the camera whenever they change.
+pub fn apply_camera_settings(
+    settings: Res<CameraSettings>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut camera in &mut cameras {
+        configure_camera(&mut camera, &settings);
+    }
+}
+
+/// Pose the camera is heading for.
+fn target_pose(camera: &PanOrbitCamera) -> CameraPose {
+    CameraPose {
+        yaw: camera.target_yaw,
+        pitch: camera.target_pitch,
+        distance: camera.target_radius,
+    }
+}
+
+/// Sets the camera heading for `pose`, turning the short way round.
+fn set_target_pose(camera: &mut PanOrbitCamera, pose: CameraPose) {
+    let turn = (pose.yaw - camera.target_yaw + PI).rem_euclid(TAU) - PI;
+    camera.target_yaw += turn;
+    camera.target_pitch = pose.pitch;
+    camera.target_radius = pose.distance;
+    camera.target_focus = Vec3::ZERO;
+}
+
+/// Starts flying the camera from where it is heading to `to`.
+fn fly_to(
+    flight: &mut CameraFlight,
+    camera: &PanOrbitCamera,
+    settings: &CameraSettings,
+    to: CameraPose,
+) {
+    flight.0 = Some(Flight {
+        from: target_pose(camera),
+        to: CameraPose {
+            distance: settings.clamp_distance(to.distance),
+            ..to
+        },
+        elapsed: 0.0,
+        duration: settings.fly_duration.max(f32::EPSILON),
+    });
+}
+
+/// Moves the camera along its flight: the view swings along the great circle between the two
+/// poses, easing in and out, and backs away from the planet in between in proportion to how far
+/// round it goes. Pressing a mouse button takes the camera back.
+pub fn fly_camera(
+    time: Res<Time>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut flight: ResMut<CameraFlight>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) {
+    let Some(mut current) = flight.0 else {
+        return;
+    };
+    if mouse.get_just_pressed().next().is_some() {
+        flight.0 = None;
+        return;
+    }
+
+    current.elapsed += time.delta_secs();
+    let t = (current.elapsed / current.duration).clamp(0.0, 1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    let from = current.from.direction();
+    let to = current.to.direction();
+    let detour = FLIGHT_ZOOM_OUT * from.angle_between(to) / PI * (PI * eased).sin();
+    let distance = current.from.distance.lerp(current.to.distance, eased) + detour;
+    let pose = if t < 1.0 {
+        CameraPose::from_direction(from.slerp(to, eased), distance)
+    } else {
+        current.to
+    };
+
+    for mut camera in &mut cameras {
+        set_target_pose(&mut camera, pose);
+    }
+    flight.0 = (t < 1.0).then_some(current);
+}
+
+/// Turns the camera about the planet's axis while auto-rotation is on, unless it is flying or
+/// being dragged.
+pub fn auto_rotate_camera(
+    time: Res<Time>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    settings: Res<CameraSettings>,
+    flight: Res<CameraFlight>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) {
+    if !settings.auto_rotate || flight.0.is_some() || mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    let turn = settings.auto_rotate_speed.to_radians() * time.delta_secs();
+    for mut camera in &mut cameras {
+        camera.target_yaw += turn;
+    }
+}
+
+/// Keyboard navigation: the arrow keys orbit, Page Up and Page Down zoom, R toggles
+/// auto-rotation, Home flies back to the starting view and the number keys to the bookmarks.
+/// Keys typed into the control windows are left alone.
+pub fn keyboard_camera_controls(
+    mut contexts: EguiContexts,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut settings: ResMut<CameraSettings>,
+    mut flight: ResMut<CameraFlight>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) -> Result {
+    if contexts.ctx_mut()?.wants_keyboard_input() {
+        return Ok(());
+    }
+
+    if keys.just_pressed(KeyCode::KeyR) {
+        settings.auto_rotate = !settings.auto_rotate;
+    }
+
+    let dt = time.delta_secs();
+    let pressed = |key: KeyCode| if keys.pressed(key) { 1.0 } else { 0.0 };
+    let axis = |negative: KeyCode, positive: KeyCode| pressed(positive) - pressed(negative);
+    let orbit = Vec2::new(
+        axis(KeyCode::ArrowRight, KeyCode::ArrowLeft),
+        axis(KeyCode::ArrowDown, KeyCode::ArrowUp),
+    ) * settings.key_orbit_speed.to_radians()
+        * dt;
+    let zoom = settings
+        .key_zoom_speed
+        .powf(axis(KeyCode::PageUp, KeyCode::PageDown) * dt);
+
+    for mut camera in &mut cameras {
+        if orbit != Vec2::ZERO || zoom != 1.0 {
+            flight.0 = None;
+            camera.target_yaw += orbit.x;
+            camera.target_pitch =
+                (camera.target_pitch + orbit.y).clamp(-MAX_KEY_PITCH, MAX_KEY_PITCH);
+            camera.target_radius = settings.clamp_distance(camera.target_radius * zoom);
+        }
+
+        if keys.just_pressed(KeyCode::Home) {
+            let home = CameraPose::from_direction(HOME_POSITION, HOME_POSITION.length());
+            fly_to(&mut flight, &camera, &settings, home);
+        }
+        let bookmark = BOOKMARK_KEYS
+            .iter()
+            .position(|&key| keys.just_pressed(key))
+            .and_then(|index| settings.bookmarks.get(index));
+        if let Some(bookmark) = bookmark {
+            let pose = CameraPose::looking_at(bookmark.position(), bookmark.distance);
+            fly_to(&mut flight, &camera, &settings, pose);
+        }
+    }
+    Ok(())
+}
+
+/// What the camera window remembers between frames.
+#[derive(Default)]
+pub struct CameraWindowState {
+    /// Name given to the next bookmark.
+    name: String,
+    /// Position typed in to look at, in degrees.
+    latitude: f64,
+    longitude: f64,
+    /// Outcome of the last save.
+    status: Option<String>,
+}
+
+/// Window to fly to positions and bookmarks, set up auto-rotation and save the camera settings
+/// to the configuration file.
+pub fn camera_window(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<CameraSettings>,
+    mut flight: ResMut<CameraFlight>,
+    config_file: Option<Res<ConfigFile>>,
+    cameras: Query<&PanOrbitCamera>,
+    mut state: Local<CameraWindowState>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some(camera) = cameras.iter().next() else {
+        return Ok(());
+    };
+
+    let mut edited = settings.clone();
+    let mut destination = None;
+    egui::Window::new("Camera")
+        .default_open(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.checkbox(&mut edited.auto_rotate, "Auto-rotate (R)");
+            ui.add(
+                egui::Slider::new(&mut edited.auto_rotate_speed, -45.0..=45.0)
+                    .text("speed")
+                    .suffix(" °/s"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.fly_duration, 0.2..=10.0)
+                    .text("flight")
+                    .suffix(" s"),
+            );
+            ui.horizontal(|ui| {
+                ui.label("Distance");
+                ui.add(
+                    egui::DragValue::new(&mut edited.min_distance)
+                        .speed(0.05)
+                        .range(1.1..=edited.max_distance)
+                        .prefix("min "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut edited.max_distance)
+                        .speed(0.05)
+                        .range(edited.min_distance..=100.0)
+                        .prefix("max "),
+                );
+            });
+            ui.checkbox(&mut edited.panning, "Pan with the right button");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Look at");
+                ui.add(
+                    egui::DragValue::new(&mut state.latitude)
+                        .range(-90.0..=90.0)
+                        .suffix("° lat"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut state.longitude)
+                        .range(-180.0..=180.0)
+                        .suffix("° lon"),
+                );
+                if ui.button("Fly").clicked() {
+                    let position = LatLon::new(state.latitude, state.longitude);
+                    destination = Some(CameraPose::looking_at(position, camera.target_radius));
+                }
+            });
+            ui.separator();
+
+            ui.label("Bookmarks");
+            let mut removed = None;
+            for (index, bookmark) in edited.bookmarks.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let label = if index < BOOKMARK_KEYS.len() {
+                        format!("{} {}", index + 1, bookmark.name)
+                    } else {
+                        bookmark.name.clone()
+                    };
+                    if ui.button(label).clicked() {
+                        destination = Some(CameraPose::looking_at(
+                            bookmark.position(),
+                            bookmark.distance,
+                        ));
+                    }
+                    ui.label(format!(
+                        "{:.1}°, {:.1}°",
+                        bookmark.latitude, bookmark.longitude
+                    ));
+                    if ui.small_button("✕").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                edited.bookmarks.remove(index);
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut state.name);
+                if ui.button("Add current view").clicked() {
+                    let pose = target_pose(camera);
+                    let position = pose.position();
+                    let name = if state.name.trim().is_empty() {
+                        format!("View {}", edited.bookmarks.len() + 1)
+                    } else {
+                        state.name.trim().to_owned()
+                    };
+                    edited.bookmarks.push(CameraBookmark {
+                        name,
+                        latitude: position.latitude,
+                        longitude: position.longitude,
+                        distance: pose.distance,
+                    });
+                    state.name.clear();
+                }
+            });
+            ui.separator();
+
+            match config_file.as_deref() {
+                Some(ConfigFile(path)) => {
+                    if ui.button(format!("Save to {}", path.display())).clicked() {
+                        state.status = Some(match SimulationConfig::save_camera(path, &edited) {
+                            Ok(()) => "Saved".to_owned(),
+                            Err(error) => error.to_string(),
+                        });
+                    }
+                }
+                None => {
+                    ui.add_enabled(false, egui::Button::new("Save"))
+                        .on_disabled_hover_text("Start with --config FILE to save the camera");
+                }
+            }
+            if let Some(status) = &state.status {
+                ui.label(status);
+            }
+        });
+
+    if let Some(pose) = destination {
+        fly_to(&mut flight, camera, &edited, pose);
+    }
+    settings.set_if_neq(edited);
+    Ok(())
+}
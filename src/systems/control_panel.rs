@@ -0,0 +1,312 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    materials::colormap::Colormap,
+    plugins::grid_debug_overlay::{
+        CellCenterGizmos, DEFAULT_CURSOR_RADIUS, GridDebugOverlay, GridLineGizmos, NeighborGizmos,
+        OverlayCulling,
+    },
+    resources::{
+        field_display::{FieldDisplay, Shading, ValueRange},
+        field_statistics::FieldStatistics,
+        mantle_grid::{CellField, MantleGrid},
+        map_view::{MapProjection, MapView},
+        overlays::Overlays,
+        plates::Plates,
+        simulation_clock::{SimulationClock, SimulationControl},
+        velocity_field::VelocityField,
+    },
+};
+
+/// Largest number of steps the simulation can be asked to take per frame.
+const MAX_STEPS_PER_FRAME: u32 = 50;
+/// Largest number of plate velocity arrows that can be asked for.
+const MAX_ARROWS: usize = 50_000;
+/// Largest number of mantle streamlines that can be asked for.
+const MAX_STREAMLINES: usize = 5_000;
+
+/// Side panel to run the simulation, choose what the planet shows and read diagnostics.
+///
+/// Every control edits a copy of its resource, which is written back only when it differs so that
+/// systems watching for changes are not triggered every frame.
+#[allow(clippy::too_many_arguments)]
+pub fn control_panel(
+    mut contexts: EguiContexts,
+    mut control: ResMut<SimulationControl>,
+    mut display: ResMut<FieldDisplay>,
+    mut overlays: ResMut<Overlays>,
+    mut velocity_field: ResMut<VelocityField>,
+    mut map_view: ResMut<MapView>,
+    mut grid_overlay: ResMut<GridDebugOverlay>,
+    mut gizmo_store: ResMut<GizmoConfigStore>,
+    clock: Res<SimulationClock>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    statistics: Option<Res<FieldStatistics>>,
+    diagnostics: Res<DiagnosticsStore>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut edited_control = control.clone();
+    let mut edited_display = display.clone();
+    let mut edited_overlays = overlays.clone();
+    let mut edited_velocity_field = velocity_field.clone();
+    let mut edited_map_view = map_view.clone();
+    let mut edited_grid_overlay = grid_overlay.clone();
+
+    egui::SidePanel::left("control_panel")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.heading("Simulation");
+            simulation_controls(ui, &mut edited_control);
+            ui.separator();
+
+            ui.heading("Display");
+            let statistics = statistics
+                .as_deref()
+                .filter(|statistics| statistics.field == display.field);
+            display_controls(ui, &mut edited_display, statistics);
+            ui.separator();
+
+            ui.heading("Map");
+            map_controls(ui, &mut edited_map_view);
+            ui.separator();
+
+            ui.heading("Overlays");
+            grid_overlay_controls(ui, &mut gizmo_store, &mut edited_grid_overlay);
+            overlay_controls(ui, &mut edited_overlays, &mut edited_velocity_field);
+            ui.separator();
+
+            ui.heading("Diagnostics");
+            egui::Grid::new("diagnostics").show(ui, |ui| {
+                ui.label("Step");
+                ui.label(clock.step.to_string());
+                ui.end_row();
+                ui.label("Simulated time");
+                ui.label(format!("{:.1} Myr", clock.elapsed / 1.0e6));
+                ui.end_row();
+                ui.label("Time step");
+                ui.label(format!("{:.2} Myr", clock.time_step / 1.0e6));
+                ui.end_row();
+                ui.label("Cells");
+                ui.label(grid.cells.len().to_string());
+                ui.end_row();
+                ui.label("Plates");
+                ui.label(plates.0.len().to_string());
+                ui.end_row();
+                if let Some(fps) = diagnostics
+                    .get(&FrameTimeDiagnosticsPlugin::FPS)
+                    .and_then(|fps| fps.smoothed())
+                {
+                    ui.label("Frame rate");
+                    ui.label(format!("{fps:.0} fps"));
+                    ui.end_row();
+                }
+            });
+        });
+
+    control.set_if_neq(edited_control);
+    display.set_if_neq(edited_display);
+    overlays.set_if_neq(edited_overlays);
+    velocity_field.set_if_neq(edited_velocity_field);
+    map_view.set_if_neq(edited_map_view);
+    grid_overlay.set_if_neq(edited_grid_overlay);
+    Ok(())
+}
+
+fn simulation_controls(ui: &mut egui::Ui, control: &mut SimulationControl) {
+    ui.horizontal(|ui| {
+        let label = if control.paused { "Run" } else { "Pause" };
+        if ui.button(label).clicked() {
+            control.paused = !control.paused;
+        }
+        if ui
+            .add_enabled(control.paused, egui::Button::new("Step"))
+            .clicked()
+        {
+            control.pending_steps += 1;
+        }
+    });
+    ui.add(
+        egui::Slider::new(&mut control.steps_per_frame, 1..=MAX_STEPS_PER_FRAME)
+            .text("steps per frame"),
+    );
+}
+
+fn display_controls(
+    ui: &mut egui::Ui,
+    display: &mut FieldDisplay,
+    statistics: Option<&FieldStatistics>,
+) {
+    let mut field = display.field;
+    egui::ComboBox::from_label("Field")
+        .selected_text(field.name())
+        .show_ui(ui, |ui| {
+            for candidate in CellField::ALL {
+                ui.selectable_value(&mut field, candidate, candidate.name());
+            }
+        });
+    if field != display.field {
+        // A new field starts from the colormap and shading that suit it, keeping the lock.
+        *display = FieldDisplay {
+            locked: display.locked,
+            ..FieldDisplay::new(field)
+        };
+    }
+
+    egui::ComboBox::from_label("Colormap")
+        .selected_text(display.colormap.name())
+        .show_ui(ui, |ui| {
+            for colormap in Colormap::ALL {
+                ui.selectable_value(&mut display.colormap, colormap, colormap.name());
+            }
+        });
+
+    ui.horizontal(|ui| {
+        ui.label("Shading");
+        ui.radio_value(&mut display.shading, Shading::Smooth, "smooth");
+        ui.radio_value(&mut display.shading, Shading::Flat, "flat");
+    });
+
+    let (min, max) = statistics.map_or((0.0, 1.0), |statistics| (statistics.min, statistics.max));
+    ui.horizontal(|ui| {
+        ui.label("Range");
+        if ui
+            .radio(matches!(display.range, ValueRange::Auto), "auto")
+            .clicked()
+        {
+            display.range = ValueRange::Auto;
+        }
+        if ui
+            .radio(
+                matches!(display.range, ValueRange::Percentile { .. }),
+                "percentile",
+            )
+            .clicked()
+            && !matches!(display.range, ValueRange::Percentile { .. })
+        {
+            display.range = ValueRange::Percentile {
+                low: 2.0,
+                high: 98.0,
+            };
+        }
+        if ui
+            .radio(matches!(display.range, ValueRange::Manual { .. }), "manual")
+            .clicked()
+            && !matches!(display.range, ValueRange::Manual { .. })
+        {
+            display.range = ValueRange::Manual { min, max };
+        }
+    });
+    match &mut display.range {
+        ValueRange::Auto => {}
+        ValueRange::Percentile { low, high } => {
+            ui.add(egui::Slider::new(low, 0.0..=50.0).text("low percentile"));
+            ui.add(egui::Slider::new(high, 50.0..=100.0).text("high percentile"));
+        }
+        ValueRange::Manual {
+            min: low,
+            max: high,
+        } => {
+            let speed = f64::from((max - min).abs().max(1.0e-3)) / 200.0;
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(low).speed(speed).prefix("min "));
+                ui.add(egui::DragValue::new(high).speed(speed).prefix("max "));
+            });
+        }
+    }
+    ui.checkbox(&mut display.locked, "Lock range");
+
+    if let Some(statistics) = statistics {
+        ui.label(format!(
+            "Values from {:.3} to {:.3}",
+            statistics.min, statistics.max
+        ));
+    }
+}
+
+fn map_controls(ui: &mut egui::Ui, view: &mut MapView) {
+    let name = |projection: Option<MapProjection>| projection.map_or("Off", MapProjection::name);
+    egui::ComboBox::from_label("Projection")
+        .selected_text(name(view.projection))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut view.projection, None, name(None));
+            for projection in MapProjection::ALL {
+                ui.selectable_value(&mut view.projection, Some(projection), projection.name());
+            }
+        });
+    if view.projection.is_some() {
+        ui.add(egui::Slider::new(&mut view.width, 0.2..=0.8).text("width"));
+    }
+}
+
+fn grid_overlay_controls(
+    ui: &mut egui::Ui,
+    store: &mut ResMut<GizmoConfigStore>,
+    overlay: &mut GridDebugOverlay,
+) {
+    gizmo_checkbox::<GridLineGizmos>(ui, store, "Grid");
+    gizmo_checkbox::<CellCenterGizmos>(ui, store, "Cell centres");
+    gizmo_checkbox::<NeighborGizmos>(ui, store, "Neighbours");
+
+    let near_cursor = matches!(overlay.culling, OverlayCulling::NearCursor { .. });
+    ui.horizontal(|ui| {
+        ui.label("Draw cells");
+        if ui.radio(!near_cursor, "facing camera").clicked() {
+            overlay.culling = OverlayCulling::FacingCamera;
+        }
+        if ui.radio(near_cursor, "near cursor").clicked() && !near_cursor {
+            overlay.culling = OverlayCulling::NearCursor {
+                radius: DEFAULT_CURSOR_RADIUS,
+            };
+        }
+    });
+    if let OverlayCulling::NearCursor { radius } = &mut overlay.culling {
+        ui.add(
+            egui::Slider::new(radius, 0.02..=1.0)
+                .text("radius")
+                .suffix(" rad"),
+        );
+    }
+}
+
+/// Checkbox enabling a gizmo config group, which only touches the store when clicked.
+fn gizmo_checkbox<T: GizmoConfigGroup>(
+    ui: &mut egui::Ui,
+    store: &mut ResMut<GizmoConfigStore>,
+    label: &str,
+) {
+    let mut enabled = store.config::<T>().0.enabled;
+    if ui.checkbox(&mut enabled, label).changed() {
+        store.config_mut::<T>().0.enabled = enabled;
+    }
+}
+
+fn overlay_controls(
+    ui: &mut egui::Ui,
+    overlays: &mut Overlays,
+    velocity_field: &mut VelocityField,
+) {
+    ui.checkbox(&mut overlays.boundaries, "Plate boundaries");
+    ui.checkbox(&mut overlays.velocities, "Plate velocities");
+    if overlays.velocities {
+        ui.add(
+            egui::Slider::new(&mut velocity_field.arrow_count, 100..=MAX_ARROWS)
+                .logarithmic(true)
+                .text("arrows"),
+        );
+    }
+    ui.checkbox(&mut overlays.streamlines, "Mantle streamlines");
+    if overlays.streamlines {
+        ui.add(
+            egui::Slider::new(&mut velocity_field.streamline_count, 10..=MAX_STREAMLINES)
+                .logarithmic(true)
+                .text("streamlines"),
+        );
+    }
+    ui.checkbox(&mut overlays.hotspots, "Hotspots");
+}
@@ -0,0 +1,61 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::{
+    resources::{cell_locator::CellLocator, mantle_grid::MantleGrid, planet_cursor::PlanetCursor},
+    systems::map_view::MapCamera,
+};
+
+/// Casts a ray from the globe's camera through the cursor onto the unit sphere and finds the cell it
+/// lands in. The map's viewport covers the globe, so the cursor points at nothing there.
+pub fn track_planet_cursor(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    map_cameras: Query<&Camera, With<MapCamera>>,
+    grid: Option<Res<MantleGrid>>,
+    locator: Option<Res<CellLocator>>,
+    mut cursor: ResMut<PlanetCursor>,
+) {
+    let screen_position = windows.single().ok().and_then(Window::cursor_position);
+    let over_map = screen_position.is_some_and(|position| {
+        map_cameras
+            .iter()
+            .filter_map(Camera::logical_viewport_rect)
+            .any(|viewport| viewport.contains(position))
+    });
+    let point = screen_position
+        .filter(|_| !over_map)
+        .zip(cameras.iter().next())
+        .and_then(|(position, (camera, transform))| {
+            camera.viewport_to_world(transform, position).ok()
+        })
+        .and_then(|ray| ray_sphere_intersection(ray.origin, *ray.direction));
+    let cell = point
+        .zip(grid.as_deref().zip(locator.as_deref()))
+        .map(|(point, (grid, locator))| locator.locate(grid, point));
+    cursor.set_if_neq(PlanetCursor {
+        point,
+        cell,
+        screen_position,
+        over_map,
+    });
+}
+
+/// First point where a ray with a unit `direction` enters the unit sphere.
+#[must_use]
+pub fn ray_sphere_intersection(origin: Vec3, direction: Vec3) -> Option<Vec3> {
+    // Solves |origin + t direction| = 1 for the smallest non-negative t.
+    let b = origin.dot(direction);
+    let c = origin.length_squared() - 1.0;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let t = if -b - root >= 0.0 {
+        -b - root
+    } else {
+        -b + root
+    };
+    (t >= 0.0).then(|| (origin + t * direction).normalize())
+}
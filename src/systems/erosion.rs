@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+use crate::{
+    resources::{mantle_grid::MantleGrid, simulation_clock::SimulationClock},
+    simulation::erosion::{CellGeometry, ErosionParameters, erode},
+};
+
+/// Erodes the surface over a simulation step. The grid is only touched when the step has a
+/// duration, so that it is not marked changed, and sent to the GPU again, for nothing.
+pub fn apply_erosion(
+    mut grid: ResMut<MantleGrid>,
+    geometry: Res<CellGeometry>,
+    clock: Res<SimulationClock>,
+    parameters: Res<ErosionParameters>,
+) {
+    if clock.time_step <= 0.0 {
+        return;
+    }
+    erode(&mut grid, &geometry, &parameters, clock.time_step);
+}
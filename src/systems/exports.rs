@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::hotspot::Hotspot,
+    io::{plate_statistics::PlateStatisticsWriter, time_series::TimeSeriesWriter},
+    resources::{
+        cell_locator::CellLocator, field_display::FieldDisplay, mantle_grid::MantleGrid,
+        plates::Plates, simulation_clock::SimulationClock, simulation_config::SimulationConfig,
+    },
+    simulation::{
+        erosion::ErosionParameters, plate_dynamics::PlateDynamicsParameters,
+        plate_statistics::Statistics,
+    },
+};
+
+/// Writes every configured raster of the current grid.
+pub fn write_raster_exports(
+    config: Res<SimulationConfig>,
+    grid: Res<MantleGrid>,
+    locator: Res<CellLocator>,
+) {
+    for export in &config.raster_exports {
+        match export.write(&grid, &locator) {
+            Ok(()) => info!("Wrote {} to {}", export.field.name(), export.path.display()),
+            Err(error) => error!("{error}"),
+        }
+    }
+}
+
+/// Writes every configured mesh of the current grid, coloured as the viewer shows the field.
+pub fn write_mesh_exports(
+    config: Res<SimulationConfig>,
+    grid: Res<MantleGrid>,
+    display: Res<FieldDisplay>,
+) {
+    for export in &config.mesh_exports {
+        match export.write(&grid, &display) {
+            Ok(()) => info!("Wrote mesh to {}", export.path.display()),
+            Err(error) => error!("{error}"),
+        }
+    }
+}
+
+/// Writes every configured GeoJSON file of plate boundaries and coastlines, with the coast at
+/// the erosion sea level.
+pub fn write_geojson_exports(
+    config: Res<SimulationConfig>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    dynamics: Res<PlateDynamicsParameters>,
+    erosion: Res<ErosionParameters>,
+) {
+    for export in &config.geojson_exports {
+        match export.write(&grid, &plates, &dynamics, erosion.sea_level) {
+            Ok(()) => info!("Wrote GeoJSON to {}", export.path.display()),
+            Err(error) => error!("Failed to write {}: {error}", export.path.display()),
+        }
+    }
+}
+
+/// Creates the configured time series store and writes the initial state to it.
+pub fn start_time_series(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    hotspots: Query<&Hotspot>,
+    clock: Res<SimulationClock>,
+) {
+    let Some(settings) = &config.time_series else {
+        return;
+    };
+    let hotspots: Vec<Hotspot> = hotspots.iter().cloned().collect();
+    let result = TimeSeriesWriter::create(settings, &grid).and_then(|mut writer| {
+        writer.write_frame(&grid, &plates, &hotspots, &clock)?;
+        Ok(writer)
+    });
+    match result {
+        Ok(writer) => {
+            info!("Writing time series to {}", settings.path.display());
+            commands.insert_resource(writer);
+        }
+        Err(error) => error!("Failed to create time series: {error}"),
+    }
+}
+
+/// Appends a frame to the time series whenever one is due.
+pub fn record_time_series(
+    mut writer: ResMut<TimeSeriesWriter>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    hotspots: Query<&Hotspot>,
+    clock: Res<SimulationClock>,
+) {
+    if !writer.is_due(clock.step) {
+        return;
+    }
+    let hotspots: Vec<Hotspot> = hotspots.iter().cloned().collect();
+    if let Err(error) = writer.write_frame(&grid, &plates, &hotspots, &clock) {
+        error!("Failed to write time series frame: {error}");
+    }
+}
+
+/// Creates the configured plate statistics table and writes the initial state to it.
+pub fn start_plate_statistics(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    parameters: Res<PlateDynamicsParameters>,
+    clock: Res<SimulationClock>,
+) {
+    let Some(settings) = &config.plate_statistics else {
+        return;
+    };
+    let statistics = Statistics::compute(&grid, &plates, &parameters);
+    let result = PlateStatisticsWriter::create(settings).and_then(|mut writer| {
+        writer.write(&clock, &statistics)?;
+        Ok(writer)
+    });
+    match result {
+        Ok(writer) => {
+            info!("Writing plate statistics to {}", settings.path.display());
+            commands.insert_resource(writer);
+        }
+        Err(error) => error!("Failed to create plate statistics table: {error}"),
+    }
+}
+
+/// Appends the plate statistics whenever a row is due.
+pub fn record_plate_statistics(
+    mut writer: ResMut<PlateStatisticsWriter>,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    parameters: Res<PlateDynamicsParameters>,
+    clock: Res<SimulationClock>,
+) {
+    if !writer.is_due(clock.step) {
+        return;
+    }
+    let statistics = Statistics::compute(&grid, &plates, &parameters);
+    if let Err(error) = writer.write(&clock, &statistics) {
+        error!("Failed to write plate statistics: {error}");
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::{
+    materials::pressure_material::PressureMaterial,
+    resources::{
+        field_display::{FieldDisplay, Shading},
+        field_statistics::FieldStatistics,
+        mantle_grid::MantleGrid,
+    },
+};
+
+/// Keeps the planet material's colour scale and colormap in step with `FieldDisplay`, following
+/// the field's statistics while the range is not locked.
+pub fn update_field_display(
+    display: Res<FieldDisplay>,
+    grid: Res<MantleGrid>,
+    statistics: Option<Res<FieldStatistics>>,
+    planets: Query<&MeshMaterial3d<PressureMaterial>>,
+    mut materials: ResMut<Assets<PressureMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let field_changed = grid.is_changed() || statistics.as_ref().is_some_and(|s| s.is_changed());
+    if !display.is_changed() && (display.locked || !field_changed) {
+        return;
+    }
+
+    // The GPU reports on the displayed field a frame or two after it is selected; until then the
+    // values held by the grid stand in.
+    let computed;
+    let statistics = match statistics
+        .as_deref()
+        .filter(|statistics| statistics.field == display.field)
+    {
+        Some(statistics) => statistics,
+        None => {
+            computed = FieldStatistics::compute(&grid, display.field);
+            &computed
+        }
+    };
+
+    let color_scale = display.color_scale(statistics);
+    for planet in &planets {
+        // Only take the material mutably when something differs, so that its bind group is not
+        // rebuilt every frame.
+        if materials
+            .get(&planet.0)
+            .is_none_or(|material| material.color_scale == color_scale && !display.is_changed())
+        {
+            continue;
+        }
+        let Some(material) = materials.get_mut(&planet.0) else {
+            continue;
+        };
+        material.color_scale = color_scale;
+        if display.is_changed() {
+            material.colormap = images.add(display.colormap.image());
+            material.flat_shading = u32::from(display.shading == Shading::Flat);
+        }
+    }
+}
+
+/// Keeps the light position the planet material shades with in step with the point light, so that
+/// moving the light moves the shading with it.
+pub fn follow_light(
+    lights: Query<&GlobalTransform, With<PointLight>>,
+    planets: Query<&MeshMaterial3d<PressureMaterial>>,
+    mut materials: ResMut<Assets<PressureMaterial>>,
+) {
+    let Some(light) = lights.iter().next() else {
+        return;
+    };
+    let position = light.translation();
+    for planet in &planets {
+        if materials
+            .get(&planet.0)
+            .is_none_or(|material| material.light_position == position)
+        {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&planet.0) {
+            material.light_position = position;
+        }
+    }
+}
+
+/// Switches between smooth and flat shading with the F key.
+pub fn toggle_shading(keys: Res<ButtonInput<KeyCode>>, mut display: ResMut<FieldDisplay>) {
+    if keys.just_pressed(KeyCode::KeyF) {
+        display.shading = display.shading.toggled();
+    }
+}
+
+/// Locks or unlocks the colour scale with the L key.
+pub fn toggle_range_lock(keys: Res<ButtonInput<KeyCode>>, mut display: ResMut<FieldDisplay>) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        display.locked = !display.locked;
+    }
+}
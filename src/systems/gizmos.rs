@@ -1,45 +1,136 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 
-use crate::resources::mantle_grid::MantleGrid;
+use crate::{
+    components::hotspot::Hotspot,
+    materials::pressure_material::DEFAULT_ELEVATION_EXAGGERATION,
+    plugins::grid_debug_overlay::{CellCenterGizmos, GridLineGizmos, NeighborGizmos, OverlayView},
+    resources::{
+        mantle_grid::{MantleGrid, PLANET_RADIUS},
+        plates::Plates,
+    },
+    simulation::plate_dynamics::{BoundaryKind, PlateDynamicsParameters, boundary_edges},
+};
 
-pub fn draw_triangle_grid(mut gizmos: Gizmos, grid: Res<MantleGrid>) {
+/// Draws the edges of the cells in view, each once. A shared edge is drawn when either of its
+/// cells is in view, so that edges along the boundary of the view do not go missing.
+pub fn draw_triangle_grid(
+    mut gizmos: Gizmos<GridLineGizmos>,
+    grid: Res<MantleGrid>,
+    view: OverlayView,
+) {
+    let Some(in_view) = view.filter() else {
+        return;
+    };
     let points = grid.sphere.raw_points();
-    let indices = grid.sphere.get_all_indices();
-
-    for triangle in indices.chunks(3) {
-        let (a, b, c) = (
-            triangle[0] as usize,
-            triangle[1] as usize,
-            triangle[2] as usize,
-        );
 
-        let pa = points[a];
-        let pb = points[b];
-        let pc = points[c];
-
-        gizmos.line(pa.into(), pb.into(), Color::srgb(0.0, 1.0, 0.5));
-        gizmos.line(pb.into(), pc.into(), Color::srgb(0.0, 1.0, 0.5));
-        gizmos.line(pc.into(), pa.into(), Color::srgb(0.0, 1.0, 0.5));
+    let mut drawn = HashSet::new();
+    for (cell, data) in grid.cells.iter().enumerate() {
+        if !in_view(data.center) {
+            continue;
+        }
+        let [a, b, c] = grid.triangle(cell);
+        for (start, end) in [(a, b), (b, c), (c, a)] {
+            // The edge runs one way round this cell and the other way round its neighbour.
+            if drawn.insert((start.min(end), start.max(end))) {
+                gizmos.line(
+                    points[start as usize].into(),
+                    points[end as usize].into(),
+                    Color::srgb(0.0, 1.0, 0.5),
+                );
+            }
+        }
     }
 }
 
-pub fn draw_triangle_grid_centers(mut gizmos: Gizmos, grid: Res<MantleGrid>) {
-    let indices = grid.sphere.get_all_indices();
-    for triangle_idx in 0..indices.len() / 3 {
-        let center = grid.cells[triangle_idx].center;
-        gizmos.cross(center, 0.005, Color::srgb(1.0, 0.0, 0.0));
+pub fn draw_triangle_grid_centers(
+    mut gizmos: Gizmos<CellCenterGizmos>,
+    grid: Res<MantleGrid>,
+    view: OverlayView,
+) {
+    let Some(in_view) = view.filter() else {
+        return;
+    };
+    for cell in grid.cells.iter().filter(|cell| in_view(cell.center)) {
+        gizmos.cross(cell.center, 0.005, Color::srgb(1.0, 0.0, 0.0));
     }
 }
 
-pub fn draw_triangle_grid_neighbors(mut gizmos: Gizmos, grid: Res<MantleGrid>) {
-    for i in 0..grid.cells.len() {
-        let center_i = grid.cells[i].center;
+pub fn draw_triangle_grid_neighbors(
+    mut gizmos: Gizmos<NeighborGizmos>,
+    grid: Res<MantleGrid>,
+    view: OverlayView,
+) {
+    let Some(in_view) = view.filter() else {
+        return;
+    };
+    for (i, cell) in grid.cells.iter().enumerate() {
+        if !in_view(cell.center) {
+            continue;
+        }
         for &neighbor_idx in &grid.neighbors[i] {
             if neighbor_idx > i {
                 // Only draw each connection once
                 let center_j = grid.cells[neighbor_idx].center;
-                gizmos.line(center_i, center_j, Color::srgb(0.0, 0.0, 1.0));
+                gizmos.line(cell.center, center_j, Color::srgb(0.0, 0.0, 1.0));
             }
         }
     }
 }
+
+pub fn draw_hotspots(mut gizmos: Gizmos, hotspots: Query<&Hotspot>) {
+    for hotspot in &hotspots {
+        let isometry = Isometry3d::new(
+            hotspot.position * 1.01,
+            Quat::from_rotation_arc(Vec3::Z, hotspot.position),
+        );
+        gizmos.circle(
+            isometry,
+            hotspot.radius.sin() * 1.01,
+            Color::srgb(1.0, 0.5, 0.0),
+        );
+    }
+}
+
+/// Draws the edges between plates, coloured by the kind of boundary: red where plates spread,
+/// blue where one subducts, yellow where they collide and green where they slide past.
+pub fn draw_plate_boundaries(
+    mut gizmos: Gizmos,
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    parameters: Res<PlateDynamicsParameters>,
+) {
+    let points = grid.sphere.raw_points();
+    for edge in boundary_edges(&grid, &plates, &parameters) {
+        let vertices = grid.triangle(edge.cell);
+        let mut shared = grid
+            .triangle(edge.neighbor)
+            .into_iter()
+            .filter(|vertex| vertices.contains(vertex));
+        let (Some(a), Some(b)) = (shared.next(), shared.next()) else {
+            continue;
+        };
+        let color = match edge.kind {
+            BoundaryKind::Ridge => Color::srgb(1.0, 0.2, 0.2),
+            BoundaryKind::Subduction { .. } => Color::srgb(0.2, 0.4, 1.0),
+            BoundaryKind::Collision => Color::srgb(1.0, 0.9, 0.2),
+            BoundaryKind::Transform => Color::srgb(0.2, 1.0, 0.4),
+        };
+        let elevation = grid.cells[edge.cell]
+            .elevation
+            .max(grid.cells[edge.neighbor].elevation);
+        let radius = surface_radius(elevation);
+        gizmos.line(
+            Vec3::from(points[a as usize]).normalize() * radius,
+            Vec3::from(points[b as usize]).normalize() * radius,
+            color,
+        );
+    }
+}
+
+/// Distance from the centre of the planet mesh to the displaced surface above `elevation`, with a
+/// small margin so that lines are not hidden by the surface they lie on.
+pub fn surface_radius(elevation: f32) -> f32 {
+    1.002 + elevation.max(0.0) * DEFAULT_ELEVATION_EXAGGERATION / PLANET_RADIUS
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::hotspot::Hotspot,
+    resources::{mantle_grid::MantleGrid, simulation_clock::SimulationClock},
+    simulation::hotspots::{apply_hotspot, cool_mantle},
+};
+
+pub fn drift_hotspots(mut hotspots: Query<&mut Hotspot>, clock: Res<SimulationClock>) {
+    for mut hotspot in &mut hotspots {
+        let drift = Quat::from_axis_angle(hotspot.drift_axis, hotspot.drift_rate * clock.time_step);
+        hotspot.position = (drift * hotspot.position).normalize();
+    }
+}
+
+pub fn apply_hotspots(
+    mut grid: ResMut<MantleGrid>,
+    hotspots: Query<&Hotspot>,
+    clock: Res<SimulationClock>,
+) {
+    cool_mantle(&mut grid, clock.time_step);
+    for hotspot in &hotspots {
+        apply_hotspot(&mut grid, hotspot, clock.time_step);
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::{
+    resources::{
+        brush::Brush,
+        cell_inspector::CellInspector,
+        cell_pressures::CellPressures,
+        mantle_grid::{MantleGrid, PLANET_RADIUS},
+        planet_cursor::PlanetCursor,
+    },
+    systems::gizmos::surface_radius,
+};
+
+/// Largest distance, in logical pixels, the cursor may move between press and release for a
+/// click to select a cell rather than orbit the camera.
+const CLICK_TOLERANCE: f32 = 4.0;
+
+/// Offset of the tooltip from the cursor, in logical pixels.
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+
+/// Picks the cells under the cursor, shows the hovered one in a tooltip and the selected one in
+/// a window of its own.
+#[allow(clippy::too_many_arguments)]
+pub fn inspect_cells(
+    mut contexts: EguiContexts,
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: Res<PlanetCursor>,
+    grid: Res<MantleGrid>,
+    pressures: Res<CellPressures>,
+    mut inspector: ResMut<CellInspector>,
+    brush: Option<Res<Brush>>,
+    mut press_position: Local<Option<Vec2>>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let over_ui = ctx.is_pointer_over_area();
+    let hovered = if over_ui { None } else { cursor.cell };
+
+    let mut selected = inspector.selected;
+    if mouse.just_pressed(MouseButton::Left) {
+        *press_position = cursor.screen_position;
+    }
+    // While the brush is on, clicks paint instead.
+    let painting = brush.is_some_and(|brush| brush.active);
+    if mouse.just_released(MouseButton::Left) && !over_ui && !painting {
+        let clicked = press_position
+            .take()
+            .zip(cursor.screen_position)
+            .is_some_and(|(pressed, released)| pressed.distance(released) <= CLICK_TOLERANCE);
+        if clicked {
+            selected = if hovered == selected { None } else { hovered };
+        }
+    }
+
+    if let (Some(cell), Some(position)) = (hovered, cursor.screen_position)
+        && hovered != selected
+    {
+        let position = position + TOOLTIP_OFFSET;
+        egui::Area::new(egui::Id::new("cell_tooltip"))
+            .fixed_pos(egui::pos2(position.x, position.y))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    cell_details(ui, &grid, &pressures, cell);
+                });
+            });
+    }
+
+    if let Some(cell) = selected {
+        let mut open = true;
+        egui::Window::new(format!("Cell {cell}"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| cell_details(ui, &grid, &pressures, cell));
+        if !open {
+            selected = None;
+        }
+    }
+
+    inspector.set_if_neq(CellInspector { hovered, selected });
+    Ok(())
+}
+
+fn cell_details(ui: &mut egui::Ui, grid: &MantleGrid, pressures: &CellPressures, cell: usize) {
+    let data = &grid.cells[cell];
+    let position = grid.cell_lat_lon(cell);
+    egui::Grid::new(("cell_details", cell)).show(ui, |ui| {
+        let mut row = |label: &str, value: String| {
+            ui.label(label);
+            ui.label(value);
+            ui.end_row();
+        };
+        row("Index", cell.to_string());
+        row(
+            "Position",
+            format!("{:.2}°, {:.2}°", position.latitude, position.longitude),
+        );
+        row(
+            "Area",
+            format!("{:.0} km²", grid.cell_area_km2(cell, PLANET_RADIUS)),
+        );
+        row("Plate", data.plate.to_string());
+        row("Pressure", format!("{:.3}", pressures.get(grid, cell)));
+        row("Elevation", format!("{:.0} m", data.elevation));
+        row("Temperature", format!("{:.1} K", data.temperature));
+        row("Age", format!("{:.1} Myr", data.age / 1.0e6));
+        for (neighbor, flux) in grid.neighbors[cell].iter().zip(&data.flux) {
+            row("Neighbour", format!("{neighbor}, flux {flux:.3}"));
+        }
+    });
+}
+
+/// Outlines the hovered cell in white and the selected one in yellow.
+pub fn draw_inspected_cells(
+    mut gizmos: Gizmos,
+    grid: Res<MantleGrid>,
+    inspector: Res<CellInspector>,
+) {
+    let points = grid.sphere.raw_points();
+    let outlines = [
+        (inspector.hovered, Color::WHITE),
+        (inspector.selected, Color::srgb(1.0, 0.85, 0.0)),
+    ];
+    for (cell, color) in outlines {
+        let Some(cell) = cell else {
+            continue;
+        };
+        let radius = surface_radius(grid.cells[cell].elevation);
+        let corners = grid
+            .triangle(cell)
+            .map(|vertex| Vec3::from(points[vertex as usize]).normalize() * radius);
+        gizmos.linestrip([corners[0], corners[1], corners[2], corners[0]], color);
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::{
+    materials::pressure_material::{ColorScale, PressureMaterial},
+    resources::field_display::FieldDisplay,
+};
+
+/// Coloured segments making up the legend's bar.
+const LEGEND_SEGMENTS: usize = 64;
+const LEGEND_WIDTH: f32 = 256.0;
+const LEGEND_FONT_SIZE: f32 = 14.0;
+
+#[derive(Component)]
+pub struct ColorLegendTitle;
+
+#[derive(Component)]
+pub struct ColorLegendSegment(pub usize);
+
+/// Value labels under the bar, from left to right.
+#[derive(Component)]
+pub struct ColorLegendLabel(pub usize);
+
+/// Spawns the colour bar in the bottom right corner of the window. Its contents are filled in by
+/// `update_color_legend`.
+pub fn spawn_color_legend(mut commands: Commands) {
+    let text = |content: &str| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: LEGEND_FONT_SIZE,
+                ..default()
+            },
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(16.0),
+                bottom: Val::Px(16.0),
+                width: Val::Px(LEGEND_WIDTH),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        ))
+        .with_children(|legend| {
+            legend.spawn((text(""), ColorLegendTitle));
+            legend
+                .spawn(Node {
+                    height: Val::Px(14.0),
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                })
+                .with_children(|bar| {
+                    for segment in 0..LEGEND_SEGMENTS {
+                        bar.spawn((
+                            Node {
+                                flex_grow: 1.0,
+                                ..default()
+                            },
+                            BackgroundColor(Color::BLACK),
+                            ColorLegendSegment(segment),
+                        ));
+                    }
+                });
+            legend
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                })
+                .with_children(|labels| {
+                    for label in 0..3 {
+                        labels.spawn((text(""), ColorLegendLabel(label)));
+                    }
+                });
+        });
+}
+
+/// Redraws the legend whenever the planet's colour scale or the displayed field changes, using
+/// the same mapping from values to colours as the planet material.
+pub fn update_color_legend(
+    display: Res<FieldDisplay>,
+    planets: Query<&MeshMaterial3d<PressureMaterial>>,
+    materials: Res<Assets<PressureMaterial>>,
+    mut shown: Local<Option<(ColorScale, FieldDisplay)>>,
+    mut segments: Query<(&ColorLegendSegment, &mut BackgroundColor)>,
+    mut titles: Query<&mut Text, (With<ColorLegendTitle>, Without<ColorLegendLabel>)>,
+    mut labels: Query<(&ColorLegendLabel, &mut Text), Without<ColorLegendTitle>>,
+) {
+    let Some(color_scale) = planets
+        .iter()
+        .find_map(|planet| materials.get(&planet.0))
+        .map(|material| material.color_scale)
+    else {
+        return;
+    };
+    if shown
+        .as_ref()
+        .is_some_and(|(scale, shown_display)| *scale == color_scale && *shown_display == *display)
+    {
+        return;
+    }
+    *shown = Some((color_scale, display.clone()));
+
+    let categorical = color_scale.categories > 0;
+    // Value at a fraction of the bar, whole category ids for a categorical colormap.
+    let value_at = |t: f32| {
+        if categorical {
+            (t * color_scale.categories as f32).floor()
+        } else {
+            color_scale.min + t * (color_scale.max - color_scale.min)
+        }
+    };
+
+    for (segment, mut background) in &mut segments {
+        let t = (segment.0 as f32 + 0.5) / LEGEND_SEGMENTS as f32;
+        let color = display.colormap.color(color_scale.position(value_at(t)));
+        background.0 = color.into();
+    }
+
+    let field = display.field;
+    let mut title = field.name().to_owned();
+    if !field.units().is_empty() {
+        title.push_str(&format!(" ({})", field.units()));
+    }
+    if display.locked {
+        title.push_str(", locked");
+    }
+    for mut text in &mut titles {
+        text.0.clone_from(&title);
+    }
+
+    for (label, mut text) in &mut labels {
+        text.0 = if categorical {
+            match label.0 {
+                0 => "0".to_owned(),
+                2 => (color_scale.categories - 1).to_string(),
+                _ => String::new(),
+            }
+        } else {
+            format_value(value_at(label.0 as f32 / 2.0))
+        };
+    }
+}
+
+/// Short label for a value on the colour bar.
+fn format_value(value: f32) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(0.01..100_000.0).contains(&magnitude) {
+        format!("{value:.2e}")
+    } else if magnitude >= 100.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
@@ -0,0 +1,266 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::{ScalingMode, Viewport, visibility::RenderLayers},
+    mesh::PrimitiveTopology,
+    prelude::*,
+    render::storage::ShaderStorageBuffer,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    materials::{map_material::MapMaterial, pressure_material::PressureMaterial},
+    resources::{
+        mantle_grid::MantleGrid,
+        map_view::{MapProjection, MapView},
+    },
+    simulation::geography::LatLon,
+};
+
+/// Render layer holding the map and its camera, which the globe's camera does not see.
+const MAP_LAYER: usize = 1;
+/// Gap between the map and the edges of the window, in logical pixels.
+const MAP_MARGIN: f32 = 16.0;
+/// Empty border around the map within its viewport, as a fraction of the map's size.
+const MAP_PADDING: f32 = 0.04;
+
+/// Camera drawing the map into a viewport of its own over the window.
+#[derive(Component)]
+pub struct MapCamera;
+
+/// The flattened planet, and the projection it was built with.
+#[derive(Component)]
+pub struct MapSurface(pub MapProjection);
+
+/// Cycles the map through the projections and off again with the M key.
+pub fn cycle_map_projection(keys: Res<ButtonInput<KeyCode>>, mut view: ResMut<MapView>) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        view.projection = view.cycled();
+    }
+}
+
+/// Builds the map, and its camera, whenever the projection changes, or removes them when the map
+/// is hidden.
+#[allow(clippy::too_many_arguments)]
+pub fn update_map_view(
+    mut commands: Commands,
+    view: Res<MapView>,
+    grid: Res<MantleGrid>,
+    planets: Query<&MeshMaterial3d<PressureMaterial>>,
+    planet_materials: Res<Assets<PressureMaterial>>,
+    mut map_materials: ResMut<Assets<MapMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut storage_buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    surfaces: Query<(Entity, &MapSurface)>,
+    cameras: Query<Entity, With<MapCamera>>,
+) {
+    let shown = surfaces.iter().next().map(|(_, surface)| surface.0);
+    if shown == view.projection {
+        return;
+    }
+    let Some(planet) = planets
+        .iter()
+        .find_map(|planet| planet_materials.get(&planet.0))
+    else {
+        return;
+    };
+
+    for (entity, _) in &surfaces {
+        commands.entity(entity).despawn();
+    }
+    for entity in &cameras {
+        commands.entity(entity).despawn();
+    }
+    let Some(projection) = view.projection else {
+        return;
+    };
+
+    let (mesh, map_vertices, map_cells) = map_mesh(&grid, projection);
+    commands.spawn((
+        MapSurface(projection),
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(map_materials.add(MapMaterial {
+            vertex_values: planet.vertex_values.clone(),
+            color_scale: planet.color_scale,
+            colormap: planet.colormap.clone(),
+            cell_values: planet.cell_values.clone(),
+            map_vertices: storage_buffers.add(ShaderStorageBuffer::from(map_vertices)),
+            map_cells: storage_buffers.add(ShaderStorageBuffer::from(map_cells)),
+            flat_shading: planet.flat_shading,
+        })),
+        Transform::default(),
+        RenderLayers::layer(MAP_LAYER),
+    ));
+
+    let extent = projection.extent() * (1.0 + MAP_PADDING);
+    commands.spawn((
+        MapCamera,
+        Camera3d::default(),
+        Camera {
+            // Drawn over the globe, into the viewport set by `layout_map_view`.
+            order: 1,
+            clear_color: ClearColorConfig::Custom(Color::srgb(0.05, 0.05, 0.08)),
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: 2.0 * extent.x,
+                height: 2.0 * extent.y,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        RenderLayers::layer(MAP_LAYER),
+    ));
+}
+
+/// Lays every cell out flat under `projection`, as three vertices of its own, returning the mesh
+/// with the grid vertex and cell behind each of its vertices. Cells crossing the antimeridian of a
+/// map cut there are drawn on both edges.
+fn map_mesh(grid: &MantleGrid, projection: MapProjection) -> (Mesh, Vec<u32>, Vec<u32>) {
+    let points = grid.sphere.raw_points();
+    let wrap = |angle: f32| (angle + PI).rem_euclid(TAU) - PI;
+
+    let mut positions = Vec::with_capacity(grid.cells.len() * 3);
+    let mut map_vertices = Vec::with_capacity(grid.cells.len() * 3);
+    let mut map_cells = Vec::with_capacity(grid.cells.len() * 3);
+    for (cell, data) in grid.cells.iter().enumerate() {
+        let reference = LatLon::from_unit_vector(data.center).longitude.to_radians() as f32;
+        // Longitudes are taken relative to the cell's own, so that its corners stay together.
+        let corners = grid.triangle(cell).map(|vertex| {
+            let position = LatLon::from_unit_vector(Vec3::from(points[vertex as usize]));
+            let longitude = reference + wrap(position.longitude.to_radians() as f32 - reference);
+            let pole = 90.0 - position.latitude.abs() < 1.0e-6;
+            (
+                vertex,
+                position.latitude.to_radians() as f32,
+                longitude,
+                pole,
+            )
+        });
+
+        // A pole has no longitude of its own: it is stretched along the top or bottom edge of the
+        // map between the longitudes of the other two corners, turning the cell into a quad.
+        let triangles = match corners.iter().position(|corner| corner.3) {
+            Some(pole) => {
+                let (vertex, latitude, ..) = corners[pole];
+                let a = corners[(pole + 1) % 3];
+                let b = corners[(pole + 2) % 3];
+                let above_a = (vertex, latitude, a.2, true);
+                let above_b = (vertex, latitude, b.2, true);
+                vec![[a, b, above_b], [a, above_b, above_a]]
+            }
+            None => vec![corners],
+        };
+
+        let crosses_antimeridian = corners
+            .iter()
+            .any(|&(_, _, longitude, pole)| !pole && longitude.abs() > PI);
+        let copies: &[f32] = if projection.wraps() && crosses_antimeridian {
+            &[0.0, -TAU * reference.signum()]
+        } else {
+            &[0.0]
+        };
+        for triangle in &triangles {
+            for &shift in copies {
+                let mut projected = triangle.map(|(vertex, latitude, longitude, _)| {
+                    (
+                        vertex,
+                        projection.project(latitude, longitude + shift, reference),
+                    )
+                });
+                // Keep every triangle counter-clockwise as the map camera sees it.
+                let [(_, a), (_, b), (_, c)] = projected;
+                if (b - a).perp_dot(c - a) < 0.0 {
+                    projected.swap(1, 2);
+                }
+                for (vertex, position) in projected {
+                    positions.push([position.x, position.y, 0.0]);
+                    map_vertices.push(vertex);
+                    map_cells.push(cell as u32);
+                }
+            }
+        }
+    }
+
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    (mesh, map_vertices, map_cells)
+}
+
+/// Keeps the map's colours in step with the planet's: its colour scale, colormap and shading.
+pub fn sync_map_material(
+    planets: Query<&MeshMaterial3d<PressureMaterial>>,
+    planet_materials: Res<Assets<PressureMaterial>>,
+    surfaces: Query<&MeshMaterial3d<MapMaterial>, With<MapSurface>>,
+    mut map_materials: ResMut<Assets<MapMaterial>>,
+) {
+    let Some(planet) = planets
+        .iter()
+        .find_map(|planet| planet_materials.get(&planet.0))
+    else {
+        return;
+    };
+    for surface in &surfaces {
+        // Only take the material mutably when something differs, so that its bind group is not
+        // rebuilt every frame.
+        if map_materials.get(&surface.0).is_none_or(|map| {
+            map.color_scale == planet.color_scale
+                && map.colormap == planet.colormap
+                && map.flat_shading == planet.flat_shading
+        }) {
+            continue;
+        }
+        let Some(map) = map_materials.get_mut(&surface.0) else {
+            continue;
+        };
+        map.color_scale = planet.color_scale;
+        map.colormap = planet.colormap.clone();
+        map.flat_shading = planet.flat_shading;
+    }
+}
+
+/// Places the map's viewport in the top right corner of the window, `MapView::width` of the
+/// window wide and as tall as the projection needs.
+pub fn layout_map_view(
+    view: Res<MapView>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Camera, &Projection), With<MapCamera>>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let window_size = window.physical_size().as_vec2();
+    let margin = MAP_MARGIN * window.scale_factor();
+
+    for (mut camera, projection) in &mut cameras {
+        let Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed { width, height },
+            ..
+        }) = *projection
+        else {
+            continue;
+        };
+        let available = (window_size - 2.0 * margin).max(Vec2::ONE);
+        let map_width = (window_size.x * view.width)
+            .min(available.x)
+            .min(available.y * width / height);
+        let size = Vec2::new(map_width, map_width * height / width).max(Vec2::ONE);
+        let position = Vec2::new(window_size.x - margin - size.x, margin).as_uvec2();
+        let size = size.as_uvec2();
+        let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == size
+        });
+        if !unchanged {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: size,
+                ..default()
+            });
+        }
+    }
+}
@@ -1,2 +1,17 @@
+pub mod brush;
+pub mod camera;
+pub mod control_panel;
+pub mod cursor;
+pub mod erosion;
+pub mod exports;
+pub mod field_display;
 pub mod gizmos;
+pub mod hotspots;
+pub mod inspector;
+pub mod legend;
+pub mod map_view;
+pub mod plates;
 pub mod setup;
+pub mod shortcuts;
+pub mod simulation_clock;
+pub mod velocity_field;
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::{
+    resources::{
+        cell_locator::CellLocator, cell_pressures::CellPressures, mantle_grid::MantleGrid,
+        plates::Plates, simulation_clock::SimulationClock,
+    },
+    simulation::{
+        advection::advect,
+        plate_dynamics::{PlateDynamicsParameters, solve_plate_motions, update_mantle_flux},
+    },
+};
+
+/// Derives the mantle flow from the latest pressures: those read back from the GPU solver when it
+/// runs, otherwise the grid's own.
+///
+/// In the windowed mode the pressures are read back asynchronously, so the flow driving a step
+/// lags the solver by one or more frames. Headless runs have no solver and use the grid's
+/// pressures, which are always current.
+pub fn update_mantle_flow(
+    mut grid: ResMut<MantleGrid>,
+    pressures: Option<Res<CellPressures>>,
+    parameters: Res<PlateDynamicsParameters>,
+) {
+    match pressures {
+        Some(pressures) => update_mantle_flux(
+            &mut grid,
+            |grid, cell| pressures.get(grid, cell),
+            &parameters,
+        ),
+        None => update_mantle_flux(
+            &mut grid,
+            |grid, cell| grid.cells[cell].pressure,
+            &parameters,
+        ),
+    }
+}
+
+pub fn solve_plate_dynamics(
+    grid: Res<MantleGrid>,
+    mut plates: ResMut<Plates>,
+    parameters: Res<PlateDynamicsParameters>,
+    clock: Res<SimulationClock>,
+) {
+    solve_plate_motions(&grid, &mut plates, &parameters, clock.time_step);
+}
+
+pub fn advect_plates(
+    mut grid: ResMut<MantleGrid>,
+    locator: Res<CellLocator>,
+    plates: Res<Plates>,
+    clock: Res<SimulationClock>,
+) {
+    advect(&mut grid, &locator, &plates, clock.time_step);
+}
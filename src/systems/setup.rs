@@ -1,18 +1,87 @@
-use bevy::{core_pipeline::Skybox, prelude::*, render::storage::ShaderStorageBuffer};
+use bevy::{
+    core_pipeline::Skybox,
+    prelude::*,
+    render::{
+        gpu_readback::{Readback, ReadbackComplete},
+        storage::ShaderStorageBuffer,
+    },
+};
 use bevy_panorbit_camera::PanOrbitCamera;
 
 use crate::{
-    materials::pressure_material::PressureMaterial,
-    resources::{mantle_grid::MantleGrid, vertex_pressure_buffer::VertexPressureBufferHandle},
+    materials::pressure_material::{DEFAULT_ELEVATION_EXAGGERATION, PressureMaterial},
+    resources::{
+        camera_settings::CameraSettings,
+        cell_field_buffer::CellFieldBufferHandle,
+        cell_locator::CellLocator,
+        cell_pressures::{CellPressures, PressureReadbackBufferHandle},
+        field_display::{FieldDisplay, Shading},
+        field_statistics::FieldStatistics,
+        mantle_grid::MantleGrid,
+        simulation_config::SimulationConfig,
+        vertex_elevation_buffer::VertexElevationBufferHandle,
+        vertex_field_buffer::VertexFieldBufferHandle,
+    },
+    simulation::{
+        erosion::CellGeometry,
+        plate_dynamics::{PlateDynamicsParameters, update_mantle_flux},
+        scenario::Scenario,
+    },
+    systems::camera::{HOME_POSITION, configure_camera},
 };
 
+/// Where the point light lighting the planet starts out.
+const LIGHT_POSITION: Vec3 = Vec3::new(4.0, 8.0, 4.0);
+
+/// Generates the configured scenario and inserts the simulation state.
+///
+/// A run asked to start from initial conditions or imported data that cannot be set up is ended
+/// with an error rather than run from a start state it did not ask for. The generated scenario is
+/// still inserted, so that the systems of the frame before the exit find what they need.
+pub fn setup_simulation(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    dynamics: Res<PlateDynamicsParameters>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let mut scenario = Scenario::generate(&config.scenario);
+    if let Err(error) = config.initial_conditions.apply(&mut scenario.grid) {
+        error!("Failed to set the initial conditions: {error}");
+        exit.write(AppExit::error());
+    } else if let Err(error) = config.import.apply(
+        &mut scenario.grid,
+        &mut scenario.plates,
+        &mut scenario.hotspots,
+    ) {
+        error!("Failed to import Earth data: {error}");
+        exit.write(AppExit::error());
+    }
+    update_mantle_flux(
+        &mut scenario.grid,
+        |grid, cell| grid.cells[cell].pressure,
+        &dynamics,
+    );
+    commands.insert_resource(CellLocator::new(&scenario.grid));
+    commands.insert_resource(CellGeometry::new(&scenario.grid));
+    commands.insert_resource(scenario.grid);
+    commands.insert_resource(scenario.plates);
+    for hotspot in scenario.hotspots {
+        commands.spawn(hotspot);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut pressure_materials: ResMut<Assets<PressureMaterial>>,
     mut storage_buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    mut images: ResMut<Assets<Image>>,
     asset_server: Res<AssetServer>,
+    grid: Res<MantleGrid>,
+    display: Res<FieldDisplay>,
+    camera_settings: Res<CameraSettings>,
 ) {
     // Spawn the sphere
     commands.spawn((
@@ -21,35 +90,72 @@ pub fn setup(
         Transform::from_xyz(0.0, 0.0, 0.0),
     ));
 
-    let grid = MantleGrid::new(20);
     let mesh = grid.mesh();
 
     let num_vertices = grid.sphere.raw_points().len();
-    let vertex_pressure_data = vec![0.0f32; num_vertices];
-    let mut vertex_pressure_buffer_asset = ShaderStorageBuffer::from(vertex_pressure_data);
-    vertex_pressure_buffer_asset.buffer_description.usage |=
+    let vertex_field_data = vec![0.0f32; num_vertices];
+    let mut vertex_field_buffer_asset = ShaderStorageBuffer::from(vertex_field_data);
+    vertex_field_buffer_asset.buffer_description.usage |=
+        bevy::render::render_resource::BufferUsages::STORAGE;
+    let vertex_field_buffer = storage_buffers.add(vertex_field_buffer_asset);
+    commands.insert_resource(VertexFieldBufferHandle(vertex_field_buffer.clone()));
+
+    let vertex_elevation_data = vec![0.0f32; num_vertices];
+    let mut vertex_elevation_buffer_asset = ShaderStorageBuffer::from(vertex_elevation_data);
+    vertex_elevation_buffer_asset.buffer_description.usage |=
         bevy::render::render_resource::BufferUsages::STORAGE;
-    let vertex_pressure_buffer = storage_buffers.add(vertex_pressure_buffer_asset);
-    commands.insert_resource(VertexPressureBufferHandle(vertex_pressure_buffer.clone()));
+    let vertex_elevation_buffer = storage_buffers.add(vertex_elevation_buffer_asset);
+    commands.insert_resource(VertexElevationBufferHandle(vertex_elevation_buffer.clone()));
+
+    // Filled by the render world while the field is shaded flat.
+    let cell_field_data = vec![0.0f32; grid.cells.len()];
+    let mut cell_field_buffer_asset = ShaderStorageBuffer::from(cell_field_data);
+    cell_field_buffer_asset.buffer_description.usage |=
+        bevy::render::render_resource::BufferUsages::STORAGE
+            | bevy::render::render_resource::BufferUsages::COPY_DST;
+    let cell_field_buffer = storage_buffers.add(cell_field_buffer_asset);
+    commands.insert_resource(CellFieldBufferHandle(cell_field_buffer.clone()));
+
+    let mesh_vertices = storage_buffers.add(ShaderStorageBuffer::from(grid.indices.clone()));
+
+    let pressure_readback_data = vec![0.0f32; grid.cells.len()];
+    let mut pressure_readback_buffer_asset = ShaderStorageBuffer::from(pressure_readback_data);
+    pressure_readback_buffer_asset.buffer_description.usage |=
+        bevy::render::render_resource::BufferUsages::COPY_DST
+            | bevy::render::render_resource::BufferUsages::COPY_SRC;
+    let pressure_readback_buffer = storage_buffers.add(pressure_readback_buffer_asset);
+    commands.insert_resource(PressureReadbackBufferHandle(
+        pressure_readback_buffer.clone(),
+    ));
+    // The readback stays on the entity, so the pressures are read back every frame.
+    commands
+        .spawn(Readback::buffer(pressure_readback_buffer))
+        .observe(store_cell_pressures);
 
     commands.spawn((
         Mesh3d(meshes.add(mesh)),
         MeshMaterial3d(pressure_materials.add(PressureMaterial {
-            vertex_pressure: vertex_pressure_buffer,
+            vertex_values: vertex_field_buffer,
+            vertex_elevation: vertex_elevation_buffer,
+            elevation_exaggeration: DEFAULT_ELEVATION_EXAGGERATION,
+            color_scale: display.color_scale(&FieldStatistics::compute(&grid, display.field)),
+            colormap: images.add(display.colormap.image()),
+            cell_values: cell_field_buffer,
+            mesh_vertices,
+            flat_shading: u32::from(display.shading == Shading::Flat),
+            light_position: LIGHT_POSITION,
         })),
         Transform::from_xyz(0.0, 0.0, 0.0),
     ));
-    commands.insert_resource(grid);
 
     // Spawn the camera
+    let mut camera = PanOrbitCamera::default();
+    configure_camera(&mut camera, &camera_settings);
     commands.spawn((
-        PanOrbitCamera {
-            zoom_upper_limit: Some(10.0),
-            zoom_lower_limit: 2.0,
-            pan_sensitivity: 0.0,
-            ..Default::default()
-        },
-        Transform::from_xyz(0.0, 2.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        camera,
+        Transform::from_translation(HOME_POSITION).looking_at(Vec3::ZERO, Vec3::Y),
+        // The legend stays on the full window rather than following the map's camera.
+        IsDefaultUiCamera,
         Skybox {
             image: asset_server.load("textures/Standard-Cube-Map/stars.ktx2"),
 
@@ -64,6 +170,11 @@ pub fn setup(
             intensity: 1500.0,
             ..default()
         },
-        Transform::from_xyz(4.0, 8.0, 4.0),
+        Transform::from_translation(LIGHT_POSITION),
     ));
 }
+
+/// Keeps `CellPressures` up to date with the pressures read back from the GPU.
+pub fn store_cell_pressures(event: On<ReadbackComplete>, mut pressures: ResMut<CellPressures>) {
+    pressures.0 = event.to_shader_type();
+}
@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+/// Run condition for the single-key shortcuts: they are off while a text field of the control
+/// windows has keyboard focus, so that typing into it does not toggle anything.
+pub fn shortcuts_enabled(egui_input: Option<Res<EguiWantsInput>>) -> bool {
+    egui_input.is_none_or(|input| !input.wants_keyboard_input())
+}
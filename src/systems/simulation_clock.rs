@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::{
+    plugins::simulation::SimulationStep,
+    resources::{
+        mantle_grid::MantleGrid,
+        simulation_clock::{SimulationClock, SimulationControl},
+        simulation_config::SimulationConfig,
+    },
+};
+
+/// Runs the simulation schedule as many times as `SimulationControl` asks for this frame.
+pub fn run_simulation_steps(world: &mut World) {
+    let control = world.resource::<SimulationControl>();
+    let steps = if !control.paused {
+        control.steps_per_frame
+    } else if control.pending_steps > 0 {
+        std::mem::take(&mut world.resource_mut::<SimulationControl>().pending_steps)
+    } else {
+        0
+    };
+    for _ in 0..steps {
+        world.run_schedule(SimulationStep);
+    }
+}
+
+pub fn advance_clock(mut clock: ResMut<SimulationClock>) {
+    clock.step += 1;
+    clock.elapsed += f64::from(clock.time_step);
+}
+
+/// Run condition that holds once the configured number of steps has been taken.
+pub fn run_finished(clock: Res<SimulationClock>, config: Res<SimulationConfig>) -> bool {
+    config.steps > 0 && clock.step >= config.steps
+}
+
+/// Ends a headless run, to be scheduled with [`run_finished`].
+pub fn exit_after_steps(
+    clock: Res<SimulationClock>,
+    grid: Res<MantleGrid>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    info!(
+        "Stopping after {} steps, {:.1} Myr simulated, grid checksum {:016x}",
+        clock.step,
+        clock.elapsed / 1.0e6,
+        grid.checksum()
+    );
+    exit.write(AppExit::Success);
+}
@@ -0,0 +1,302 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, MeshTag, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::{
+    materials::{
+        colormap::Colormap,
+        velocity_arrow_material::{ARROW_TAG_SCALE, VelocityArrowMaterial},
+    },
+    resources::{
+        cell_locator::CellLocator, mantle_grid::MantleGrid, overlays::Overlays, plates::Plates,
+        velocity_field::VelocityField,
+    },
+    simulation::plate_dynamics::mantle_velocity,
+    systems::gizmos::surface_radius,
+};
+
+/// Plate speed, in radians per year, drawn as an arrow as long as the spacing between arrows.
+const REFERENCE_PLATE_SPEED: f32 = 1.0e-8;
+/// Longest arrow, as a multiple of the spacing between arrows.
+const MAX_ARROW_LENGTH: f32 = 2.0;
+/// Steps traced along each streamline.
+const STREAMLINE_STEPS: usize = 24;
+/// Length of a streamline step, as a fraction of the spacing between seeds.
+const STREAMLINE_STEP_LENGTH: f32 = 0.25;
+/// Least time between rebuilds of the streamlines while the flow keeps changing, in seconds.
+const STREAMLINE_INTERVAL: f32 = 0.5;
+
+/// Mesh and material shared by every velocity arrow, so that they are drawn as instances of one
+/// mesh.
+#[derive(Resource)]
+pub struct VelocityArrowAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<VelocityArrowMaterial>,
+}
+
+/// Parent of all the velocity arrows, hidden along with them.
+#[derive(Component)]
+pub struct VelocityArrows;
+
+/// Arrow showing the velocity of the plate over a cell.
+#[derive(Component)]
+pub struct VelocityArrow {
+    pub cell: usize,
+}
+
+/// Line mesh holding every streamline of mantle flow.
+#[derive(Component)]
+pub struct Streamlines;
+
+pub fn setup_velocity_field(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut arrow_materials: ResMut<Assets<VelocityArrowMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(VelocityArrowAssets {
+        mesh: meshes.add(arrow_mesh()),
+        material: arrow_materials.add(VelocityArrowMaterial {
+            colormap: images.add(Colormap::Viridis.image()),
+        }),
+    });
+    commands.spawn((VelocityArrows, Transform::default(), Visibility::Hidden));
+    commands.spawn((
+        Streamlines,
+        MeshMaterial3d(materials.add(StandardMaterial {
+            unlit: true,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+/// Flat arrow of unit length pointing along +Y, facing +Z.
+fn arrow_mesh() -> Mesh {
+    const SHAFT_WIDTH: f32 = 0.04;
+    const HEAD_WIDTH: f32 = 0.15;
+    const HEAD_START: f32 = 0.6;
+
+    let positions = vec![
+        [-SHAFT_WIDTH, 0.0, 0.0],
+        [SHAFT_WIDTH, 0.0, 0.0],
+        [SHAFT_WIDTH, HEAD_START, 0.0],
+        [-SHAFT_WIDTH, HEAD_START, 0.0],
+        [-HEAD_WIDTH, HEAD_START, 0.0],
+        [HEAD_WIDTH, HEAD_START, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U16(vec![0, 1, 2, 0, 2, 3, 4, 5, 6]))
+}
+
+/// Points spread evenly over the unit sphere along a Fibonacci spiral.
+fn fibonacci_sphere(count: usize) -> impl Iterator<Item = Vec3> {
+    let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+    (0..count).map(move |i| {
+        let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+        let ring = (1.0 - y * y).sqrt();
+        let (sin, cos) = (golden_angle * i as f32).sin_cos();
+        Vec3::new(ring * cos, y, ring * sin)
+    })
+}
+
+/// Angular spacing between `count` points spread evenly over the sphere.
+fn spacing(count: usize) -> f32 {
+    (4.0 * PI / count.max(1) as f32).sqrt()
+}
+
+/// Replaces the arrows with `VelocityField::arrow_count` new ones over the cells nearest to
+/// evenly spread points.
+pub fn spawn_velocity_arrows(
+    mut commands: Commands,
+    field: Res<VelocityField>,
+    assets: Res<VelocityArrowAssets>,
+    grid: Res<MantleGrid>,
+    locator: Res<CellLocator>,
+    roots: Query<Entity, With<VelocityArrows>>,
+) {
+    for root in &roots {
+        commands
+            .entity(root)
+            .despawn_related::<Children>()
+            .with_children(|arrows| {
+                for point in fibonacci_sphere(field.arrow_count) {
+                    arrows.spawn((
+                        VelocityArrow {
+                            cell: locator.locate(&grid, point),
+                        },
+                        Mesh3d(assets.mesh.clone()),
+                        MeshMaterial3d(assets.material.clone()),
+                        Transform::from_scale(Vec3::ZERO),
+                        MeshTag(0),
+                    ));
+                }
+            });
+    }
+}
+
+/// Lays each arrow along the surface velocity of the plate beneath it, scaled and coloured by
+/// its speed relative to the fastest arrow.
+pub fn update_velocity_arrows(
+    grid: Res<MantleGrid>,
+    plates: Res<Plates>,
+    field: Res<VelocityField>,
+    mut arrows: Query<(&VelocityArrow, &mut Transform, &mut MeshTag)>,
+    added: Query<(), Added<VelocityArrow>>,
+) {
+    if !grid.is_changed() && !plates.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let velocity = |cell: usize| {
+        let center = grid.cells[cell].center;
+        plates
+            .0
+            .get(grid.cells[cell].plate)
+            .map_or(Vec3::ZERO, |plate| plate.surface_velocity(center))
+    };
+    let max_speed = arrows
+        .iter()
+        .map(|(arrow, ..)| velocity(arrow.cell).length())
+        .fold(0.0, f32::max);
+    let spacing = spacing(field.arrow_count);
+
+    for (arrow, mut transform, mut tag) in &mut arrows {
+        let data = &grid.cells[arrow.cell];
+        let velocity = velocity(arrow.cell);
+        let speed = velocity.length();
+        let forward = velocity.normalize_or_zero();
+        let length = (speed / REFERENCE_PLATE_SPEED).min(MAX_ARROW_LENGTH) * spacing;
+
+        let updated = Transform {
+            translation: data.center * surface_radius(data.elevation),
+            rotation: Quat::from_mat3(&Mat3::from_cols(
+                forward.cross(data.center),
+                forward,
+                data.center,
+            )),
+            scale: if forward == Vec3::ZERO {
+                Vec3::ZERO
+            } else {
+                Vec3::splat(length)
+            },
+        };
+        transform.set_if_neq(updated);
+        let relative = if max_speed > 0.0 {
+            speed / max_speed
+        } else {
+            0.0
+        };
+        tag.set_if_neq(MeshTag((relative * ARROW_TAG_SCALE as f32) as u32));
+    }
+}
+
+/// Shows or hides the velocity overlays as `Overlays` asks.
+pub fn show_velocity_overlays(
+    overlays: Res<Overlays>,
+    mut arrows: Query<&mut Visibility, (With<VelocityArrows>, Without<Streamlines>)>,
+    mut streamlines: Query<(&mut Visibility, Has<Mesh3d>), With<Streamlines>>,
+) {
+    let shown = |shown: bool| {
+        if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+    for mut visibility in &mut arrows {
+        visibility.set_if_neq(shown(overlays.velocities));
+    }
+    // The line mesh only exists once some streamline has been traced.
+    for (mut visibility, traced) in &mut streamlines {
+        visibility.set_if_neq(shown(overlays.streamlines && traced));
+    }
+}
+
+/// Traces streamlines of mantle flow from evenly spread seeds into a single line mesh, coloured
+/// by the flow's strength relative to the strongest. The flow is the one dragging the plates,
+/// read from `CellData::flux` through `mantle_velocity`. While the flow keeps changing, the mesh is
+/// rebuilt at most every `STREAMLINE_INTERVAL` seconds.
+#[allow(clippy::too_many_arguments)]
+pub fn update_streamlines(
+    mut commands: Commands,
+    time: Res<Time>,
+    grid: Res<MantleGrid>,
+    locator: Res<CellLocator>,
+    field: Res<VelocityField>,
+    overlays: Res<Overlays>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    streamlines: Query<(Entity, Option<&Mesh3d>), With<Streamlines>>,
+    mut dirty: Local<bool>,
+    mut last_rebuild: Local<Option<f32>>,
+) -> Result {
+    *dirty |= grid.is_changed() || field.is_changed() || overlays.is_changed();
+    let now = time.elapsed_secs();
+    if !*dirty || last_rebuild.is_some_and(|last| now - last < STREAMLINE_INTERVAL) {
+        return Ok(());
+    }
+    *dirty = false;
+    *last_rebuild = Some(now);
+
+    let flow: Vec<Vec3> = (0..grid.cells.len())
+        .map(|cell| mantle_velocity(&grid, cell))
+        .collect();
+    let max_flow = flow.iter().map(|flow| flow.length()).fold(0.0, f32::max);
+    if max_flow == 0.0 {
+        return Ok(());
+    }
+    let step = spacing(field.streamline_count) * STREAMLINE_STEP_LENGTH;
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    for seed in fibonacci_sphere(field.streamline_count) {
+        let mut point = seed;
+        let mut cell = locator.locate(&grid, point);
+        for _ in 0..STREAMLINE_STEPS {
+            let strength = flow[cell].length() / max_flow;
+            if strength < 1.0e-3 {
+                break;
+            }
+            let next = (point + flow[cell].normalize() * step).normalize();
+            let next_cell = locator.locate(&grid, next);
+            let color = Colormap::Viridis.color(strength);
+            let color = LinearRgba::from(color).to_f32_array();
+            positions.push(point * surface_radius(grid.cells[cell].elevation));
+            positions.push(next * surface_radius(grid.cells[next_cell].elevation));
+            colors.extend([color, color]);
+            point = next;
+            cell = next_cell;
+        }
+    }
+    if positions.is_empty() {
+        return Ok(());
+    }
+
+    let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    for (entity, current) in &streamlines {
+        match current {
+            Some(current) => {
+                meshes.insert(&current.0, mesh.clone())?;
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(Mesh3d(meshes.add(mesh.clone())));
+            }
+        }
+    }
+    Ok(())
+}
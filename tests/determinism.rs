@@ -0,0 +1,43 @@
+//! Runs from the same seed and configuration must produce bit-identical CPU results.
+
+use bevy::prelude::*;
+use tectonic_plate_simulator::{
+    plugins::simulation::SimulationPlugin,
+    resources::{
+        mantle_grid::MantleGrid, simulation_clock::SimulationClock,
+        simulation_config::SimulationConfig,
+    },
+    systems::setup::setup_simulation,
+};
+
+const STEPS: u64 = 10;
+
+/// Checksum of the grid after `STEPS` steps of a fresh headless run.
+fn run(config: &SimulationConfig) -> u64 {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SimulationPlugin)
+        .insert_resource(config.clone())
+        .add_systems(Startup, setup_simulation);
+    while app.world().resource::<SimulationClock>().step < STEPS {
+        app.update();
+    }
+    app.world().resource::<MantleGrid>().checksum()
+}
+
+fn config(seed: u64) -> SimulationConfig {
+    let mut config = SimulationConfig::default();
+    config.scenario.seed = seed;
+    config.scenario.subdivisions = 8;
+    config
+}
+
+#[test]
+fn same_seed_gives_identical_grids() {
+    assert_eq!(run(&config(7)), run(&config(7)));
+}
+
+#[test]
+fn different_seeds_give_different_grids() {
+    assert_ne!(run(&config(7)), run(&config(8)));
+}
@@ -0,0 +1,44 @@
+//! Runs that cannot start from the state they ask for must end with an error.
+
+use bevy::prelude::*;
+use tectonic_plate_simulator::{
+    plugins::simulation::SimulationPlugin, resources::simulation_config::SimulationConfig,
+    simulation::initial_conditions::InitialCondition, systems::setup::setup_simulation,
+};
+
+fn exit_after_setup(config: SimulationConfig) -> Option<AppExit> {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SimulationPlugin)
+        .insert_resource(config)
+        .add_systems(Startup, setup_simulation);
+    app.update();
+    app.should_exit()
+}
+
+#[test]
+fn valid_configuration_keeps_running() {
+    let mut config = SimulationConfig::default();
+    config.scenario.subdivisions = 4;
+    assert_eq!(exit_after_setup(config), None);
+}
+
+#[test]
+fn invalid_initial_conditions_end_the_run() {
+    let mut config = SimulationConfig::default();
+    config.scenario.subdivisions = 4;
+    config.initial_conditions.pressure = InitialCondition::Random {
+        seed: 0,
+        min: 10.0,
+        max: -10.0,
+    };
+    assert!(exit_after_setup(config).is_some_and(|exit| exit.is_error()));
+}
+
+#[test]
+fn missing_import_ends_the_run() {
+    let mut config = SimulationConfig::default();
+    config.scenario.subdivisions = 4;
+    config.import.hotspots = Some("does/not/exist.zarr".into());
+    assert!(exit_after_setup(config).is_some_and(|exit| exit.is_error()));
+}